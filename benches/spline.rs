@@ -0,0 +1,53 @@
+//! Benchmarks of spline lookups, and of lofting and flattening the example
+//! hull at a high resolution, where they add up.
+//!
+//! Run with `cargo bench`.
+
+#[macro_use]
+extern crate criterion;
+extern crate lapstrake;
+extern crate scad_dots;
+
+use std::path::Path;
+
+use criterion::Criterion;
+use lapstrake::{Spec, Spline};
+use scad_dots::utils::P3;
+
+// A long wavy spline, sampled finely.
+fn wavy_spline(resolution: usize) -> Spline {
+    let points = (0..40)
+        .map(|i| {
+            let x = i as f32;
+            P3::new(x, x.sin(), (x / 3.).cos())
+        }).collect();
+    Spline::new(points, resolution).expect("failed to make spline")
+}
+
+fn bench_at_t(c: &mut Criterion) {
+    let spline = wavy_spline(100);
+    c.bench_function("spline at_t, 4000 samples", move |b| {
+        b.iter(|| {
+            for i in 0..1000 {
+                spline.at_t(i as f32 / 1000.).expect("failed at_t");
+            }
+        })
+    });
+}
+
+fn bench_flatten(c: &mut Criterion) {
+    let mut spec =
+        Spec::load_from(Path::new("input")).expect("failed to load spec");
+    spec.config.resolution = 50;
+    let hull = spec.get_hull().expect("failed to loft hull");
+    c.bench_function("flatten example planks, resolution 50", move |b| {
+        b.iter(|| hull.get_flattened_planks().expect("failed to flatten"))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_at_t, bench_flatten
+}
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+//! Bundle all of the outputs of a run into a single zip archive.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use error::{LapstrakeError, ResultExt};
+
+/// Write the given files into a zip archive, along with a manifest
+/// listing them. `description` goes at the top of the manifest, and
+/// should say what the files are.
+pub fn write_archive(
+    archive: &Path,
+    files: &[PathBuf],
+    description: &str,
+) -> Result<(), LapstrakeError> {
+    println!("Saving archive: {:?}.", archive);
+    write_archive_helper(archive, files, description)
+        .with_context(|| format!("Failed to write archive {:?}", archive))
+}
+
+fn write_archive_helper(
+    archive: &Path,
+    files: &[PathBuf],
+    description: &str,
+) -> Result<(), LapstrakeError> {
+    let mut zip = ZipWriter::new(fs::File::create(archive)?);
+    let mut manifest = format!(
+        "lapstrake {}\n{}\n\nFiles:\n",
+        env!("CARGO_PKG_VERSION"),
+        description
+    );
+
+    for file in files {
+        let contents = fs::read(file)
+            .with_context(|| format!("Could not read output {:?}", file))?;
+        let name = match file.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => {
+                return Err(LapstrakeError::General(format!(
+                    "Not a file: {:?}",
+                    file
+                )))
+            }
+        };
+        zip.start_file(name.clone(), FileOptions::default())?;
+        zip.write_all(&contents)?;
+        manifest.push_str(&format!("  {} ({} bytes)", name, contents.len()));
+        let thumbnail = file.with_extension("png");
+        let is_png = file.extension() == Some(OsStr::new("png"));
+        if !is_png && files.contains(&thumbnail) {
+            manifest.push_str(&format!(
+                ", thumbnail: {}",
+                thumbnail.file_name().unwrap().to_string_lossy()
+            ));
+        }
+        manifest.push('\n');
+    }
+
+    zip.start_file("manifest.txt", FileOptions::default())?;
+    zip.write_all(manifest.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! The backbone: the timbers along the centerline that the planks are
+//! fastened to. The stem, hog, and sternpost follow the rabbet, inside of
+//! the planking, and are scarfed together end to end. The keel is
+//! fastened on below the hog, outside of the planking.
+
+use scad_dots::utils::{Axis, P2, V2};
+
+use draw::{Drawing, Layer};
+use error::LapstrakeError;
+use hull::Hull;
+use render_2d::{
+    Align, Baseline, Bounded, PathStyle2, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use spec::{POST, STEM};
+use util::{
+    interpolate_y, offset_polyline, point_along, polyline_length,
+    practically_zero, project_points, trim_polyline,
+};
+
+/// How many times longer a scarf is than the depth it's cut across.
+const SCARF_RATIO: f32 = 8.;
+
+// One timber of the backbone, in profile.
+struct Timber {
+    name: &'static str,
+    // Its outline, as (fore-aft, height) points.
+    outline: Vec<P2>,
+    // The lines to cut its scarfs along, at its ends, and across it at
+    // the ends of the trunk's slot.
+    marks: Vec<Vec<P2>>,
+    siding: f32,
+    moulding: f32,
+}
+
+impl Hull {
+    /// Draw templates for the timbers of the backbone in profile: the
+    /// stem, hog, and sternpost, moulded inwards from the rabbet, and the
+    /// keel, moulded down from the keel line. Each scarf is marked on both
+    /// of the timbers that it joins, and is cut across the shallower of
+    /// them.
+    pub fn draw_backbone(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.1;
+        let palette = self.theme.palette();
+        // Flip heights, so that the templates are right side up.
+        let flip = |points: &[P2]| -> Vec<P2> {
+            points.iter().map(|p| P2::new(p.x, -p.y)).collect()
+        };
+
+        let mut templates = vec![];
+        for timber in self.backbone_timbers()? {
+            let path = SvgPath::new(flip(&timber.outline))
+                .stroke(palette.outline, STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let bound = path.bound().expect("timber has no bound");
+            let mut marks = SvgGroup::new();
+            for mark in &timber.marks {
+                marks.append(
+                    SvgPath::new(flip(mark))
+                        .stroke(palette.grid, STROKE / 2.)
+                        .style(PathStyle2::Dashed),
+                );
+            }
+            let label = SvgText {
+                lines: vec![
+                    timber.name.to_string(),
+                    format!(
+                        "Sided {}, moulded {}",
+                        self.units.from_feet(timber.siding),
+                        self.units.from_feet(timber.moulding)
+                    ),
+                ],
+                pos: bound.relative_pos(1., 0.5) + V2::new(FONT_SIZE, 0.),
+                color: palette.label,
+                size: FONT_SIZE,
+                style: TextStyle::default(),
+            }.anchored(Align::Start, Baseline::Central);
+
+            let mut group = SvgGroup::new();
+            group.append(Layer::Stations.wrap(path));
+            group.append(marks.layer(Layer::Marks.name()));
+            group.append(Layer::Labels.wrap(label));
+            templates.push(group);
+        }
+
+        let mut doc = SvgDoc::new();
+        let parts =
+            vec![self.scale_bar()?, SvgGroup::new_vertical(templates, 1.1)?];
+        doc.append(SvgGroup::new_vertical(parts, 1.1)?);
+        self.run_drawing_passes(Drawing::Backbone, doc)
+    }
+
+    // The timbers of the backbone, from fore to aft, with the keel last.
+    // The stem and sternpost are left out if there are no stations for
+    // them.
+    fn backbone_timbers(&self) -> Result<Vec<Timber>, LapstrakeError> {
+        let backbone = self.backbone;
+        let mut keel =
+            project_points(Axis::Y, &self.get_line(0.)?.sample(None)?);
+        if keel[0].x > keel[keel.len() - 1].x {
+            keel.reverse();
+        }
+
+        // The rabbet along the whole centerline, from the head of the stem,
+        // along the keel line, and up to the head of the sternpost.
+        let mut rabbet = vec![];
+        // Each timber's name, siding, moulding, and how far along the
+        // rabbet it ends.
+        let mut spans = vec![];
+        let stem_siding = self.stem.map_or(backbone.keel_siding, |s| s.siding);
+        if let (Ok(station), Some(size)) = (self.get_station(STEM), self.stem)
+        {
+            let mut profile =
+                project_points(Axis::Y, &station.spline.sample(None)?);
+            profile.reverse();
+            rabbet.extend(profile);
+            let length = polyline_length(&rabbet);
+            spans.push(("Stem", stem_siding, size.moulding, length));
+        }
+        let keel_start = polyline_length(&rabbet);
+        let skip = if rabbet.is_empty() { 0 } else { 1 };
+        rabbet.extend(keel.into_iter().skip(skip));
+        let keel_end = polyline_length(&rabbet);
+        spans.push((
+            "Hog",
+            backbone.hog_siding,
+            backbone.hog_moulding,
+            keel_end,
+        ));
+        if let Ok(station) = self.get_station(POST) {
+            let profile =
+                project_points(Axis::Y, &station.spline.sample(None)?);
+            rabbet.extend(profile.into_iter().skip(1));
+            let length = polyline_length(&rabbet);
+            let moulding = backbone.post_moulding;
+            spans.push(("Sternpost", stem_siding, moulding, length));
+        }
+
+        // Each scarf reaches half of its length past the joint, either
+        // way.
+        let mut reaches = vec![];
+        let mut scarfs = vec![];
+        for pair in spans.windows(2) {
+            let joint = pair[0].3;
+            let depth = pair[0].2.min(pair[1].2);
+            let reach = SCARF_RATIO * depth / 2.;
+            reaches.push(reach);
+            scarfs.push(vec![
+                point_along(&rabbet, joint - reach),
+                inside_point(&rabbet, joint + reach, depth),
+            ]);
+        }
+        let mut timbers = vec![];
+        let mut start = 0.;
+        let keel_line = trim_polyline(&rabbet, keel_start, keel_end);
+        for (i, &(name, siding, moulding, end)) in spans.iter().enumerate() {
+            let mut marks = vec![];
+            let (mut from, mut to) = (start, end);
+            if i > 0 {
+                from -= reaches[i - 1];
+                marks.push(scarfs[i - 1].clone());
+            }
+            if i + 1 < spans.len() {
+                to += reaches[i];
+                marks.push(scarfs[i].clone());
+            }
+            if name == "Hog" {
+                marks.extend(self.trunk_marks(&keel_line, moulding));
+            }
+            let outer = trim_polyline(&rabbet, from, to);
+            let mut outline = outer.clone();
+            outline
+                .extend(offset_polyline(&outer, moulding).into_iter().rev());
+            timbers.push(Timber {
+                name: name,
+                outline: outline,
+                marks: marks,
+                siding: siding,
+                moulding: moulding,
+            });
+            start = end;
+        }
+
+        // The keel runs the length of the keel line, under the hog.
+        let moulding = backbone.keel_moulding;
+        let mut outline = keel_line.clone();
+        outline.extend(
+            offset_polyline(&keel_line, -moulding).into_iter().rev(),
+        );
+        timbers.push(Timber {
+            name: "Keel",
+            outline: outline,
+            marks: self.trunk_marks(&keel_line, -moulding),
+            siding: backbone.keel_siding,
+            moulding: backbone.keel_moulding,
+        });
+        Ok(timbers)
+    }
+
+    // Lines across a timber that's `moulding` deep above the keel line
+    // (or below it, if negative), at the ends of the trunk's slot.
+    fn trunk_marks(&self, keel_line: &[P2], moulding: f32) -> Vec<Vec<P2>> {
+        let trunk = match self.trunk {
+            Some(trunk) => trunk,
+            None => return vec![],
+        };
+        let mut marks = vec![];
+        for &x in &[trunk.fore, trunk.aft] {
+            match interpolate_y(keel_line, x) {
+                Some(y) => {
+                    marks.push(vec![P2::new(x, y), P2::new(x, y + moulding)])
+                }
+                None => println!(
+                    "Warning: the trunk's slot ends at {}, past the keel.",
+                    x
+                ),
+            }
+        }
+        marks
+    }
+}
+
+// The point `depth` inside of the rabbet, square to it, at `distance`
+// along it. The inside is to the left, going from fore to aft.
+fn inside_point(rabbet: &[P2], distance: f32, depth: f32) -> P2 {
+    let point = point_along(rabbet, distance);
+    let tangent = point_along(rabbet, distance + depth)
+        - point_along(rabbet, distance - depth);
+    if practically_zero(tangent.norm()) {
+        return point;
+    }
+    point + V2::new(-tangent.y, tangent.x) * (depth / tangent.norm())
+}
@@ -0,0 +1,149 @@
+//! External ballast keels: their volume, weight, and casting patterns.
+
+use scad_dots::utils::{P2, V2};
+
+use draw::Drawing;
+use error::LapstrakeError;
+use hull::Hull;
+use render_2d::{
+    Bounded, PathStyle2, SvgColor, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use spec::{Ballast, BallastSection};
+use util::interpolate_y;
+
+/// The density of lead, in pounds per cubic foot.
+pub const LEAD_DENSITY: f32 = 708.;
+
+impl Ballast {
+    /// The volume of the ballast keel, in cubic feet. The ballast is
+    /// assumed to change linearly between sections.
+    pub fn volume(&self) -> f32 {
+        let mut volume = 0.;
+        for pair in self.sections.windows(2) {
+            let length = pair[1].x() - pair[0].x();
+            volume += length * (pair[0].area() + pair[1].area()) / 2.;
+        }
+        volume.abs()
+    }
+
+    /// The weight of the ballast keel, given the density of its material
+    /// in pounds per cubic foot.
+    pub fn weight(&self, density: f32) -> f32 {
+        self.volume() * density
+    }
+}
+
+impl BallastSection {
+    fn x(&self) -> f32 {
+        self.position.into()
+    }
+
+    /// The area of this cross-section, in square feet.
+    fn area(&self) -> f32 {
+        let depth: f32 = self.depth.into();
+        let top: f32 = self.top_width.into();
+        let bottom: f32 = self.bottom_width.into();
+        depth * (top + bottom) / 2.
+    }
+
+    // The trapezoidal outline of this cross-section, with the top
+    // centered on the origin.
+    fn outline(&self) -> Vec<P2> {
+        let depth: f32 = self.depth.into();
+        let top: f32 = self.top_width.into();
+        let bottom: f32 = self.bottom_width.into();
+        vec![
+            P2::new(-top / 2., 0.),
+            P2::new(top / 2., 0.),
+            P2::new(bottom / 2., depth),
+            P2::new(-bottom / 2., depth),
+        ]
+    }
+}
+
+impl Hull {
+    /// Draw patterns for casting or laminating the ballast keel: the
+    /// profile, whose top follows the hull's keel line, and the
+    /// cross-section at each of the ballast's sections.
+    pub fn draw_ballast(
+        &self,
+        ballast: &Ballast,
+        density: f32,
+    ) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.25;
+
+        let keel = self.keel_line()?;
+        let mut top = vec![];
+        let mut bottom = vec![];
+        for section in &ballast.sections {
+            let x = section.x();
+            let keel_z = interpolate_y(&keel, x).ok_or_else(|| {
+                LapstrakeError::Draw.context(&format!(
+                    "Ballast section at {} is outside of the hull's keel.",
+                    section.position
+                ))
+            })?;
+            let depth: f32 = section.depth.into();
+            // Flip z, so that the profile is right side up.
+            top.push(P2::new(x, -keel_z));
+            bottom.push(P2::new(x, -keel_z + depth));
+        }
+        let mut outline = top.clone();
+        outline.extend(bottom.into_iter().rev());
+        let profile_path = SvgPath::new(outline)
+            .stroke(SvgColor::Black, STROKE)
+            .style(PathStyle2::Line)
+            .close();
+        let profile_bound = profile_path.bound().unwrap();
+        let summary = SvgText {
+            lines: vec![
+                format!(
+                    "Ballast volume: {}",
+                    ballast.units.describe_volume(ballast.volume())
+                ),
+                format!(
+                    "Weight: {:.0} lb at {} lb per cubic foot",
+                    ballast.weight(density),
+                    density
+                ),
+            ],
+            pos: profile_bound.relative_pos(0.5, 1.) + V2::new(0., FONT_SIZE),
+            color: SvgColor::Black,
+            size: FONT_SIZE,
+            style: TextStyle::default(),
+        };
+        let mut profile = SvgGroup::new();
+        profile.append(profile_path);
+        profile.append(summary);
+
+        let mut sections = vec![];
+        for section in &ballast.sections {
+            let path = SvgPath::new(section.outline())
+                .stroke(SvgColor::Black, STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let label = SvgText {
+                lines: vec![format!("{}", section.position)],
+                pos: path.bound().unwrap().center(),
+                color: SvgColor::Black,
+                size: FONT_SIZE,
+                style: TextStyle::default(),
+            };
+            let mut group = SvgGroup::new();
+            group.append(path);
+            group.append(label);
+            sections.push(group);
+        }
+
+        let sections = SvgGroup::new_horizontal(sections, 0.5)?;
+        let stack = SvgGroup::new_vertical(
+            vec![self.scale_bar()?, profile, sections],
+            1.,
+        )?;
+        let mut doc = SvgDoc::new();
+        doc.append(stack);
+        self.run_drawing_passes(Drawing::Ballast, doc)
+    }
+}
@@ -0,0 +1,93 @@
+//! Compute interpolating cubic B-splines.
+//!
+//! The B-spline that passes through every given point, with zero
+//! curvature at its ends, is the same curve as the natural cubic spline
+//! through them. That's how it's found here: by solving for the second
+//! derivative at each point, with the points spaced by chord length.
+
+use scad_dots::utils::{distance, P3, V3};
+
+/// Sample `resolution` points along each span between neighboring
+/// points, plus the last point, so that there are
+/// `(points.len() - 1) * resolution + 1` samples in all.
+pub fn sample(points: &[P3], resolution: usize) -> Vec<P3> {
+    let n = points.len();
+    let mut knots = vec![0.];
+    for pair in points.windows(2) {
+        let last = knots[knots.len() - 1];
+        knots.push(last + distance(&pair[0], &pair[1]));
+    }
+    let moments = second_derivatives(points, &knots);
+
+    let mut samples = vec![];
+    for i in 0..n - 1 {
+        let h = knots[i + 1] - knots[i];
+        for k in 0..resolution {
+            let f = k as f32 / resolution as f32;
+            samples.push(evaluate(points, &moments, i, h, f));
+        }
+    }
+    samples.push(points[n - 1]);
+    samples
+}
+
+// The second derivative of the curve at each point, with respect to the
+// knots. The ends have none.
+fn second_derivatives(points: &[P3], knots: &[f32]) -> Vec<V3> {
+    let n = points.len();
+    let mut moments = vec![V3::zeros(); n];
+    if n < 3 {
+        return moments;
+    }
+    // Solve the tridiagonal system for the interior points, with the
+    // Thomas algorithm.
+    let h: Vec<f32> = knots.windows(2).map(|k| k[1] - k[0]).collect();
+    let mut diagonal = vec![0.; n];
+    let mut rhs = vec![V3::zeros(); n];
+    for i in 1..n - 1 {
+        diagonal[i] = 2. * (h[i - 1] + h[i]);
+        rhs[i] = 6.
+            * ((points[i + 1] - points[i]) / h[i]
+                - (points[i] - points[i - 1]) / h[i - 1]);
+    }
+    for i in 2..n - 1 {
+        let factor = h[i - 1] / diagonal[i - 1];
+        diagonal[i] -= factor * h[i - 1];
+        rhs[i] = rhs[i] - rhs[i - 1] * factor;
+    }
+    for i in (1..n - 1).rev() {
+        let next = moments[i + 1];
+        moments[i] = (rhs[i] - next * h[i]) / diagonal[i];
+    }
+    moments
+}
+
+// The point a fraction `f` of the way along the span from point `i`,
+// which is `h` long.
+fn evaluate(points: &[P3], moments: &[V3], i: usize, h: f32, f: f32) -> P3 {
+    let g = 1. - f;
+    let linear = points[i].coords * g + points[i + 1].coords * f;
+    let bend = (moments[i] * (g * g * g - g) + moments[i + 1] * (f * f * f - f))
+        * (h * h / 6.);
+    P3::from_coordinates(linear + bend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bspline_interpolates() {
+        let points = vec![
+            P3::new(0., 0., 0.),
+            P3::new(1., 2., 0.),
+            P3::new(3., 1., 1.),
+            P3::new(4., 3., 2.),
+        ];
+        let samples = sample(&points, 5);
+        assert_eq!(samples.len(), 16);
+        for (i, point) in points.iter().enumerate() {
+            assert!(distance(point, &samples[5 * i]) < 0.0001);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Cache lofted hulls on disk, so that they don't have to be recomputed
+//! on every run when the spec sheets haven't changed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json;
+
+use error::{LapstrakeError, ResultExt};
+use hull::Hull;
+use load::SpecSource;
+use spec::Spec;
+
+/// The version of the cached hulls' format, and of the lofting that made
+/// them. Bump this whenever `Hull`'s fields or the way it's lofted
+/// change, so that old cached hulls are lofted again instead of reused.
+const CACHE_VERSION: u32 = 1;
+
+/// A folder of cached hulls, keyed by a hash of the spec sheets they
+/// were lofted from.
+pub struct HullCache {
+    directory: PathBuf,
+}
+
+impl HullCache {
+    pub fn new(directory: &Path) -> HullCache {
+        HullCache {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    /// Load the spec sheets and loft the hull, or reuse a cached hull if
+    /// the sheets haven't changed since it was saved. Sheets read from
+    /// stdin can't be hashed ahead of time, so they are never cached.
+    pub fn load(
+        &self,
+        data: &SpecSource,
+        planks: &SpecSource,
+        config: &SpecSource,
+    ) -> Result<Hull, LapstrakeError> {
+        let key = match HullCache::key(&[data, planks, config])? {
+            Some(key) => key,
+            None => {
+                return Spec::load_from_sources(data, planks, config)?
+                    .get_hull()
+            }
+        };
+
+        let path = self.directory.join(format!("hull-{:016x}.json", key));
+        if path.exists() {
+            match HullCache::read(&path) {
+                Ok(hull) => {
+                    println!("Using cached hull: {:?}.", path);
+                    return Ok(hull);
+                }
+                Err(err) => println!("Ignoring bad cache file: {}", err),
+            }
+        }
+
+        let hull = Spec::load_from_sources(data, planks, config)?.get_hull()?;
+        // A failure to cache shouldn't stop anything else from working.
+        if let Err(err) = self.write(&path, &hull) {
+            println!("Failed to cache hull: {}", err);
+        }
+        Ok(hull)
+    }
+
+    // Hash the contents of the spec sheets, along with the version of the
+    // cache. The hash has to come out the same from one build to the
+    // next, so it's done by hand instead of with std's hasher.
+    fn key(sources: &[&SpecSource]) -> Result<Option<u64>, LapstrakeError> {
+        let mut hash = fnv_hash(FNV_OFFSET, &CACHE_VERSION.to_le_bytes());
+        for &source in sources {
+            match *source {
+                SpecSource::Stdin => return Ok(None),
+                SpecSource::File(ref path) => {
+                    let contents = fs::read(path).with_context(|| {
+                        format!("Could not read spec file: {:?}", path)
+                    })?;
+                    // Include the length, so that moving bytes from one
+                    // sheet to the next changes the hash.
+                    let length = contents.len() as u64;
+                    hash = fnv_hash(hash, &length.to_le_bytes());
+                    hash = fnv_hash(hash, &contents);
+                }
+            }
+        }
+        Ok(Some(hash))
+    }
+
+    fn read(path: &Path) -> Result<Hull, LapstrakeError> {
+        let file = fs::File::open(path)?;
+        serde_json::from_reader(file)
+            .map_err(LapstrakeError::from)
+            .with_context(|| format!("Could not read cache file: {:?}", path))
+    }
+
+    fn write(&self, path: &Path, hull: &Hull) -> Result<(), LapstrakeError> {
+        fs::create_dir_all(&self.directory)?;
+        let file = fs::File::create(path)?;
+        serde_json::to_writer(file, hull)?;
+        Ok(())
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// Continue a 64-bit FNV-1a hash over more bytes.
+fn fnv_hash(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv_hash() {
+        // The published test vectors, which the cache keys depend on
+        // staying the same.
+        assert_eq!(fnv_hash(FNV_OFFSET, b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv_hash(FNV_OFFSET, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv_hash(FNV_OFFSET, b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
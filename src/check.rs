@@ -0,0 +1,165 @@
+//! Consistency checks on the spec sheets. Unlike loading, which stops at
+//! the first problem, these report everything that's wrong at once.
+
+use std::fmt;
+
+use spec::{HeightLine, PlankStation, Spec, STEM};
+use spline::MIN_SPLINE_POINTS;
+
+/// Something wrong with one of the spec sheets.
+#[derive(Debug, Clone)]
+pub struct SpecProblem {
+    /// Which sheet the problem is in, like "data.csv".
+    pub sheet: &'static str,
+    pub message: String,
+}
+
+impl Spec {
+    /// Check that the spec sheets agree with each other and make sense,
+    /// and return every problem found.
+    pub fn check(&self) -> Vec<SpecProblem> {
+        let mut problems = vec![];
+        self.check_data(&mut problems);
+        self.check_plank_sheet(&mut problems);
+        problems
+    }
+
+    // Every station needs a sheer, and the stations must be in order
+    // from bow to stern.
+    fn check_data(&self, problems: &mut Vec<SpecProblem>) {
+        let mut problem = |message: String| {
+            problems.push(SpecProblem {
+                sheet: "data.csv",
+                message: message,
+            })
+        };
+        let rabbet = match self.data.stem_rabbet() {
+            Ok(rabbet) => rabbet,
+            Err(error) => {
+                problem(error.to_string());
+                vec![]
+            }
+        };
+        if !rabbet.is_empty() && rabbet.len() < MIN_SPLINE_POINTS {
+            problem(format!(
+                "The stem's profile needs at least {} points, but it has {}.",
+                MIN_SPLINE_POINTS,
+                rabbet.len()
+            ));
+        }
+        let mut previous: Option<(&String, f32)> = None;
+        for (i, name) in self.data.stations.iter().enumerate() {
+            // A stem that has a profile is lofted along it instead, with
+            // its sheer at its highest point.
+            if name == STEM && !rabbet.is_empty() {
+                previous = Some((name, rabbet[rabbet.len() - 1].0));
+                continue;
+            }
+            if self.get_sheer_breadth(i).is_err() {
+                problem(format!("Station {} has no sheer breadth.", name));
+            }
+            if self.get_sheer_height(i).is_err() {
+                problem(format!("Station {} has no sheer height.", name));
+            }
+            let position: f32 = match self
+                .get_station_position(i, HeightLine::Sheer)
+            {
+                Ok(position) => position.into(),
+                Err(_) => {
+                    problem(format!(
+                        "Station {} has no sheer fore-aft position.",
+                        name
+                    ));
+                    continue;
+                }
+            };
+            if let Some((previous_name, previous_position)) = previous {
+                if position <= previous_position {
+                    problem(format!(
+                        "Station {} isn't aft of station {}.",
+                        name, previous_name
+                    ));
+                }
+            }
+            previous = Some((name, position));
+        }
+    }
+
+    // The planks sheet must name real stations, have a cell for every
+    // column, and give each plank's edges in order from the keel up.
+    fn check_plank_sheet(&self, problems: &mut Vec<SpecProblem>) {
+        let mut problem = |message: String| {
+            problems.push(SpecProblem {
+                sheet: "planks.csv",
+                message: message,
+            })
+        };
+        let planks = &self.planks;
+        for station in &planks.stations {
+            if let PlankStation::Station(ref name) = *station {
+                if !self.data.stations.contains(name) {
+                    problem(format!(
+                        "Station {} isn't in the data sheet.",
+                        name
+                    ));
+                }
+            }
+        }
+
+        let rows = &planks.plank_locations;
+        if rows.len() % 2 != 0 {
+            problem(format!(
+                "There are {} plank rows, but each plank needs two.",
+                rows.len()
+            ));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != planks.stations.len() {
+                problem(format!(
+                    "Row {} has {} cells, but there are {} stations.",
+                    i + 1,
+                    row.len(),
+                    planks.stations.len()
+                ));
+            }
+            for fraction in row.iter().filter_map(|&f| f) {
+                if fraction < 0. || fraction > 1. {
+                    problem(format!(
+                        "Row {} has {}, which isn't between 0 and 1.",
+                        i + 1,
+                        fraction
+                    ));
+                }
+            }
+        }
+
+        // The crossings of bottom edges are found by `check_planks()`,
+        // once there's a hull to measure them against. Top edges must be
+        // in order too.
+        for i in 1..rows.len() / 2 {
+            let lower = &rows[2 * i - 1];
+            let upper = &rows[2 * i + 1];
+            for (column, station) in planks.stations.iter().enumerate() {
+                let lower = lower.get(column).and_then(|&f| f);
+                let upper = upper.get(column).and_then(|&f| f);
+                if let (Some(lower), Some(upper)) = (lower, upper) {
+                    if upper < lower {
+                        problem(format!(
+                            "Plank {} has its top edge below plank {}'s \
+                             at station {}.",
+                            i + 1,
+                            i,
+                            station
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SpecProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.sheet, self.message)
+    }
+}
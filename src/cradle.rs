@@ -0,0 +1,91 @@
+//! Templates for cradles and boat stands that fit the finished hull.
+
+use scad_dots::utils::{Axis, P2};
+
+use draw::Drawing;
+use error::LapstrakeError;
+use hull::Hull;
+use render_2d::{
+    Bounded, PathStyle2, SvgColor, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use unit::Feet;
+use util::{offset_polyline, project_points, reflect2};
+
+impl Hull {
+    /// Draw a cradle template at each of the given fore-aft positions.
+    /// Each cradle follows the hull's cross-section from the keel up to a
+    /// fraction `extent` of the way to the sheer, standing off from the
+    /// hull by `padding` to leave room for padding material, and sits on
+    /// a flat base that is `base` below the lowest point of the hull.
+    pub fn draw_cradles(
+        &self,
+        positions: &[Feet],
+        padding: f32,
+        base: f32,
+        extent: f32,
+    ) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const SAMPLES: usize = 40;
+
+        if positions.is_empty() {
+            return Err(LapstrakeError::Draw
+                .context("No positions were given for the cradles"));
+        }
+
+        let mut groups = vec![];
+        for &posn in positions {
+            let station = self.hallucinate_station(posn.into())?;
+            let mut contact = vec![];
+            for i in 0..SAMPLES + 1 {
+                let t = extent * i as f32 / SAMPLES as f32;
+                contact.push(station.at_t(t)?);
+            }
+            // Flip z, so that the cradle is right side up.
+            let contact: Vec<P2> = project_points(Axis::X, &contact)
+                .into_iter()
+                .map(|p| P2::new(p.x, -p.y))
+                .collect();
+            let right = offset_polyline(&contact, padding);
+            let left = reflect2(Axis::X, &right);
+
+            let bottom = contact
+                .iter()
+                .map(|p| p.y)
+                .fold(::std::f32::MIN, f32::max)
+                + padding
+                + base;
+            let right_top = right[right.len() - 1];
+            let left_top = left[left.len() - 1];
+
+            // Go down the left side, across the base, and up the right.
+            let mut outline: Vec<P2> = left.into_iter().rev().collect();
+            outline.extend(right);
+            outline.push(P2::new(right_top.x, bottom));
+            outline.push(P2::new(left_top.x, bottom));
+
+            let path = SvgPath::new(outline)
+                .stroke(SvgColor::Black, STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let bound = path.bound().unwrap();
+            let label = SvgText {
+                lines: vec![format!("Cradle at {}", posn)],
+                pos: bound.relative_pos(0.5, 0.8),
+                color: SvgColor::Black,
+                size: 0.2 * base.max(padding),
+                style: TextStyle::default(),
+            };
+            let mut group = SvgGroup::new();
+            group.append(path);
+            group.append(label);
+            groups.push(group);
+        }
+
+        let mut doc = SvgDoc::new();
+        let row = SvgGroup::new_horizontal(groups, 1.)?;
+        let stack = SvgGroup::new_vertical(vec![self.scale_bar()?, row], 1.)?;
+        doc.append(stack);
+        self.run_drawing_passes(Drawing::Cradles, doc)
+    }
+}
@@ -0,0 +1,119 @@
+//! Porcupine plots of the curvature of the hull's lines, which show humps
+//! and hollows in the offsets before any wood is cut.
+
+use scad_dots::utils::Axis;
+
+use draw::Drawing;
+use error::LapstrakeError;
+use hull::Hull;
+use render_2d::{PathStyle2, SvgColor, SvgDoc, SvgGroup, SvgPath};
+use spline::{Curvature, Spline};
+use util::{practically_zero, project_points};
+
+const STROKE: f32 = 0.02;
+// The length of the longest quill in each view, in feet.
+const QUILL_LENGTH: f32 = 0.5;
+// Quills shorter than this fraction of the longest are too close to
+// straight to say which way their line bends.
+const STRAIGHT: f32 = 0.05;
+
+impl Hull {
+    /// Draw a porcupine plot of the curvature of each station in the
+    /// body plan, of the sheer and waterlines in plan view, and of the
+    /// sheer and buttocks in profile. Each line grows quills whose length
+    /// is proportional to its curvature, with their tips joined up. A
+    /// fair line has a smooth outline of quills. Quills that bend the
+    /// opposite way to most of their line, at a hump or hollow, are red.
+    pub fn draw_curvature(&self) -> Result<SvgDoc, LapstrakeError> {
+        let sheer = self.get_line(1.0)?;
+        let stations: Vec<Spline> = self
+            .stations
+            .iter()
+            .map(|station| station.spline.clone())
+            .collect();
+        // Skip the lines that cross too few stations to be splined.
+        let mut waterlines = vec![sheer.clone()];
+        waterlines.extend(
+            self.heights
+                .iter()
+                .filter_map(|&height| self.get_waterline(height).ok()),
+        );
+        let mut buttocks = vec![sheer];
+        buttocks.extend(
+            self.breadths
+                .iter()
+                .filter_map(|&breadth| self.get_buttock(breadth).ok()),
+        );
+
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![
+                self.scale_bar()?,
+                porcupine_view(&stations, Axis::X)?,
+                porcupine_view(&waterlines, Axis::Z)?,
+                porcupine_view(&buttocks, Axis::Y)?,
+            ],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::Curvature, doc)
+    }
+}
+
+// Draw the lines projected along the given axis, with their quills all
+// to the same scale.
+fn porcupine_view(
+    lines: &[Spline],
+    axis: Axis,
+) -> Result<SvgGroup, LapstrakeError> {
+    let profiles: Vec<Vec<Curvature>> = lines
+        .iter()
+        .map(|line| line.curvature_profile(axis))
+        .collect();
+    let max = profiles
+        .iter()
+        .flat_map(|profile| profile.iter())
+        .map(|c| c.curvature.abs())
+        .fold(0., f32::max);
+    let scale = if practically_zero(max) {
+        0.
+    } else {
+        QUILL_LENGTH / max
+    };
+
+    let mut group = SvgGroup::new();
+    for (line, profile) in lines.iter().zip(&profiles) {
+        group.append(
+            SvgPath::new(project_points(axis, &line.sample(None)?))
+                .stroke(SvgColor::Black, STROKE)
+                .style(PathStyle2::Line),
+        );
+        // Which way most of the line bends.
+        let overall: f32 = profile.iter().map(|c| c.curvature).sum();
+        let mut tips = vec![];
+        for c in profile {
+            // Quills stand out away from the center of curvature.
+            let tip = c.point - c.normal * c.curvature * scale;
+            let reversed = c.curvature * overall < 0.
+                && c.curvature.abs() > STRAIGHT * max;
+            let color = if reversed {
+                SvgColor::Red
+            } else {
+                SvgColor::LightGrey
+            };
+            group.append(
+                SvgPath::new(vec![c.point, tip])
+                    .stroke(color, STROKE / 2.)
+                    .style(PathStyle2::Line),
+            );
+            tips.push(tip);
+        }
+        if tips.len() >= 2 {
+            group.append(
+                SvgPath::new(tips)
+                    .stroke(SvgColor::Blue, STROKE / 2.)
+                    .style(PathStyle2::Line),
+            );
+        }
+    }
+    Ok(group)
+}
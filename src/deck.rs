@@ -0,0 +1,203 @@
+//! The deck: its outline in plan view, and the flat panels that it can
+//! be cut from.
+
+use std::iter;
+
+use scad_dots::utils::{P2, P3, V2};
+
+use draw::{Drawing, Layer};
+use error::LapstrakeError;
+use hull::Hull;
+use plank::{FlattenedPlank, Plank};
+use render_2d::{
+    Align, Baseline, Bounded, PathStyle2, SvgColor, SvgDoc, SvgGroup,
+    SvgPath, SvgText, TextStyle,
+};
+use util::offset_polyline;
+
+/// How many points to draw each deck beam's camber through.
+const BEAM_SAMPLES: usize = 32;
+
+impl Hull {
+    /// The outline of the deck in plan view, as (x, y) points running aft
+    /// along the starboard sheer and then forward along the port sheer.
+    pub fn deck_outline(&self) -> Result<Vec<P2>, LapstrakeError> {
+        let starboard: Vec<P2> = self
+            .get_line(1.0)?
+            .sample(None)?
+            .iter()
+            .map(|p| P2::new(p.x, p.y))
+            .collect();
+        let mut outline = starboard.clone();
+        outline.extend(starboard.iter().rev().map(|p| P2::new(p.x, -p.y)));
+        Ok(outline)
+    }
+
+    /// Get the starboard half of the deck as plank-like panels, each
+    /// running between the crown on the centerline and the sheer, and
+    /// split at the configured seam. The deck is cambered as a parabola
+    /// across its width, which is the same curve at every station, so
+    /// that the crown rises highest where the deck is widest.
+    pub fn get_deck_panels(&self) -> Result<Vec<Plank>, LapstrakeError> {
+        let sheer = self.get_line(1.0)?.sample(None)?;
+        let max_breadth = sheer.iter().fold(0., |max: f32, p| max.max(p.y));
+
+        let mut pieces = vec![sheer.clone()];
+        if let Some(seam) = self.deck_seam {
+            if let Some(at_seam) = interpolate_at_x(&sheer, seam) {
+                let fore = sheer
+                    .iter()
+                    .cloned()
+                    .filter(|p| p.x < seam)
+                    .chain(iter::once(at_seam))
+                    .collect();
+                let aft = iter::once(at_seam)
+                    .chain(sheer.iter().cloned().filter(|p| p.x > seam))
+                    .collect();
+                pieces = vec![fore, aft];
+            }
+        }
+
+        pieces
+            .into_iter()
+            .map(|sheer: Vec<P3>| {
+                let crown = sheer
+                    .iter()
+                    .map(|&p| self.crown_point(p, max_breadth))
+                    .collect();
+                // The lines are already finely sampled.
+                Plank::new(
+                    crown,
+                    sheer,
+                    1,
+                    self.interpolation,
+                    self.equality_threshold,
+                )
+            })
+            .collect()
+    }
+
+    /// Draw the deck outline in plan view, and below it the flattened
+    /// deck panels. Each panel covers one side of the deck, so cut two
+    /// of each, one flipped over.
+    pub fn draw_deck(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        let mut outline = SvgGroup::new();
+        outline.append(
+            SvgPath::new(self.deck_outline()?)
+                .stroke(SvgColor::Black, STROKE)
+                .style(PathStyle2::Line),
+        );
+
+        let mut panels = SvgGroup::new();
+        let flattened =
+            FlattenedPlank::flatten_planks(self.get_deck_panels()?)?;
+        for panel in flattened {
+            panels.append(panel.render_2d());
+        }
+
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![self.scale_bar()?, outline, panels],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::Deck, doc)
+    }
+
+    /// Draw a template for the deck beam at each station, reaching across
+    /// from sheer to sheer, with its top cut to the deck's camber. They're
+    /// all cut from the same curve, so the narrower beams have less crown.
+    pub fn draw_deck_beams(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.1;
+        let palette = self.theme.palette();
+        let sheer = self.get_line(1.0)?.sample(None)?;
+        let max_breadth = sheer.iter().fold(0., |max: f32, p| max.max(p.y));
+
+        let mut templates = vec![];
+        for station in &self.stations {
+            let breadth = station.sheer().y;
+            if breadth < self.equality_threshold {
+                continue;
+            }
+            let crown = self.crown_point(station.sheer(), max_breadth).z
+                - station.sheer().z;
+            // Heights are flipped, so that the beams are right side up.
+            let top: Vec<P2> = (0..BEAM_SAMPLES + 1)
+                .map(|i| {
+                    let t = i as f32 / BEAM_SAMPLES as f32;
+                    let y = breadth * (2. * t - 1.);
+                    let rise = if max_breadth > 0. {
+                        self.deck_camber * (breadth.powi(2) - y.powi(2))
+                            / max_breadth.powi(2)
+                    } else {
+                        0.
+                    };
+                    P2::new(y, -rise)
+                })
+                .collect();
+            let mut outline = top.clone();
+            outline.extend(
+                offset_polyline(&top, self.deck_beam_moulding)
+                    .into_iter()
+                    .rev(),
+            );
+            let path = SvgPath::new(outline)
+                .stroke(palette.outline, STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let bound = path.bound().expect("deck beam has no bound");
+            let centerline = SvgPath::new(vec![
+                P2::new(0., bound.low.y),
+                P2::new(0., bound.high.y),
+            ]).stroke(palette.grid, STROKE / 2.)
+                .style(PathStyle2::Dashed);
+            let label = SvgText {
+                lines: vec![
+                    station.name.clone(),
+                    format!("Crown {}", self.units.from_feet(crown)),
+                ],
+                pos: bound.relative_pos(1., 0.5) + V2::new(FONT_SIZE, 0.),
+                color: palette.label,
+                size: FONT_SIZE,
+                style: TextStyle::default(),
+            }.anchored(Align::Start, Baseline::Central);
+
+            let mut group = SvgGroup::new();
+            group.append(Layer::Stations.wrap(path));
+            group.append(Layer::Marks.wrap(centerline));
+            group.append(Layer::Labels.wrap(label));
+            templates.push(group);
+        }
+
+        let mut doc = SvgDoc::new();
+        let parts =
+            vec![self.scale_bar()?, SvgGroup::new_vertical(templates, 1.1)?];
+        doc.append(SvgGroup::new_vertical(parts, 1.1)?);
+        self.run_drawing_passes(Drawing::DeckBeams, doc)
+    }
+
+    // The point on the centerline at the crown of the deck, for the given
+    // point on the sheer.
+    fn crown_point(&self, sheer: P3, max_breadth: f32) -> P3 {
+        let rise = if max_breadth > 0. {
+            self.deck_camber * (sheer.y / max_breadth).powi(2)
+        } else {
+            0.
+        };
+        P3::new(sheer.x, 0., sheer.z + rise)
+    }
+}
+
+// Linearly interpolate along a line that runs from fore to aft, at the
+// given x. Returns None if the line doesn't reach that far.
+fn interpolate_at_x(line: &[P3], x: f32) -> Option<P3> {
+    for pair in line.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        if p.x < x && x < q.x {
+            let t = (x - p.x) / (q.x - p.x);
+            return Some(p + (q - p) * t);
+        }
+    }
+    None
+}
@@ -3,134 +3,1066 @@ use scad_dots::utils::{Axis, P2, P3, V2};
 
 use error::LapstrakeError;
 use hull::{Hull, Station};
+use plank::{FlattenedPlank, Plank};
 use render_2d::{
-    make_scale_bar, Bound, Bounded, PathStyle2, SvgCircle, SvgColor, SvgDoc,
-    SvgGroup, SvgPath, SvgText,
+    Align, Baseline, Bound, Bounded, PathStyle2, SvgCircle, SvgColor,
+    SvgDimension, SvgDoc, SvgGroup, SvgPath, SvgSymbol, SvgText, TextStyle,
+    ToSvg,
 };
-use render_3d::{PathStyle3, ScadPath, SCAD_STROKE};
-use unit::Feet;
-use util::{project_points, reflect2, reflect3};
+use render_3d::{PathStyle3, ScadPath, ScadSurface, SCAD_STROKE};
+use spec::Config;
+use unit::{Length, Units};
+use util::{
+    both_sides, offset_polygon, practically_zero, project_points, reflect2,
+    reflect3, sort_by_number,
+};
+
+/// The standard drawings that custom passes can be added to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drawing {
+    HalfBreadths,
+    Waterlines,
+    Buttocks,
+    Profile,
+    LinesPlan,
+    CrossSections,
+    Planks,
+    Lifts,
+    Transom,
+    Stem,
+    Backbone,
+    DeckBeams,
+    Deck,
+    Cradles,
+    Ballast,
+    Jig,
+    Curvature,
+}
+
+/// How far the smooth curves in the lines drawings can stray from the
+/// sampled lines, in feet: a 32nd of an inch.
+pub(crate) const CURVE_TOLERANCE: f32 = 1. / 384.;
+
+/// Where to put the alignment holes and mounting tab on the station
+/// templates, to suit the jig they'll be set up on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TemplateLayout {
+    pub hole_count: usize,
+    pub hole_diameter: f32,
+    /// Where the column of holes goes, as a fraction of the way across
+    /// the area that all of the templates share.
+    pub hole_across: f32,
+    /// Where the lowest and highest holes go, as fractions of the way up
+    /// that area. Any others are spaced evenly between them.
+    pub hole_low: f32,
+    pub hole_high: f32,
+    /// The tab's length, as a fraction of each template's width.
+    pub tab_length: f32,
+    /// How high above base the tab is. Defaults to a bit above the
+    /// highest sheer.
+    pub tab_height: Option<f32>,
+    /// How high above base the design waterline is, if it's marked.
+    #[serde(default)]
+    pub design_waterline: Option<f32>,
+    /// How thick the sheets that the molds and jig are cut from are.
+    #[serde(default = "default_material_thickness")]
+    pub material_thickness: f32,
+    /// Whether to shrink the templates by the plank thickness, so that
+    /// they fit inside the planking instead of matching its outside.
+    #[serde(default)]
+    pub inside_planking: bool,
+    /// Whether to mark each template with the hull's section at its other
+    /// face, a material thickness towards midships, to bevel its edge to.
+    #[serde(default)]
+    pub bevel_marks: bool,
+}
+
+fn default_material_thickness() -> f32 {
+    TemplateLayout::default().material_thickness
+}
+
+impl Default for TemplateLayout {
+    fn default() -> TemplateLayout {
+        TemplateLayout {
+            hole_count: 2,
+            hole_diameter: 0.125,
+            hole_across: 0.5,
+            hole_low: 0.33,
+            hole_high: 0.66,
+            tab_length: 0.75,
+            tab_height: None,
+            design_waterline: None,
+            // 3/4" plywood.
+            material_thickness: 0.0625,
+            inside_planking: false,
+            bevel_marks: false,
+        }
+    }
+}
+
+impl TemplateLayout {
+    /// Take the layout from the config sheet, using the defaults for
+    /// anything that's left out.
+    pub fn from_config(config: &Config) -> TemplateLayout {
+        let default = TemplateLayout::default();
+        TemplateLayout {
+            hole_count: config.hole_count.unwrap_or(default.hole_count),
+            hole_diameter: config
+                .hole_diameter
+                .map_or(default.hole_diameter, |d| d.into()),
+            hole_across: config.hole_across.unwrap_or(default.hole_across),
+            hole_low: config.hole_low.unwrap_or(default.hole_low),
+            hole_high: config.hole_high.unwrap_or(default.hole_high),
+            tab_length: config.tab_length.unwrap_or(default.tab_length),
+            tab_height: config.tab_height.map(|h| h.into()),
+            design_waterline: config.design_waterline.map(|h| h.into()),
+            material_thickness: config
+                .material_thickness
+                .map_or(default.material_thickness, |t| t.into()),
+            inside_planking: config.inside_planking,
+            bevel_marks: config.bevel_marks,
+        }
+    }
+
+    // The position of each hole, as fractions across and up.
+    pub(crate) fn hole_fractions(&self) -> Vec<(f32, f32)> {
+        (0..self.hole_count)
+            .map(|i| {
+                let t = if self.hole_count == 1 {
+                    0.5
+                } else {
+                    i as f32 / (self.hole_count - 1) as f32
+                };
+                let up = self.hole_low + t * (self.hole_high - self.hole_low);
+                (self.hole_across, up)
+            })
+            .collect()
+    }
+}
+
+/// How far below a drawing its title block goes, in feet.
+const TITLE_BLOCK_MARGIN: f32 = 0.5;
+
+/// The block of information in the corner of a shop drawing, saying
+/// what's drawn and how to read it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TitleBlock {
+    pub boat_name: Option<String>,
+    pub designer: Option<String>,
+    pub date: Option<String>,
+    /// How the spec sheets were measured, like "millimeters".
+    pub units: String,
+}
+
+impl TitleBlock {
+    /// Take the title block from the config sheet, if it asks for one.
+    pub fn from_config(config: &Config) -> Option<TitleBlock> {
+        if !config.title_block {
+            return None;
+        }
+        let units = match config.units {
+            Units::Feet => "feet-inches-eighths",
+            Units::Millimeters => "millimeters",
+        };
+        Some(TitleBlock {
+            boat_name: config.boat_name.clone(),
+            designer: config.designer.clone(),
+            date: config.date.clone(),
+            units: units.to_owned(),
+        })
+    }
+
+    /// Add the title block to the bottom right corner of the document,
+    /// as its `sheet`th sheet. The scale is what the document will be
+    /// saved at, as in `SvgDoc::save()`.
+    pub fn draw_onto(
+        &self,
+        doc: &mut SvgDoc,
+        sheet: usize,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        let mut block = self.block(sheet, scale_from_feet)?;
+        // Right below the drawing, lined up with its right side.
+        let corner = doc.bound().map_or(P2::origin(), |bound| bound.high);
+        let width = block.bound().map_or(0., |bound| bound.width());
+        block.translate_to(P2::new(
+            corner.x - width,
+            corner.y + TITLE_BLOCK_MARGIN,
+        ))?;
+        doc.append(block.layer(Layer::Labels.name()));
+        Ok(())
+    }
+
+    /// How much room the title block takes up below a drawing saved at
+    /// the given scale, in feet.
+    pub fn height(&self, scale_from_feet: f32) -> Result<f32, LapstrakeError> {
+        let block = self.block(1, scale_from_feet)?;
+        Ok(TITLE_BLOCK_MARGIN + block.bound().map_or(0., |b| b.height()))
+    }
+
+    // The framed block of text, at the origin.
+    fn block(
+        &self,
+        sheet: usize,
+        scale_from_feet: f32,
+    ) -> Result<SvgGroup, LapstrakeError> {
+        // Keep the text a readable size on paper, whatever the scale.
+        const FONT_INCHES: f32 = 0.15;
+        let size = FONT_INCHES / 12. / scale_from_feet;
+        let inches_per_foot = 12. * scale_from_feet;
+        let mut lines = vec![];
+        if let Some(ref name) = self.boat_name {
+            lines.push(name.clone());
+        }
+        if let Some(ref designer) = self.designer {
+            lines.push(format!("Designed by {}", designer));
+        }
+        if let Some(ref date) = self.date {
+            lines.push(date.clone());
+        }
+        lines.push(format!(
+            "Scale {:.3}\" = 1' (1:{:.0})",
+            inches_per_foot,
+            1. / scale_from_feet
+        ));
+        lines.push(format!("Measured in {}", self.units));
+        lines.push(format!("Sheet {}", sheet));
+
+        let mut block = SvgGroup::new();
+        block.append(SvgText {
+            lines,
+            pos: P2::origin(),
+            color: SvgColor::Black,
+            size,
+            style: TextStyle::default(),
+        });
+        block.framed(size / 2., SvgColor::Black, size / 10.)
+    }
+}
+
+/// The layers that the standard drawings are split into, so that svg
+/// editors can show, hide, and edit each kind of line separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// The height and breadth grid, and station marks.
+    Grid,
+    /// The sheer, keel, waterlines, and buttocks.
+    Lines,
+    /// Station cross-sections, and the outlines of station templates.
+    Stations,
+    /// The outlines of flattened planks.
+    Planks,
+    /// Lines to mark on parts, but not to cut, like gains.
+    Marks,
+    /// Alignment holes and fastener holes.
+    Holes,
+    /// Text and dimensions.
+    Labels,
+}
+
+impl Layer {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Layer::Grid => "grid",
+            Layer::Lines => "lines",
+            Layer::Stations => "stations",
+            Layer::Planks => "planks",
+            Layer::Marks => "marks",
+            Layer::Holes => "holes",
+            Layer::Labels => "labels",
+        }
+    }
+
+    /// Put `thing` on this layer.
+    pub fn wrap<T: ToSvg + Bounded>(&self, thing: T) -> SvgGroup {
+        let mut group = SvgGroup::new();
+        group.append(thing);
+        group.layer(self.name())
+    }
+}
+
+/// Which set of colors to draw with, to suit where the drawings are
+/// going.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    /// Black outlines, with blue waterlines, buttocks, and diagonals.
+    #[serde(rename = "standard")]
+    Standard,
+    /// Only black and greys, for printing or photocopying.
+    #[serde(rename = "print")]
+    Print,
+    /// Darker greys and saturated colors, which hold up on a projector
+    /// or a dim shop printer.
+    #[serde(rename = "contrast")]
+    Contrast,
+    /// Like the standard theme, but each station gets its own color, so
+    /// they're easier to tell apart in the body plan.
+    #[serde(rename = "stations")]
+    Stations,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::Standard
+    }
+}
+
+/// Which side of the centerline each station is drawn on in the body
+/// plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyPlan {
+    /// The first half of the stations, in the order they're listed, on
+    /// the right, and the rest on the left.
+    #[serde(rename = "split")]
+    Split,
+    /// Following the traditional convention, stations forward of
+    /// midships on the right, and those aft of it on the left.
+    #[serde(rename = "traditional")]
+    Traditional,
+    /// Every station drawn complete, on both sides of the centerline.
+    #[serde(rename = "full")]
+    Full,
+}
+
+impl Default for BodyPlan {
+    fn default() -> BodyPlan {
+        BodyPlan::Split
+    }
+}
+
+impl Theme {
+    pub fn palette(&self) -> Palette {
+        match *self {
+            Theme::Standard => Palette {
+                outline: SvgColor::Black,
+                highlight: SvgColor::Blue,
+                grid: SvgColor::DarkGrey,
+                faint: SvgColor::LightGrey,
+                label: SvgColor::DarkGrey,
+                by_station: false,
+            },
+            Theme::Print => Palette {
+                outline: SvgColor::Black,
+                highlight: SvgColor::Custom(0x55, 0x55, 0x55),
+                grid: SvgColor::Custom(0x99, 0x99, 0x99),
+                faint: SvgColor::Custom(0xcc, 0xcc, 0xcc),
+                label: SvgColor::Black,
+                by_station: false,
+            },
+            Theme::Contrast => Palette {
+                outline: SvgColor::Black,
+                highlight: SvgColor::Custom(0x00, 0x40, 0xff),
+                grid: SvgColor::Custom(0x66, 0x66, 0x66),
+                faint: SvgColor::Custom(0x99, 0x99, 0x99),
+                label: SvgColor::Black,
+                by_station: false,
+            },
+            Theme::Stations => Palette {
+                by_station: true,
+                ..Theme::Standard.palette()
+            },
+        }
+    }
+}
+
+/// The colors that the drawings use for each kind of line.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// The hull itself: stations, sheer, keel, and cut lines.
+    pub outline: SvgColor,
+    /// Waterlines, buttocks, and diagonals.
+    pub highlight: SvgColor,
+    /// The height and breadth grid, and gain lines.
+    pub grid: SvgColor,
+    /// Marks that shouldn't distract from anything else.
+    pub faint: SvgColor,
+    /// Station names and other annotations.
+    pub label: SvgColor,
+    /// Whether to give each station a different color.
+    pub by_station: bool,
+}
+
+impl Palette {
+    /// The color of the `i`th of `count` stations. Unless the palette
+    /// colors by station, they're all outlines.
+    pub fn station(&self, i: usize, count: usize) -> SvgColor {
+        if !self.by_station || count == 0 {
+            return self.outline;
+        }
+        // Sweep around the color wheel, stopping short of red again so
+        // that the first and last stations differ.
+        hue(0.8 * i as f32 / count as f32)
+    }
+}
+
+// A fully saturated color, with `hue` going from 0 to 1 around the wheel.
+fn hue(hue: f32) -> SvgColor {
+    let h = 6. * (hue - hue.floor());
+    let rising = h - h.floor();
+    let falling = 1. - rising;
+    let (r, g, b) = match h as usize {
+        0 => (1., rising, 0.),
+        1 => (falling, 1., 0.),
+        2 => (0., 1., rising),
+        3 => (0., falling, 1.),
+        4 => (rising, 0., 1.),
+        _ => (1., 0., falling),
+    };
+    // Darken it a bit, so yellows and cyans still show up on white.
+    let scale = |c: f32| (0.8 * 255. * c).round() as u8;
+    SvgColor::Custom(scale(r), scale(g), scale(b))
+}
+
+/// A custom drawing pass, for adding annotations to the standard
+/// drawings without changing how they're drawn. Register one with
+/// `Hull::add_drawing_pass()`, and it will be run after each drawing has
+/// been built.
+pub trait DrawingPass: Send + Sync {
+    fn draw(
+        &self,
+        drawing: Drawing,
+        hull: &Hull,
+        doc: &mut SvgDoc,
+    ) -> Result<(), LapstrakeError>;
+}
 
 impl Hull {
     pub fn draw_half_breadths(&self) -> Result<SvgDoc, LapstrakeError> {
-        let stroke = 0.02;
-        let mut paths = self.draw_height_breadth_grid(stroke);
-        let half = (self.stations.len() as f32) / 2.;
-        for (i, station) in self.stations.iter().enumerate() {
-            let mut samples: Vec<P3> = station.spline.sample(None)?;
-            let mut points: Vec<P3> = station.points.clone();
-            if (i as f32) >= half {
-                samples = reflect3(Axis::Y, &samples);
-                points = reflect3(Axis::Y, &points);
-            }
-            paths.push(
-                SvgPath::new(project_points(Axis::X, &samples))
-                    .stroke(SvgColor::Black, stroke)
-                    .style(PathStyle2::Line),
-            );
-            paths.push(
-                SvgPath::new(project_points(Axis::X, &points))
-                    .stroke(SvgColor::Black, stroke)
-                    .style(PathStyle2::Dots),
-            );
-        }
         let mut doc = SvgDoc::new();
-        doc.append_vec(paths);
-        Ok(doc)
+        doc.append(self.body_plan_view()?);
+        self.run_drawing_passes(Drawing::HalfBreadths, doc)
     }
 
-    // TODO split up long function
+    /// Draw templates for the station molds. `kerf` is the width of
+    /// material removed by the saw or laser that will cut them out, in
+    /// feet; each outline is grown to make up for it. Each pair of
+    /// `mirrored` stations, from `find_mirrored_stations()`, shares one
+    /// template.
     pub fn draw_cross_sections(
         &self,
         excluded: &[String],
+        mirrored: &[(String, String)],
+        kerf: f32,
     ) -> Result<SvgDoc, LapstrakeError> {
-        const HOLE_DIAMETER: f32 = 0.125;
+        let (holes, templates) =
+            self.cross_section_templates(excluded, mirrored, kerf)?;
+        let groups = templates.into_iter().map(|(_, group)| group).collect();
+        let mut doc = SvgDoc::new();
+        doc.define(&holes);
+        let grid = SvgGroup::new_grid(groups, 1.1)?;
+        let stack =
+            SvgGroup::new_vertical(vec![self.scale_bar()?, grid], 1.1)?;
+        doc.append(stack);
+        self.run_drawing_passes(Drawing::CrossSections, doc)
+    }
+
+    /// Like `draw_cross_sections()`, but with each template in its own
+    /// document, for laser cutters that want one part per file. Each
+    /// comes with the name it's labeled with.
+    pub fn draw_cross_section_files(
+        &self,
+        excluded: &[String],
+        mirrored: &[(String, String)],
+        kerf: f32,
+    ) -> Result<Vec<(String, SvgDoc)>, LapstrakeError> {
+        let (holes, templates) =
+            self.cross_section_templates(excluded, mirrored, kerf)?;
+        let mut docs = vec![];
+        for (name, group) in templates {
+            let mut doc = SvgDoc::new();
+            doc.define(&holes);
+            let stack =
+                SvgGroup::new_vertical(vec![self.scale_bar()?, group], 1.1)?;
+            doc.append(stack);
+            let doc = self.run_drawing_passes(Drawing::CrossSections, doc)?;
+            docs.push((name, doc));
+        }
+        Ok(docs)
+    }
+
+    /// Like `draw_cross_sections()`, but with the templates laid out on
+    /// as many documents as it takes to fit them on pages of the given
+    /// size, in feet. The scale bar goes at the top of each page.
+    pub fn draw_cross_section_pages(
+        &self,
+        excluded: &[String],
+        mirrored: &[(String, String)],
+        kerf: f32,
+        page: V2,
+    ) -> Result<Vec<SvgDoc>, LapstrakeError> {
+        const SPACING: f32 = 1.1;
+        let (holes, templates) =
+            self.cross_section_templates(excluded, mirrored, kerf)?;
+        let groups = templates.into_iter().map(|(_, group)| group).collect();
+        let bar_height = self.scale_bar()?.bound().map_or(0., |b| b.height());
+        // Leave room for the title block below the templates, too.
+        let block_height = match self.title_block {
+            Some(ref block) => block.height(self.drawing_scale)?,
+            None => 0.,
+        };
+        let room =
+            V2::new(page.x, page.y - bar_height - SPACING - block_height);
+        let mut docs = vec![];
+        for sheet in SvgGroup::new_pages(groups, SPACING, room)? {
+            let mut doc = SvgDoc::new();
+            doc.define(&holes);
+            let contents = vec![self.scale_bar()?, sheet];
+            doc.append(SvgGroup::new_vertical(contents, SPACING)?);
+            docs.push(self.run_drawing_passes(Drawing::CrossSections, doc)?);
+        }
+        Ok(docs)
+    }
+
+    // Make the station templates, each with its label, along with the
+    // symbol for the alignment holes that they all share.
+    fn cross_section_templates(
+        &self,
+        excluded: &[String],
+        mirrored: &[(String, String)],
+        kerf: f32,
+    ) -> Result<(SvgSymbol, Vec<(String, SvgGroup)>), LapstrakeError> {
         const STROKE: f32 = 0.02;
+        let layout = &self.template_layout;
+        let palette = self.theme.palette();
+        // Stations with the same shape share one template, labeled with
+        // both names.
+        let mirrored: Vec<(String, String)> = mirrored
+            .iter()
+            .filter(|&&(ref fore, ref aft)| {
+                !excluded.contains(fore) && !excluded.contains(aft)
+            })
+            .cloned()
+            .collect();
+        let inset = if layout.inside_planking {
+            self.plank_thickness
+        } else {
+            0.
+        };
+        let midships = self.midships();
         let mut paths = Vec::new();
         let mut bounds = Vec::new();
-        for station in &self.stations {
-            if excluded.contains(&station.name) {
+        let count = self.stations.len();
+        for (i, station) in self.stations.iter().enumerate() {
+            if excluded.contains(&station.name)
+                || mirrored.iter().any(|&(_, ref aft)| *aft == station.name)
+            {
                 continue;
             }
-            let path = station
-                .get_cross_section_path()?
-                .stroke(SvgColor::Black, 0.02);
+            let name = match mirrored
+                .iter()
+                .find(|&&(ref fore, _)| *fore == station.name)
+            {
+                Some(&(ref fore, ref aft)) => format!("{} & {}", fore, aft),
+                None => station.name.clone(),
+            };
+            let mut points = station.cross_section_points(inset)?;
+            let trunk = self.trunk_through(station.position());
+            if let Some(trunk) = trunk {
+                println!("Station {} is slotted for the trunk.", name);
+                points = trunk.notch(&points);
+            }
+            let path = SvgPath::new(points)
+                .stroke(palette.station(i, count), STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let mut bevels = SvgGroup::new();
+            if layout.bevel_marks {
+                // The section at the template's other face, which the
+                // hull grows or shrinks towards.
+                let position = station.position();
+                let towards = if position < midships { 1. } else { -1. };
+                let face = position + towards * layout.material_thickness;
+                let face = self.units.from_feet(face);
+                let face = self.hallucinate_station(face)?;
+                bevels.append(Layer::Marks.wrap(
+                    SvgPath::new(face.cross_section_points(inset)?)
+                        .stroke(palette.grid, STROKE / 2.)
+                        .style(PathStyle2::DashDot),
+                ));
+                // And ticks along the edge, labeled with its bevel.
+                for bevel in self.frame_bevels(station)? {
+                    for &side in &[1., -1.] {
+                        let normal =
+                            V2::new(side * bevel.normal.x, bevel.normal.y);
+                        let edge = P2::new(side * bevel.breadth, bevel.height)
+                            - normal * inset;
+                        bevels.append(mark_bevel(
+                            edge,
+                            normal,
+                            bevel.angle,
+                            &palette,
+                        ));
+                    }
+                }
+            }
+            // Flag the slot, so it isn't mistaken for a mistake.
+            if let Some(trunk) = trunk {
+                bevels.append(Layer::Labels.wrap(
+                    SvgText {
+                        lines: vec!["Trunk".into()],
+                        pos: P2::new(0., trunk.top + layout.hole_diameter),
+                        color: palette.label,
+                        size: layout.hole_diameter,
+                        style: TextStyle::default(),
+                    }.anchored(Align::Middle, Baseline::Alphabetic),
+                ));
+            }
             bounds.push(path.bound());
-            paths.push((station.name.clone(), path));
+            paths.push((name, path, bevels));
         }
 
         let max_y = Bound::union_all(&bounds).high.y;
+        let (holes, holes_bound) =
+            self.alignment_holes(&bounds, layout.hole_fractions(), kerf)?;
 
-        let intersection = Bound::intersect_all(&bounds)
-            .ok_or(LapstrakeError::Draw.context(
-            "cross-sections have no overlap in which to place alignment holes",
-        ))?;
-
-        let hole_positions = vec![
-            intersection.relative_pos(0.5, 0.33),
-            intersection.relative_pos(0.5, 0.66),
-        ];
-
-        let mut holes = SvgGroup::new();
-        for pos in hole_positions {
-            let hole = SvgCircle::new(pos, HOLE_DIAMETER / 2.)
-                .stroke(SvgColor::Black, STROKE);
-            if !intersection.contains(&hole.bound().unwrap()) {
-                return Err(LapstrakeError::Draw.context(
-                    "hole doesn't fit in overlap between cross-sections",
-                ));
-            }
-            holes.append(hole)
-        }
-
-        let mut groups = Vec::new();
-        for (name, mut path) in paths {
+        let mut templates = Vec::new();
+        for (name, mut path, bevels) in paths {
             // Add tab to each cross-section, for mounting it into a jig
             // let mut path = path.to_owned();
             let bound = path.bound().expect("path has no bound");
-            let tab_length = V2::new(0.75 * bound.width(), 0.);
-            let tab_center = P2::new(bound.center().x, 1.2 * max_y);
+            let tab_length = V2::new(layout.tab_length * bound.width(), 0.);
+            let tab_height = layout.tab_height.unwrap_or(1.2 * max_y);
+            let tab_center = P2::new(bound.center().x, tab_height);
             path.append(vec![
                 tab_center - tab_length / 2.,
                 tab_center + tab_length / 2.,
             ]);
+            // Cut along the outside of the line, so the kerf comes out of
+            // the scrap instead of the template.
+            let path = path.offset(kerf / 2.)?;
 
             // Add text label with name of cross-section
-            let holes_bound = holes.bound().unwrap();
             let label = SvgText {
-                lines: vec![name.into()],
+                lines: vec![name.clone()],
                 pos: holes_bound.center(),
-                color: SvgColor::Black,
-                size: 0.9 * (holes_bound.height() - 2. * HOLE_DIAMETER),
+                color: palette.outline,
+                size: (0.9 * (holes_bound.height() - 2. * layout.hole_diameter))
+                    .max(layout.hole_diameter),
+                style: TextStyle::default(),
+            };
+
+            // Datum marks for lining the frame up on the strongback.
+            let (left, right) = (bound.low.x, bound.high.x);
+            let mark = |points: Vec<P2>, style: PathStyle2| {
+                SvgPath::new(points)
+                    .stroke(palette.grid, STROKE / 2.)
+                    .style(style)
             };
+            let mut marks = SvgGroup::new();
+            let bottom = bound.low.y.min(0.);
+            marks.append(mark(
+                vec![P2::new(0., bottom), P2::new(0., tab_height)],
+                PathStyle2::Dashed,
+            ));
+            marks.append(mark(
+                vec![P2::new(left, 0.), P2::new(right, 0.)],
+                PathStyle2::Line,
+            ));
+            if let Some(height) = layout.design_waterline {
+                marks.append(mark(
+                    vec![P2::new(left, height), P2::new(right, height)],
+                    PathStyle2::Line,
+                ));
+            }
 
             let mut group = SvgGroup::new();
-            group.append(path);
-            group.append(label);
-            group.append(holes.clone());
+            group.append(Layer::Stations.wrap(path));
+            group.append(marks.layer(Layer::Marks.name()));
+            group.append(bevels);
+            group.append(Layer::Labels.wrap(label));
+            group.append(Layer::Holes.wrap(holes.place(V2::new(0., 0.))));
 
-            groups.push(group);
+            templates.push((name, group));
         }
+        Ok((holes, templates))
+    }
+
+    // Make the symbol for the alignment holes that templates with the
+    // given bounds share, at the given fractions of the way across and
+    // up the area where they all overlap, along with where they are.
+    pub(crate) fn alignment_holes(
+        &self,
+        bounds: &[Option<Bound>],
+        fractions: Vec<(f32, f32)>,
+        kerf: f32,
+    ) -> Result<(SvgSymbol, Bound), LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        let diameter = self.template_layout.hole_diameter;
+        let palette = self.theme.palette();
+        let intersection = Bound::intersect_all(bounds).ok_or(
+            LapstrakeError::Draw.context(
+                "templates have no overlap in which to place alignment holes",
+            ),
+        )?;
+        let mut holes = SvgGroup::new();
+        for (across, up) in fractions {
+            let pos = intersection.relative_pos(across, up);
+            let hole = SvgCircle::new(pos, (diameter - kerf) / 2.)
+                .stroke(palette.outline, STROKE);
+            if !intersection.contains(&hole.bound().unwrap()) {
+                return Err(LapstrakeError::Draw.context(
+                    "hole doesn't fit in overlap between templates",
+                ));
+            }
+            holes.append(hole)
+        }
+        let holes_bound = holes.bound().ok_or(
+            LapstrakeError::Draw.context("templates need an alignment hole"),
+        )?;
+        Ok((SvgSymbol::new("alignment-holes", holes), holes_bound))
+    }
+
+    /// Draw each of the hull's waterlines in plan view, along with the
+    /// sheer. Waterlines that cross too few stations are skipped.
+    pub fn draw_waterlines(&self) -> Result<SvgDoc, LapstrakeError> {
         let mut doc = SvgDoc::new();
-        let grid = SvgGroup::new_grid(groups, 1.1)?;
-        let stack = SvgGroup::new_vertical(vec![make_scale_bar()?, grid], 1.1)?;
-        doc.append(stack);
-        Ok(doc)
+        doc.append(self.waterline_view()?);
+        self.run_drawing_passes(Drawing::Waterlines, doc)
+    }
+
+    /// Draw each of the hull's buttock lines in profile view, along with
+    /// the sheer and keel. Buttocks that cross too few stations are
+    /// skipped.
+    pub fn draw_buttocks(&self) -> Result<SvgDoc, LapstrakeError> {
+        let mut doc = SvgDoc::new();
+        doc.append(self.profile_view()?);
+        self.run_drawing_passes(Drawing::Buttocks, doc)
     }
 
-    /// Flatten the planks and lay them out in an svg document.
+    /// Draw the hull from the side: the sheer, the wale (if it was
+    /// given), and the profile of the keel, closed off at either end by
+    /// the stem and the stern. Each station is ticked and named above the
+    /// sheer, where it crosses it.
+    pub fn draw_profile(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.25;
+        let palette = self.theme.palette();
+        // Flip heights, so that the profile is right side up.
+        let flip = |points: &[P3]| -> Vec<P2> {
+            points.iter().map(|p| P2::new(p.x, -p.z)).collect()
+        };
+        let line = |points: Vec<P2>, color: SvgColor| {
+            SvgPath::new(points)
+                .curved(CURVE_TOLERANCE)
+                .stroke(color, STROKE)
+                .style(PathStyle2::Line)
+        };
+
+        let mut lines = SvgGroup::new();
+        lines.append(line(
+            flip(&self.get_line(1.)?.sample(None)?),
+            palette.outline,
+        ));
+        lines.append(line(
+            flip(&self.get_line(0.)?.sample(None)?),
+            palette.outline,
+        ));
+        if let Some(wale) = self.wale_line()? {
+            lines.append(line(flip(&wale.sample(None)?), palette.highlight));
+        }
+        // The end stations show the profile of the stem and of the
+        // transom or sternpost.
+        let mut ends: Vec<&Station> = self.stations.iter().collect();
+        sort_by_number(&mut ends, |station| station.position())?;
+        for station in &[ends[0], ends[ends.len() - 1]] {
+            lines.append(line(
+                flip(&station.spline.sample(None)?),
+                palette.outline,
+            ));
+        }
+
+        let mut marks = SvgGroup::new();
+        let mut labels = SvgGroup::new();
+        for station in &self.stations {
+            let sheer = P2::new(station.position(), -station.sheer().z);
+            let tick = vec![sheer, sheer - V2::new(0., FONT_SIZE / 2.)];
+            marks.append(
+                SvgPath::new(tick)
+                    .stroke(palette.grid, STROKE / 2.)
+                    .style(PathStyle2::Line),
+            );
+            labels.append(
+                SvgText {
+                    lines: vec![station.name.clone()],
+                    pos: sheer - V2::new(0., FONT_SIZE * 0.75),
+                    color: palette.label,
+                    size: FONT_SIZE,
+                    style: TextStyle::default(),
+                }.rotated(-90.)
+                    .anchored(Align::Start, Baseline::Central),
+            );
+        }
+
+        let mut profile = SvgGroup::new();
+        profile.append(lines.layer(Layer::Lines.name()));
+        profile.append(marks.layer(Layer::Marks.name()));
+        profile.append(labels.layer(Layer::Labels.name()));
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![self.scale_bar()?, profile],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::Profile, doc)
+    }
+
+    /// Draw a complete lines plan: the body plan, the half-breadth plan
+    /// of the waterlines, and the profile of the buttocks, stacked with a
+    /// scale bar. The plan and profile views share station marks, and are
+    /// lined up fore and aft. The beam, the overall length, and the
+    /// spacing between stations are dimensioned.
+    pub fn draw_lines_plan(&self) -> Result<SvgDoc, LapstrakeError> {
+        const DIMENSION_OFFSET: f32 = 0.75;
+        let mut body = self.body_plan_view()?;
+        let mut plan = self.waterline_view()?;
+        let mut profile = self.profile_view()?;
+        let min_x = self.min_coord(Axis::X);
+        let max_x = self.max_coord(Axis::X);
+        let max_y = self.max_coord(Axis::Y);
+        let min_z = self.min_coord(Axis::Z);
+        let max_z = self.max_coord(Axis::Z);
+        let palette = self.theme.palette();
+        for station in &self.stations {
+            mark_station(&mut plan, station, 0., max_y, &palette);
+            mark_station(&mut profile, station, min_z, max_z, &palette);
+        }
+        let dimension = |start: P2, end: P2| {
+            Layer::Labels.wrap(
+                SvgDimension::new(start, end, DIMENSION_OFFSET)
+                    .stroke(palette.label, 0.01),
+            )
+        };
+        body.append(dimension(P2::new(-max_y, min_z), P2::new(max_y, min_z)));
+        profile.append(dimension(P2::new(min_x, min_z), P2::new(max_x, min_z)));
+        for pair in self.stations.windows(2) {
+            // Always dimension left to right, so that the offset puts the
+            // dimension outside of the plan.
+            let (a, b) = (pair[0].position(), pair[1].position());
+            let (left, right) = (a.min(b), a.max(b));
+            plan.append(dimension(P2::new(left, 0.), P2::new(right, 0.)));
+        }
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![self.scale_bar()?, body, plan, profile],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::LinesPlan, doc)
+    }
+
+    /// Flatten the planks and lay them out in an svg document. Each
+    /// plank's top edge is labeled with its bevel at each station, its
+    /// gains are marked with dashed lines, and its lap is marked with
+    /// holes for its fasteners, if their spacing was configured.
     pub fn draw_planks(&self) -> Result<SvgDoc, LapstrakeError> {
+        // An eighth of an inch across.
+        const FASTENER_HOLE_RADIUS: f32 = 1. / 192.;
+        let palette = self.theme.palette();
         let mut doc = SvgDoc::new();
-        for plank in &self.get_flattened_planks()? {
-            doc.append(plank.render_2d());
+        let planks = self.get_planks()?;
+        let flattened = FlattenedPlank::flatten_planks(planks.clone())?;
+        let bevels = self.get_bevels()?;
+        let mut outlines = SvgGroup::new();
+        let mut labels = SvgGroup::new();
+        let mut marks = SvgGroup::new();
+        let mut holes = SvgGroup::new();
+        for (i, (plank, flat)) in planks.iter().zip(&flattened).enumerate() {
+            outlines.append(flat.render_2d());
+            for bevel in bevels.iter().filter(|bevel| bevel.plank == i + 1) {
+                let index = plank.top_index_near(bevel.position)?;
+                let label = format!("{:.1} deg", bevel.angle);
+                labels.append(flat.label_top_edge(index, &label));
+            }
+            if let Some(upper) = planks.get(i + 1) {
+                for line in plank.gain_lines(upper, flat)? {
+                    marks.append(
+                        SvgPath::new(line)
+                            .stroke(palette.grid, 0.01)
+                            .style(PathStyle2::Dashed),
+                    );
+                }
+            }
+            // The garboard is fastened to the keel, not to another plank.
+            let spacing = if i > 0 { self.fastener_spacing } else { None };
+            if let Some(spacing) = spacing {
+                let inset = self.fastener_inset;
+                for pos in flat.fastener_positions(spacing, inset) {
+                    holes.append(
+                        SvgCircle::new(pos, FASTENER_HOLE_RADIUS)
+                            .stroke(palette.outline, 0.005),
+                    );
+                }
+            }
+        }
+        doc.append(outlines.layer(Layer::Planks.name()));
+        doc.append(marks.layer(Layer::Marks.name()));
+        doc.append(holes.layer(Layer::Holes.name()));
+        doc.append(labels.layer(Layer::Labels.name()));
+        self.run_drawing_passes(Drawing::Planks, doc)
+    }
+
+    // The body plan: the stations' cross-sections, with some on each side
+    // of the centerline, as chosen by `self.body_plan`.
+    fn body_plan_view(&self) -> Result<SvgGroup, LapstrakeError> {
+        let stroke = 0.02;
+        let palette = self.theme.palette();
+        let mut grid = SvgGroup::new();
+        grid.append_vec(self.draw_height_breadth_grid(stroke));
+        let mut labels = SvgGroup::new();
+        labels.append_vec(self.label_height_breadth_grid());
+        let mut stations = SvgGroup::new();
+        let count = self.stations.len();
+        let half = (count as f32) / 2.;
+        let midships = self.midships();
+        for (i, station) in self.stations.iter().enumerate() {
+            let color = palette.station(i, count);
+            // Which sides to draw it on: unreflected is to the right.
+            let sides = match self.body_plan {
+                BodyPlan::Split => vec![(i as f32) >= half],
+                BodyPlan::Traditional => vec![station.position() > midships],
+                BodyPlan::Full => vec![false, true],
+            };
+            for reflect in sides {
+                let mut samples: Vec<P3> = station.spline.sample(None)?;
+                let mut points: Vec<P3> = station.points.clone();
+                if reflect {
+                    samples = reflect3(Axis::Y, &samples);
+                    points = reflect3(Axis::Y, &points);
+                }
+                stations.append(
+                    SvgPath::new(project_points(Axis::X, &samples))
+                        .curved(CURVE_TOLERANCE)
+                        .stroke(color, stroke)
+                        .style(PathStyle2::Line),
+                );
+                stations.append(
+                    SvgPath::new(project_points(Axis::X, &points))
+                        .stroke(color, stroke)
+                        .style(PathStyle2::Dots),
+                );
+            }
+        }
+        let mut group = SvgGroup::new();
+        group.append(grid.layer(Layer::Grid.name()));
+        group.append(stations.layer(Layer::Stations.name()));
+        group.append(labels.layer(Layer::Labels.name()));
+        Ok(group)
+    }
+
+    // The half-breadth plan: the sheer and waterlines, seen from above.
+    fn waterline_view(&self) -> Result<SvgGroup, LapstrakeError> {
+        let stroke = 0.02;
+        let palette = self.theme.palette();
+        let mut group = SvgGroup::new();
+        let sheer = self.get_line(1.0)?.sample(None)?;
+        group.append(
+            SvgPath::new(project_points(Axis::Z, &sheer))
+                .curved(CURVE_TOLERANCE)
+                .stroke(palette.outline, stroke)
+                .style(PathStyle2::Line),
+        );
+        for &height in &self.heights {
+            let waterline = match self.get_waterline(height) {
+                Ok(waterline) => waterline.sample(None)?,
+                Err(_) => {
+                    println!(
+                        concat!(
+                            "Warning: skipping the waterline at height ",
+                            "{:.2} ft, which crosses too few stations."
+                        ),
+                        height
+                    );
+                    continue;
+                }
+            };
+            group.append(
+                SvgPath::new(project_points(Axis::Z, &waterline))
+                    .curved(CURVE_TOLERANCE)
+                    .stroke(palette.highlight, stroke)
+                    .style(PathStyle2::Line),
+            );
+        }
+        // Only the lines go on the lines layer, so that the station marks
+        // and dimensions added to the view later land on their own.
+        let mut view = SvgGroup::new();
+        view.append(group.layer(Layer::Lines.name()));
+        Ok(view)
+    }
+
+    // The profile: the sheer, keel, and buttock lines, seen from the side.
+    fn profile_view(&self) -> Result<SvgGroup, LapstrakeError> {
+        let stroke = 0.02;
+        let palette = self.theme.palette();
+        let mut group = SvgGroup::new();
+        let sheer = self.get_line(1.0)?.sample(None)?;
+        group.append(
+            SvgPath::new(project_points(Axis::Y, &sheer))
+                .curved(CURVE_TOLERANCE)
+                .stroke(palette.outline, stroke)
+                .style(PathStyle2::Line),
+        );
+        group.append(
+            SvgPath::new(self.keel_line()?)
+                .stroke(palette.outline, stroke)
+                .style(PathStyle2::Line),
+        );
+        for &breadth in &self.breadths {
+            let buttock = match self.get_buttock(breadth) {
+                Ok(buttock) => buttock.sample(None)?,
+                Err(_) => {
+                    println!(
+                        concat!(
+                            "Warning: skipping the buttock at breadth ",
+                            "{:.2} ft, which crosses too few stations."
+                        ),
+                        breadth
+                    );
+                    continue;
+                }
+            };
+            group.append(
+                SvgPath::new(project_points(Axis::Y, &buttock))
+                    .curved(CURVE_TOLERANCE)
+                    .stroke(palette.highlight, stroke)
+                    .style(PathStyle2::Line),
+            );
+        }
+        let mut view = SvgGroup::new();
+        view.append(group.layer(Layer::Lines.name()));
+        Ok(view)
+    }
+
+    // Let any registered custom passes add to a finished drawing, and
+    // give it the title block.
+    pub(crate) fn run_drawing_passes(
+        &self,
+        drawing: Drawing,
+        doc: SvgDoc,
+    ) -> Result<SvgDoc, LapstrakeError> {
+        let mut doc = self.with_title_block(doc);
+        for pass in &self.drawing_passes {
+            pass.draw(drawing, self, &mut doc)?;
         }
         Ok(doc)
     }
 
+    // Give a finished drawing the title block from the config sheet, if
+    // it asks for one, to be drawn when the drawing is saved.
+    pub(crate) fn with_title_block(&self, mut doc: SvgDoc) -> SvgDoc {
+        if let Some(ref block) = self.title_block {
+            doc.set_title_block(block.clone());
+        }
+        doc
+    }
+
     pub fn draw_height_breadth_grid(&self, stroke: f32) -> Vec<SvgPath> {
         // TODO don't draw extra height lines
         // TODO generalize for different views
-        let color = SvgColor::DarkGrey;
+        let palette = self.theme.palette();
+        let color = palette.grid;
+        // Following lines plan convention, heights are dashed and
+        // breadths are dash-dotted, so they can be told apart where
+        // they cross.
+        let height_style = PathStyle2::Dashed;
+        let breadth_style = PathStyle2::DashDot;
         let style = PathStyle2::Line;
 
         let min_x = self.min_coord(Axis::Y);
@@ -144,26 +1076,86 @@ impl Hull {
             lines.push(
                 SvgPath::new(reflect2(Axis::X, &line))
                     .stroke(color, stroke)
-                    .style(style),
+                    .style(height_style),
+            );
+            lines.push(
+                SvgPath::new(line)
+                    .stroke(color, stroke)
+                    .style(height_style),
             );
-            lines.push(SvgPath::new(line).stroke(color, stroke).style(style));
         }
         for &breadth in &self.breadths {
             let line = vec![P2::new(breadth, min_y), P2::new(breadth, max_y)];
             lines.push(
                 SvgPath::new(reflect2(Axis::X, &line))
                     .stroke(color, stroke)
+                    .style(breadth_style),
+            );
+            lines.push(
+                SvgPath::new(line)
+                    .stroke(color, stroke)
+                    .style(breadth_style),
+            );
+        }
+        for &(height, breadth) in &self.diagonals {
+            let line = vec![P2::new(0., height), P2::new(breadth, 0.)];
+            lines.push(
+                SvgPath::new(reflect2(Axis::X, &line))
+                    .stroke(palette.highlight, stroke)
+                    .style(style),
+            );
+            lines.push(
+                SvgPath::new(line)
+                    .stroke(palette.highlight, stroke)
                     .style(style),
             );
-            lines.push(SvgPath::new(line).stroke(color, stroke).style(style));
         }
         lines
     }
 
+    /// Label the lines drawn by `draw_height_breadth_grid()` in the
+    /// margin, with the measurements they were given in the spec sheets:
+    /// "WL 1-6-0" for a waterline (height), and "BL 0-9-0" for a buttock
+    /// line (breadth).
+    pub fn label_height_breadth_grid(&self) -> Vec<SvgText> {
+        const FONT_SIZE: f32 = 0.2;
+        let color = self.theme.palette().label;
+        let max_x = self.max_coord(Axis::Y);
+        let max_y = self.max_coord(Axis::Z);
+        let label = |lines: String, pos: P2| SvgText {
+            lines: vec![lines],
+            pos: pos,
+            color: color,
+            size: FONT_SIZE,
+            style: TextStyle::default(),
+        };
+
+        let mut labels = Vec::new();
+        // Waterlines are labeled off their left ends, and buttock lines
+        // off their ends past the sheer, like station names.
+        for &height in &self.heights {
+            let name = format!("WL {:?}", self.units.from_feet(height));
+            let pos = P2::new(-max_x - FONT_SIZE / 2., height);
+            labels.push(
+                label(name, pos).anchored(Align::End, Baseline::Central),
+            );
+        }
+        for &breadth in &self.breadths {
+            let name = format!("BL {:?}", self.units.from_feet(breadth));
+            let pos = P2::new(breadth, max_y + FONT_SIZE / 2.);
+            labels.push(
+                label(name, pos)
+                    .rotated(90.)
+                    .anchored(Align::Start, Baseline::Central),
+            );
+        }
+        labels
+    }
+
     /// Construct a station at the given for-aft position, then render it.
     pub fn render_station_at(
         &self,
-        posn: Feet,
+        posn: Length,
     ) -> Result<Tree, LapstrakeError> {
         let station = self.hallucinate_station(posn)?;
         let path = ScadPath::new(station.points.clone())
@@ -175,44 +1167,139 @@ impl Hull {
 
     /// Render all of this hull's stations.
     pub fn render_stations(&self) -> Result<Tree, LapstrakeError> {
-        let mut trees = Vec::new();
-        for station in &self.stations {
-            trees.push(
-                ScadPath::new(station.points.clone())
-                    .stroke(0.1)
-                    .show_points()
-                    .link(PathStyle3::Line)?,
-            )
-        }
-        Ok(Tree::union(trees))
+        let stations = self.stations.iter().map(|s| s.points.clone());
+        render_station_points(stations.collect())
     }
 
     pub fn render_planks(&self) -> Result<Tree, LapstrakeError> {
-        // Get renderings for the planks.
-        let mut plank_renderings = vec![];
-        for plank in &self.get_planks()? {
-            plank_renderings.push(plank.render_3d()?);
+        render_plank_lines(&self.get_planks()?)
+    }
+
+    /// Render the planks as solids of their configured thickness, each
+    /// lapped over the one below it, and beveled to fit the one above.
+    pub fn render_solid_planks(&self) -> Result<Tree, LapstrakeError> {
+        let planks = self.get_planks()?;
+        let mut renderings = vec![];
+        for (i, plank) in planks.iter().enumerate() {
+            renderings.push(plank.render_solid(i > 0, planks.get(i + 1))?);
         }
-        Ok(Tree::union(plank_renderings))
+        Ok(Tree::union(renderings))
+    }
+
+    /// Render the keel, the sheer, and the wale (if it was given) as
+    /// lines running fore and aft along the starboard side.
+    pub fn render_longitudinals(&self) -> Result<Tree, LapstrakeError> {
+        render_lines(self.longitudinal_lines()?)
+    }
+
+    // The points along the keel, sheer, and wale.
+    fn longitudinal_lines(&self) -> Result<Vec<Vec<P3>>, LapstrakeError> {
+        let mut lines = vec![self.get_line(0.)?, self.get_line(1.)?];
+        lines.extend(self.wale_line()?);
+        lines.iter().map(|line| line.sample(None)).collect()
     }
 
     pub fn render_half_wireframe(&self) -> Result<Tree, LapstrakeError> {
         // Render the planks & hull stations on one side
-        Ok(union![self.render_planks()?, self.render_stations()?])
+        Ok(union![
+            self.render_planks()?,
+            self.render_stations()?,
+            self.render_longitudinals()?
+        ])
+    }
+
+    /// Like `render_half_wireframe()`, but with the stations and planks
+    /// mirrored across the centerline, to show the whole boat.
+    pub fn render_full_wireframe(&self) -> Result<Tree, LapstrakeError> {
+        let planks = self.get_planks()?;
+        let port_planks: Vec<Plank> =
+            planks.iter().map(|p| p.reflect(Axis::Y)).collect();
+        let port_stations = self
+            .stations
+            .iter()
+            .map(|s| reflect3(Axis::Y, &s.points))
+            .collect();
+        let port_lines = self
+            .longitudinal_lines()?
+            .iter()
+            .map(|line| reflect3(Axis::Y, line))
+            .collect();
+        Ok(union![
+            render_plank_lines(&planks)?,
+            render_plank_lines(&port_planks)?,
+            self.render_stations()?,
+            render_station_points(port_stations)?,
+            self.render_longitudinals()?,
+            render_lines(port_lines)?
+        ])
+    }
+
+    /// Render the outline of the waterplane at the given height above
+    /// base, reaching a little past the hull on every side, so that its
+    /// trim and freeboard can be checked against the wireframe.
+    pub fn render_waterplane(
+        &self,
+        height: f32,
+    ) -> Result<Tree, LapstrakeError> {
+        let margin = 0.1 * self.max_coord(Axis::Y);
+        let fore = self.min_coord(Axis::X) - margin;
+        let aft = self.max_coord(Axis::X) + margin;
+        let side = self.max_coord(Axis::Y) + margin;
+        let corners = vec![
+            P3::new(fore, side, height),
+            P3::new(aft, side, height),
+            P3::new(aft, -side, height),
+            P3::new(fore, -side, height),
+            P3::new(fore, side, height),
+        ];
+        Ok(ScadPath::new(corners)
+            .stroke(SCAD_STROKE)
+            .link(PathStyle3::Line)?)
+    }
+
+    /// Loft a closed surface through the whole hull, both sides of it,
+    /// capped off across the sheer and at the ends.
+    pub fn render_surface(&self) -> Result<ScadSurface, LapstrakeError> {
+        const GIRTH_SAMPLES: usize = 20;
+        const LENGTH_SAMPLES: usize = 60;
+        let grid = self.surface_grid(GIRTH_SAMPLES, LENGTH_SAMPLES)?;
+        let mut rings = vec![];
+        for i in 0..LENGTH_SAMPLES {
+            let starboard = grid.iter().map(|row| row[i]).collect();
+            rings.push(both_sides(starboard));
+        }
+        // The rings must advance towards +x.
+        if rings[0][0].x > rings[LENGTH_SAMPLES - 1][0].x {
+            rings.reverse();
+        }
+        ScadSurface::loft(&rings)
+    }
+
+    /// Like `render_half_wireframe()`, but with solid planks.
+    pub fn render_half_solid(&self) -> Result<Tree, LapstrakeError> {
+        Ok(union![self.render_solid_planks()?, self.render_stations()?])
     }
 }
 
 impl Station {
-    fn get_cross_section_path(&self) -> Result<SvgPath, LapstrakeError> {
+    // The points around both halves of the cross-section, from the
+    // starboard sheer down and back up to the port sheer, shrunk by
+    // `inset` all around (including across the sheer).
+    pub(crate) fn cross_section_points(
+        &self,
+        inset: f32,
+    ) -> Result<Vec<P2>, LapstrakeError> {
         // Draw right and left halves of cross-section
         let mut points: Vec<_> =
             self.spline.sample(None)?.into_iter().rev().collect();
         let left = reflect3(Axis::Y, &points);
         points.extend(left.iter().rev());
-        Ok(SvgPath::new(project_points(Axis::X, &points))
-            .stroke(SvgColor::Black, 0.02)
-            .style(PathStyle2::Line)
-            .close())
+        let points = project_points(Axis::X, &points);
+        if practically_zero(inset) {
+            Ok(points)
+        } else {
+            Ok(offset_polygon(&points, -inset))
+        }
     }
 
     pub fn render_3d(&self) -> Result<Tree, LapstrakeError> {
@@ -223,3 +1310,103 @@ impl Station {
         Ok(path)
     }
 }
+
+// Render each station as a line through its reference points.
+fn render_station_points(
+    stations: Vec<Vec<P3>>,
+) -> Result<Tree, LapstrakeError> {
+    let mut trees = Vec::new();
+    for points in stations {
+        trees.push(
+            ScadPath::new(points)
+                .stroke(0.1)
+                .show_points()
+                .link(PathStyle3::Line)?,
+        )
+    }
+    Ok(Tree::union(trees))
+}
+
+// Render each line as a thin path, without its points.
+fn render_lines(lines: Vec<Vec<P3>>) -> Result<Tree, LapstrakeError> {
+    let mut trees = Vec::new();
+    for points in lines {
+        trees.push(
+            ScadPath::new(points)
+                .stroke(SCAD_STROKE)
+                .link(PathStyle3::Line)?,
+        )
+    }
+    Ok(Tree::union(trees))
+}
+
+// Render the edges of the planks, and the lands where each one overlaps
+// the one below it.
+fn render_plank_lines(planks: &[Plank]) -> Result<Tree, LapstrakeError> {
+    let mut plank_renderings = vec![];
+    for plank in planks {
+        plank_renderings.push(plank.render_3d()?);
+    }
+    for pair in planks.windows(2) {
+        plank_renderings.push(pair[0].render_land(&pair[1])?);
+    }
+    Ok(Tree::union(plank_renderings))
+}
+
+// Draw a tick into a frame template from the point `edge` on its edge,
+// where its outward normal is `normal`, labeled with the bevel angle
+// there.
+fn mark_bevel(
+    edge: P2,
+    normal: V2,
+    angle: f32,
+    palette: &Palette,
+) -> SvgGroup {
+    const TICK: f32 = 0.05;
+    let mut group = SvgGroup::new();
+    group.append(Layer::Marks.wrap(
+        SvgPath::new(vec![edge, edge - normal * TICK])
+            .stroke(palette.grid, 0.005)
+            .style(PathStyle2::Line),
+    ));
+    group.append(Layer::Labels.wrap(
+        SvgText {
+            lines: vec![format!("{:.1} deg", angle)],
+            pos: edge - normal * (TICK * 1.5),
+            color: palette.label,
+            size: TICK / 2.,
+            style: TextStyle::default(),
+        }.anchored(Align::Middle, Baseline::Central),
+    ));
+    group
+}
+
+// Draw a labeled line across a plan or profile view, at a station's
+// fore-aft position.
+fn mark_station(
+    group: &mut SvgGroup,
+    station: &Station,
+    low: f32,
+    high: f32,
+    palette: &Palette,
+) {
+    const FONT_SIZE: f32 = 0.25;
+    let x = station.position();
+    group.append(Layer::Grid.wrap(
+        SvgPath::new(vec![P2::new(x, low), P2::new(x, high)])
+            .stroke(palette.faint, 0.01)
+            .style(PathStyle2::Line),
+    ));
+    // Run the name on past the end of the line, so it stays clear of
+    // the drawing and of its neighbors' names.
+    group.append(Layer::Labels.wrap(
+        SvgText {
+            lines: vec![station.name.clone()],
+            pos: P2::new(x, high + FONT_SIZE / 2.),
+            color: palette.label,
+            size: FONT_SIZE,
+            style: TextStyle::default(),
+        }.rotated(90.)
+            .anchored(Align::Start, Baseline::Central),
+    ));
+}
@@ -0,0 +1,75 @@
+//! Dump the lofted hull's geometry as json, for other tools to use
+//! without having to parse the drawings.
+
+use scad_dots::utils::{P2, P3};
+use serde_json;
+
+use error::LapstrakeError;
+use hull::Hull;
+
+/// The geometry of a lofted hull, in feet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HullDump {
+    pub stations: Vec<StationDump>,
+    /// Where the wale crosses each station, as (x, z) points.
+    pub wale: Vec<P2>,
+    pub planks: Vec<PlankDump>,
+}
+
+/// One cross section of the hull.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationDump {
+    pub name: String,
+    /// Points sampled along the station's spline, from the keel up to
+    /// the sheer.
+    pub points: Vec<P3>,
+}
+
+/// One plank, both in place on the hull and flattened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlankDump {
+    /// Points sampled along the plank's bottom edge on the hull.
+    pub bottom_edge: Vec<P3>,
+    /// Points sampled along the plank's top edge on the hull.
+    pub top_edge: Vec<P3>,
+    /// The outline of the flattened plank, as laid out in the plank
+    /// drawing: along the top edge, then back along the bottom edge.
+    pub flattened_outline: Vec<P2>,
+}
+
+impl Hull {
+    /// Collect the hull's stations, wale, and planks.
+    pub fn dump(&self) -> Result<HullDump, LapstrakeError> {
+        let stations = self
+            .stations
+            .iter()
+            .map(|station| {
+                Ok(StationDump {
+                    name: station.name.clone(),
+                    points: station.spline.sample(None)?,
+                })
+            }).collect::<Result<_, LapstrakeError>>()?;
+        let flattened = self.get_flattened_planks()?;
+        let planks = self
+            .get_planks()?
+            .iter()
+            .zip(&flattened)
+            .map(|(plank, flat)| {
+                Ok(PlankDump {
+                    bottom_edge: plank.bottom_line.sample(None)?,
+                    top_edge: plank.top_line.sample(None)?,
+                    flattened_outline: flat.get_outline(),
+                })
+            }).collect::<Result<_, LapstrakeError>>()?;
+        Ok(HullDump {
+            stations: stations,
+            wale: self.wale.clone(),
+            planks: planks,
+        })
+    }
+
+    /// Dump the hull's geometry as pretty-printed json.
+    pub fn to_json(&self) -> Result<String, LapstrakeError> {
+        Ok(serde_json::to_string_pretty(&self.dump()?)?)
+    }
+}
@@ -0,0 +1,159 @@
+//! Export drawings as DXF files, for CNC routers and laser cutters.
+//!
+//! This writes the simple R12 flavor of DXF, which nearly every CAM
+//! program can read. Coordinates are in inches, and the y axis is
+//! flipped so that the drawing comes out the same way up as the svg.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use scad_dots::utils::P2;
+
+use error::{LapstrakeError, ResultExt};
+use render_2d::{
+    Align, Baseline, Canvas, Export, Stroke, SvgColor, SvgDoc, TextStyle,
+};
+
+/// Export drawings as DXF files, with polylines in inches.
+pub struct DxfExport;
+
+impl Export for DxfExport {
+    fn extension(&self) -> &'static str {
+        "dxf"
+    }
+
+    fn export(
+        &self,
+        mut doc: SvgDoc,
+        filename: &Path,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", filename);
+        doc.add_title_block(scale_from_feet)?;
+        let mut dxf = Dxf::new(scale_from_feet);
+        doc.render_to(&mut dxf);
+        fs::write(filename, dxf.finish())
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
+struct Dxf {
+    entities: String,
+    // Inches per foot of the input drawing.
+    scale: f32,
+    current_path: Vec<P2>,
+}
+
+impl Dxf {
+    fn new(scale_from_feet: f32) -> Dxf {
+        Dxf {
+            entities: String::new(),
+            scale: scale_from_feet * 12.,
+            current_path: vec![],
+        }
+    }
+
+    // Write one group code and its value.
+    fn pair<T: ::std::fmt::Display>(&mut self, code: u32, value: T) {
+        writeln!(self.entities, "{}\n{}", code, value).unwrap();
+    }
+
+    // Write a point, converted to inches, with the group codes for its x
+    // and y coordinates.
+    fn point(&mut self, x_code: u32, pos: P2) {
+        let (x, y) = (pos.x * self.scale, -pos.y * self.scale);
+        self.pair(x_code, x);
+        self.pair(x_code + 10, y);
+    }
+
+    fn polyline(&mut self, points: &[P2], closed: bool) {
+        self.pair(0, "POLYLINE");
+        self.pair(8, 0);
+        self.pair(66, 1);
+        self.pair(70, if closed { 1 } else { 0 });
+        for &point in points {
+            self.pair(0, "VERTEX");
+            self.pair(8, 0);
+            self.point(10, point);
+        }
+        self.pair(0, "SEQEND");
+        self.pair(8, 0);
+    }
+
+    fn finish(self) -> String {
+        let mut dxf = String::new();
+        // Declare that the units are inches.
+        dxf.push_str("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n1\n0\nENDSEC\n");
+        dxf.push_str("0\nSECTION\n2\nENTITIES\n");
+        dxf.push_str(&self.entities);
+        dxf.push_str("0\nENDSEC\n0\nEOF\n");
+        dxf
+    }
+}
+
+impl Canvas for Dxf {
+    fn move_to(&mut self, pos: P2) {
+        self.current_path = vec![pos];
+    }
+
+    fn line_to(&mut self, pos: P2) {
+        self.current_path.push(pos);
+    }
+
+    fn end_path(&mut self, closed: bool, _stroke: Stroke) {
+        let points = ::std::mem::replace(&mut self.current_path, vec![]);
+        if points.len() >= 2 {
+            self.polyline(&points, closed);
+        }
+    }
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        _fill: Option<SvgColor>,
+    ) {
+        // Only outlined circles, like alignment holes, are meant to be
+        // cut. Filled ones are just dots marking points.
+        if stroke.is_none() {
+            return;
+        }
+        self.pair(0, "CIRCLE");
+        self.pair(8, 0);
+        self.point(10, center);
+        self.pair(40, radius * self.scale);
+    }
+
+    fn text(
+        &mut self,
+        pos: P2,
+        text: &str,
+        size: f32,
+        _color: SvgColor,
+        style: TextStyle,
+    ) {
+        self.pair(0, "TEXT");
+        self.pair(8, 0);
+        self.point(10, pos);
+        self.pair(40, size * self.scale);
+        self.pair(1, text);
+        // DXF's y axis points up, so its angles are counterclockwise.
+        if style.angle != 0. {
+            self.pair(50, -style.angle);
+        }
+        let horizontal = match style.align {
+            Align::Start => 0,
+            Align::Middle => 1,
+            Align::End => 2,
+        };
+        let vertical = match style.baseline {
+            Baseline::Alphabetic => 0,
+            Baseline::Central => 2,
+        };
+        self.pair(72, horizontal);
+        self.pair(73, vertical);
+        self.point(11, pos);
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt;
 use std::io;
 
 use csv;
+use serde_json;
+use zip::result::ZipError;
 
 use scad_dots::errors::ScadDotsError;
 
@@ -15,6 +17,10 @@ pub enum LapstrakeError {
     Model(ScadDotsError),
     Io(io::Error),
     Csv(csv::Error),
+    Json(serde_json::Error),
+    Zip(ZipError),
+    /// Several independent problems, like every bad cell in a sheet.
+    Multiple(Vec<LapstrakeError>),
     Context {
         message: String,
         cause: Box<LapstrakeError>,
@@ -69,6 +75,25 @@ impl LapstrakeError {
     pub fn load(message: &str) -> Self {
         LapstrakeError::Load(message.to_owned())
     }
+
+    /// Describe the error along with everything that caused it, including
+    /// the causes of errors from other libraries, for printing to the
+    /// user.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut innermost = self;
+        while let LapstrakeError::Context { ref cause, .. } = *innermost {
+            innermost = cause;
+        }
+        // An error wrapped by a variant like `Io` is already part of the
+        // message, but its own causes aren't.
+        let mut cause = innermost.cause().and_then(|err| err.cause());
+        while let Some(err) = cause {
+            report.push_str(&format!("\n  caused by: {}", err));
+            cause = err.cause();
+        }
+        report
+    }
 }
 
 impl Error for LapstrakeError {
@@ -77,6 +102,8 @@ impl Error for LapstrakeError {
             LapstrakeError::Context { ref cause, .. } => Some(cause),
             LapstrakeError::Model(ref cause) => Some(cause),
             LapstrakeError::Io(ref cause) => Some(cause),
+            LapstrakeError::Json(ref cause) => Some(cause),
+            LapstrakeError::Zip(ref cause) => Some(cause),
             _ => None,
         }
     }
@@ -100,6 +127,18 @@ impl From<csv::Error> for LapstrakeError {
     }
 }
 
+impl From<serde_json::Error> for LapstrakeError {
+    fn from(json_err: serde_json::Error) -> LapstrakeError {
+        LapstrakeError::Json(json_err)
+    }
+}
+
+impl From<ZipError> for LapstrakeError {
+    fn from(zip_err: ZipError) -> LapstrakeError {
+        LapstrakeError::Zip(zip_err)
+    }
+}
+
 impl fmt::Display for LapstrakeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -110,10 +149,19 @@ impl fmt::Display for LapstrakeError {
             LapstrakeError::Spline => write!(f, "Spline error"),
             LapstrakeError::Io(err) => write!(f, "Input/output error: {}", err),
             LapstrakeError::Csv(err) => write!(f, "CSV file error: {}", err),
+            LapstrakeError::Json(err) => write!(f, "JSON error: {}", err),
+            LapstrakeError::Zip(err) => write!(f, "Zip archive error: {}", err),
             LapstrakeError::Model(err) => {
                 write!(f, "Failed to make 3d model: {}", err)
             }
             LapstrakeError::General(message) => write!(f, "{}", message),
+            LapstrakeError::Multiple(errors) => {
+                write!(f, "Found {} problems:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {}", error)?;
+                }
+                Ok(())
+            }
             LapstrakeError::Context { message, cause } => {
                 write!(f, "{}\n  caused by: {}", message, cause)
             }
@@ -0,0 +1,113 @@
+//! Export sampled curves as tidy csv files, for plotting and analysis
+//! in other tools (gnuplot, matplotlib, spreadsheets, ...).
+
+use std::path::Path;
+
+use csv;
+use scad_dots::utils::P3;
+
+use error::{LapstrakeError, ResultExt};
+use hull::Hull;
+
+/// One sampled point along one curve.
+#[derive(Debug, Serialize)]
+struct CurvePoint<'a> {
+    /// What kind of curve this is: station, waterline, or sheer.
+    curve: &'a str,
+    /// Which curve of that kind this is.
+    name: &'a str,
+    /// The index of the point along the curve.
+    index: usize,
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+/// The area of one station's cross-section.
+#[derive(Debug, Serialize)]
+struct SectionalArea<'a> {
+    station: &'a str,
+    x: f32,
+    area: f32,
+}
+
+impl Hull {
+    /// Write every station section, waterline, and the sheer line as
+    /// sampled points to `curves_file`, and the sectional area of every
+    /// station to `areas_file`.
+    pub fn export_curves(
+        &self,
+        curves_file: &Path,
+        areas_file: &Path,
+    ) -> Result<(), LapstrakeError> {
+        self.write_curves(curves_file).with_context(|| {
+            format!("Failed to write curve data to {:?}", curves_file)
+        })?;
+        self.write_sectional_areas(areas_file).with_context(|| {
+            format!("Failed to write sectional areas to {:?}", areas_file)
+        })
+    }
+
+    fn write_curves(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)?;
+
+        for station in &self.stations {
+            let samples = station.spline.sample(None)?;
+            write_curve(&mut writer, "station", &station.name, &samples)?;
+        }
+
+        for &height in &self.heights {
+            let mut samples = vec![];
+            for station in &self.stations {
+                if let Some(breadth) = station.breadth_at_height(height)? {
+                    samples.push(P3::new(station.position(), breadth, height));
+                }
+            }
+            let name = format!("{}", height);
+            write_curve(&mut writer, "waterline", &name, &samples)?;
+        }
+
+        let sheer: Vec<P3> = self.stations.iter().map(|s| s.sheer()).collect();
+        write_curve(&mut writer, "sheer", "sheer", &sheer)?;
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_sectional_areas(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)?;
+        for station in &self.stations {
+            writer.serialize(SectionalArea {
+                station: &station.name,
+                x: station.position(),
+                area: station.area()?,
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn write_curve<W>(
+    writer: &mut csv::Writer<W>,
+    curve: &str,
+    name: &str,
+    points: &[P3],
+) -> Result<(), LapstrakeError>
+where
+    W: ::std::io::Write,
+{
+    for (index, point) in points.iter().enumerate() {
+        writer.serialize(CurvePoint {
+            curve: curve,
+            name: name,
+            index: index,
+            x: point.x,
+            y: point.y,
+            z: point.z,
+        })?;
+    }
+    Ok(())
+}
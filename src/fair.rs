@@ -0,0 +1,386 @@
+//! Fair the offsets, like springing a batten through them on the lofting
+//! floor. Each longitudinal line of the data sheet is smoothed, and the
+//! measurements that the smoothed line misses by too much are reported,
+//! and can be replaced by their faired values.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use load::KNUCKLE_MARK;
+use spec::{Data, DataRow, STEM};
+use unit::{Length, Units};
+use util::{practically_zero, sort_by_number};
+
+/// How far a measurement can be off its faired line before it's
+/// reported, if not given: 1/4".
+pub const DEFAULT_FAIRING_TOLERANCE: f32 = 0.25 / 12.;
+
+// The order of the differences that the smoothing penalizes. Penalizing
+// the third differences leaves lines of steadily changing curvature
+// alone, so the ends of the hull aren't flattened.
+const ORDER: usize = 3;
+
+/// A measurement that was too far off the faired line through it, and
+/// the faired value that replaced it.
+#[derive(Debug, Clone)]
+pub struct UnfairMeasurement {
+    pub station: String,
+    /// The section of the data sheet: height, breadth, or diagonal.
+    pub section: &'static str,
+    /// The row of the data sheet that the measurement belongs to.
+    pub line: String,
+    pub measured: Length,
+    pub faired: Length,
+    /// How far the measurement was from the faired line, in feet.
+    pub deviation: f32,
+    /// The units to print the deviation in.
+    pub units: Units,
+}
+
+impl fmt::Display for UnfairMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "station {}, {} {}: measured {:?}, faired {:?} (off by {})",
+            self.station,
+            self.section,
+            self.line,
+            self.measured,
+            self.faired,
+            self.units.from_feet(self.deviation)
+        )
+    }
+}
+
+// What's needed to fair the rows of one data sheet.
+struct Fairing<'a> {
+    units: Units,
+    stations: &'a [String],
+    positions: &'a [Option<f32>],
+    smoothing: f32,
+    tolerance: f32,
+}
+
+impl Data {
+    /// Fair each height, breadth, and diagonal line through the
+    /// stations, and replace every measurement that's more than
+    /// `tolerance` feet off its faired line with the faired value. A
+    /// larger `smoothing` makes for a stiffer batten; it's relative to
+    /// the station spacing, so around 1 suits most hulls. Lines with
+    /// fewer than four measurements are left alone. Returns every
+    /// measurement that was replaced.
+    pub fn fair(
+        &mut self,
+        smoothing: f32,
+        tolerance: f32,
+    ) -> Result<Vec<UnfairMeasurement>, LapstrakeError> {
+        let positions = match self.sheer_positions() {
+            Some(positions) => positions,
+            None => return Ok(vec![]),
+        };
+        let fairing = Fairing {
+            units: self.units,
+            stations: &self.stations,
+            positions: &positions,
+            smoothing,
+            tolerance,
+        };
+        let mut unfair = vec![];
+        for row in &mut self.heights {
+            fairing.fair_row("height", row, &mut unfair)?;
+        }
+        for row in &mut self.breadths {
+            fairing.fair_row("breadth", row, &mut unfair)?;
+        }
+        for row in &mut self.diagonals {
+            fairing.fair_row("diagonal", row, &mut unfair)?;
+        }
+        Ok(unfair)
+    }
+
+    /// Save in the same format as the data.csv spec sheet. Since csv
+    /// files can't be highlighted, each row holding any of the `changed`
+    /// measurements gets a note above it, saying which stations changed.
+    /// An empty Stem column that was only added for the stem's profile is
+    /// left out, since it's added again when the sheet is loaded.
+    pub fn write_csv(
+        &self,
+        file: &Path,
+        changed: &[UnfairMeasurement],
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        // The notes are shorter than the other rows.
+        let mut writer = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        let columns = self.written_columns();
+        let mut header = vec!["Stations".to_string()];
+        header.extend(columns.iter().map(|&i| self.stations[i].clone()));
+        writer.write_record(&header)?;
+
+        let sheet = Sheet {
+            columns: &columns,
+            blank: vec![String::new(); columns.len()],
+            changed,
+        };
+        let w = &mut writer;
+        let (positions, heights) = (&self.positions, &self.heights);
+        sheet.write_rows(w, "FORE-AFT POSITION", "", positions, &[])?;
+        let knuckles = &self.knuckle_breadths;
+        sheet.write_rows(w, "HEIGHT", "height", heights, knuckles)?;
+        let (breadths, knuckles) = (&self.breadths, &self.knuckle_heights);
+        sheet.write_rows(w, "BREADTH", "breadth", breadths, knuckles)?;
+        if !self.diagonals.is_empty() {
+            let diagonals = &self.diagonals;
+            sheet.write_rows(w, "DIAGONAL", "diagonal", diagonals, &[])?;
+        }
+        if !self.stem_profile.is_empty() {
+            let mut record = vec!["STEM PROFILE".to_string()];
+            record.extend(sheet.blank.iter().cloned());
+            w.write_record(&record)?;
+            for &(height, position) in &self.stem_profile {
+                w.write_record(&[
+                    format!("{:?}", height),
+                    format!("{:?}", position),
+                ])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    // The indices of the stations to write columns for: all of them,
+    // except for a Stem column without any measurements when the stem's
+    // profile is given.
+    fn written_columns(&self) -> Vec<usize> {
+        let added_stem = |i: usize| {
+            !self.stem_profile.is_empty()
+                && self.stations[i] == STEM
+                && self
+                    .positions
+                    .iter()
+                    .chain(&self.breadths)
+                    .all(|row| row.1[i].is_none())
+                && self.heights.iter().all(|row| row.1[i].is_none())
+                && self.diagonals.iter().all(|row| row.1[i].is_none())
+        };
+        (0..self.stations.len()).filter(|&i| !added_stem(i)).collect()
+    }
+}
+
+// How to write the rows of the data sheet: which `columns` to write, a
+// `blank` cell for each of them, and the measurements that were
+// `changed`.
+struct Sheet<'a> {
+    columns: &'a [usize],
+    blank: Vec<String>,
+    changed: &'a [UnfairMeasurement],
+}
+
+impl<'a> Fairing<'a> {
+    fn fair_row<T: fmt::Display>(
+        &self,
+        section: &'static str,
+        row: &mut DataRow<T>,
+        unfair: &mut Vec<UnfairMeasurement>,
+    ) -> Result<(), LapstrakeError> {
+        // The known points along this line, as (station index, position,
+        // value), from fore to aft.
+        let mut known: Vec<(usize, f32, f32)> = row
+            .1
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| match (self.positions[i], *value) {
+                (Some(x), Some(value)) => Some((i, x, value.into())),
+                _ => None,
+            })
+            .collect();
+        if known.len() <= ORDER {
+            return Ok(());
+        }
+        sort_by_number(&mut known, |k| k.1)?;
+        let xs: Vec<f32> = known.iter().map(|k| k.1).collect();
+        let ys: Vec<f32> = known.iter().map(|k| k.2).collect();
+        if xs.windows(2).any(|pair| practically_zero(pair[1] - pair[0])) {
+            return Ok(());
+        }
+
+        let faired = smooth(&xs, &ys, self.smoothing);
+        for (&(i, _, y), &value) in known.iter().zip(&faired) {
+            let deviation = (y - value).abs();
+            if deviation <= self.tolerance {
+                continue;
+            }
+            let faired = self.units.from_feet(value);
+            unfair.push(UnfairMeasurement {
+                station: self.stations[i].clone(),
+                section,
+                line: row.0.to_string(),
+                measured: row.1[i].expect("known measurement"),
+                faired,
+                deviation,
+                units: self.units,
+            });
+            row.1[i] = Some(faired);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Sheet<'a> {
+    // Write one section of the data sheet: its name, followed by a blank
+    // cell for each column, then its rows. The rows whose lines are among
+    // the `knuckles` are marked as such.
+    fn write_rows<T: fmt::Display + PartialEq, W: io::Write>(
+        &self,
+        writer: &mut csv::Writer<W>,
+        name: &str,
+        section: &str,
+        rows: &[DataRow<T>],
+        knuckles: &[T],
+    ) -> Result<(), LapstrakeError> {
+        let mut record = vec![name.to_string()];
+        record.extend(self.blank.iter().cloned());
+        writer.write_record(&record)?;
+        for &(ref head, ref values) in rows {
+            let line = head.to_string();
+            let faired: Vec<&str> = self
+                .changed
+                .iter()
+                .filter(|m| m.section == section && m.line == line)
+                .map(|m| m.station.as_str())
+                .collect();
+            if !faired.is_empty() {
+                let note =
+                    format!("# Faired at stations: {}", faired.join(" "));
+                writer.write_record(&[note])?;
+            }
+            let mut record = if knuckles.contains(head) {
+                vec![format!("{} {}", line, KNUCKLE_MARK)]
+            } else {
+                vec![line]
+            };
+            record.extend(self.columns.iter().map(|&i| match values[i] {
+                Some(value) => format!("{:?}", value),
+                None => "x".into(),
+            }));
+            writer.write_record(&record)?;
+        }
+        Ok(())
+    }
+}
+
+// Smooth the values `ys` at the increasing positions `xs`, by finding
+// the values that best balance staying close to the originals against
+// having small `ORDER`th derivatives, weighted by `smoothing`. This is a
+// discrete smoothing spline (a Whittaker smoother).
+fn smooth(xs: &[f32], ys: &[f32], smoothing: f32) -> Vec<f32> {
+    let n = xs.len();
+    let spacing = (xs[n - 1] - xs[0]) / (n - 1) as f32;
+    // Scale the derivatives by the station spacing, so that the
+    // smoothing doesn't depend on the size of the hull.
+    let scale = (1..ORDER + 1).product::<usize>() as f32
+        * spacing.powi(ORDER as i32);
+
+    // Solve (I + smoothing * D^T D) f = y, where each row of D takes a
+    // divided difference of neighboring values.
+    let mut matrix = vec![vec![0.; n]; n];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = 1.;
+    }
+    for start in 0..n - ORDER {
+        let window = &xs[start..start + ORDER + 1];
+        let weights: Vec<f32> = (0..ORDER + 1)
+            .map(|j| {
+                let product: f32 = (0..ORDER + 1)
+                    .filter(|&m| m != j)
+                    .map(|m| window[j] - window[m])
+                    .product();
+                scale / product
+            })
+            .collect();
+        for (j, wj) in weights.iter().enumerate() {
+            for (k, wk) in weights.iter().enumerate() {
+                matrix[start + j][start + k] += smoothing * wj * wk;
+            }
+        }
+    }
+    solve(matrix, ys.to_vec())
+}
+
+// Solve the linear system `matrix * x = rhs` by Gaussian elimination.
+// The matrix must be symmetric and positive definite, so no pivoting is
+// needed.
+fn solve(mut matrix: Vec<Vec<f32>>, mut rhs: Vec<f32>) -> Vec<f32> {
+    let n = rhs.len();
+    for i in 0..n {
+        for j in i + 1..n {
+            let factor = matrix[j][i] / matrix[i][i];
+            if factor == 0. {
+                continue;
+            }
+            for k in i..n {
+                matrix[j][k] -= factor * matrix[i][k];
+            }
+            rhs[j] -= factor * rhs[i];
+        }
+    }
+    let mut x = vec![0.; n];
+    for i in (0..n).rev() {
+        let sum: f32 = (i + 1..n).map(|k| matrix[i][k] * x[k]).sum();
+        x[i] = (rhs[i] - sum) / matrix[i][i];
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec::HeightLine;
+
+    #[test]
+    fn test_smooth() {
+        // A cubic is already fair, so it shouldn't change.
+        let xs = vec![0., 1., 2.5, 3., 4., 6., 7.];
+        let cubic: Vec<f32> = xs.iter().map(|x| 0.02 * x * x * x - x).collect();
+        for (y, f) in cubic.iter().zip(smooth(&xs, &cubic, 1.)) {
+            assert!((y - f).abs() < 0.001);
+        }
+        // A bump should be mostly smoothed away.
+        let mut bumped = vec![1.; 7];
+        bumped[3] = 2.;
+        let faired = smooth(&xs, &bumped, 1.);
+        assert!(faired[3] < 1.5);
+    }
+
+    #[test]
+    fn test_written_columns() {
+        let mm = |x: f32| Some(Length::Millimeters(x));
+        let mut data = Data {
+            units: Units::Millimeters,
+            stations: vec![STEM.into(), "1".into(), "2".into()],
+            positions: vec![(
+                HeightLine::Sheer,
+                vec![None, mm(100.), mm(200.)],
+            )],
+            heights: vec![],
+            breadths: vec![],
+            diagonals: vec![],
+            knuckle_breadths: vec![],
+            knuckle_heights: vec![],
+            stem_profile: vec![],
+        };
+        // The Stem column is kept unless the profile stands in for it.
+        assert_eq!(data.written_columns(), vec![0, 1, 2]);
+        let heel = Length::Millimeters(0.);
+        data.stem_profile = vec![(heel, heel)];
+        assert_eq!(data.written_columns(), vec![1, 2]);
+        data.positions[0].1[0] = mm(0.);
+        assert_eq!(data.written_columns(), vec![0, 1, 2]);
+    }
+}
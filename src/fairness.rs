@@ -0,0 +1,155 @@
+//! Find unfair areas of the hull surface, by comparing its local
+//! curvature to a smoothed version of itself, and save them as a heat
+//! map on a 3d mesh.
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use scad_dots::utils::{distance, P3};
+
+use error::{LapstrakeError, ResultExt};
+use hull::Hull;
+
+// How finely to sample the hull surface.
+const GIRTH_SAMPLES: usize = 30;
+const LENGTH_SAMPLES: usize = 80;
+
+impl Hull {
+    /// Save the hull surface as a .ply mesh, with each vertex colored by
+    /// how far its curvature deviates from the smoothed curvature around
+    /// it: blue is fair, and red is a hot spot. Meshlab and Blender can
+    /// both show the colors.
+    pub fn save_fairness_map(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let grid = self.surface_grid(GIRTH_SAMPLES, LENGTH_SAMPLES)?;
+        let deviation = curvature_deviation(&grid);
+        write_ply(file, &grid, &deviation)
+            .with_context(|| format!("Failed to write mesh {:?}", file))
+    }
+}
+
+// The local curvature at each grid point, minus the average curvature
+// of its neighbors.
+fn curvature_deviation(grid: &[Vec<P3>]) -> Vec<Vec<f32>> {
+    let curvature = map_grid(grid.len(), grid[0].len(), |j, i| {
+        curvature_along(&grid[j], i)
+            + curvature_along(&column(grid, i), j)
+    });
+    let smoothed = map_grid(grid.len(), grid[0].len(), |j, i| {
+        let mut total = 0.;
+        let mut count = 0.;
+        for dj in -1..2 {
+            for di in -1..2 {
+                let (nj, ni) = (j as i32 + dj, i as i32 + di);
+                if nj >= 0
+                    && ni >= 0
+                    && (nj as usize) < grid.len()
+                    && (ni as usize) < grid[0].len()
+                {
+                    total += curvature[nj as usize][ni as usize];
+                    count += 1.;
+                }
+            }
+        }
+        total / count
+    });
+    map_grid(grid.len(), grid[0].len(), |j, i| {
+        (curvature[j][i] - smoothed[j][i]).abs()
+    })
+}
+
+fn map_grid<F>(rows: usize, cols: usize, f: F) -> Vec<Vec<f32>>
+where
+    F: Fn(usize, usize) -> f32,
+{
+    (0..rows)
+        .map(|j| (0..cols).map(|i| f(j, i)).collect())
+        .collect()
+}
+
+fn column(grid: &[Vec<P3>], i: usize) -> Vec<P3> {
+    grid.iter().map(|row| row[i]).collect()
+}
+
+// Estimate the curvature of a polyline at one of its points, using the
+// second difference. The ends borrow the curvature of their neighbors.
+fn curvature_along(points: &[P3], i: usize) -> f32 {
+    if points.len() < 3 {
+        return 0.;
+    }
+    let i = i.max(1).min(points.len() - 2);
+    let (a, b, c) = (points[i - 1], points[i], points[i + 1]);
+    let spacing = (distance(&a, &b) + distance(&b, &c)) / 2.;
+    if spacing <= 0. {
+        return 0.;
+    }
+    let second_difference = (a.coords - b.coords * 2. + c.coords).norm();
+    second_difference / (spacing * spacing)
+}
+
+fn write_ply(
+    file: &Path,
+    grid: &[Vec<P3>],
+    heat: &[Vec<f32>],
+) -> Result<(), LapstrakeError> {
+    let (rows, cols) = (grid.len(), grid[0].len());
+    let max_heat = heat
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0., |max: f32, &h| max.max(h));
+
+    let mut out = BufWriter::new(fs::File::create(file)?);
+    writeln!(out, "ply")?;
+    writeln!(out, "format ascii 1.0")?;
+    writeln!(out, "element vertex {}", rows * cols)?;
+    writeln!(out, "property float x")?;
+    writeln!(out, "property float y")?;
+    writeln!(out, "property float z")?;
+    writeln!(out, "property uchar red")?;
+    writeln!(out, "property uchar green")?;
+    writeln!(out, "property uchar blue")?;
+    writeln!(out, "element face {}", (rows - 1) * (cols - 1))?;
+    writeln!(out, "property list uchar int vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    for j in 0..rows {
+        for i in 0..cols {
+            let p = grid[j][i];
+            let fraction = if max_heat > 0. {
+                heat[j][i] / max_heat
+            } else {
+                0.
+            };
+            let (r, g, b) = heat_color(fraction);
+            writeln!(out, "{} {} {} {} {} {}", p.x, p.y, p.z, r, g, b)?;
+        }
+    }
+    for j in 0..rows - 1 {
+        for i in 0..cols - 1 {
+            let corner = j * cols + i;
+            writeln!(
+                out,
+                "4 {} {} {} {}",
+                corner,
+                corner + 1,
+                corner + cols + 1,
+                corner + cols
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Blue for 0, through green, to red for 1.
+fn heat_color(fraction: f32) -> (u8, u8, u8) {
+    let f = fraction.max(0.).min(1.);
+    let red = (2. * f - 1.).max(0.);
+    let blue = (1. - 2. * f).max(0.);
+    let green = 1. - red - blue;
+    (
+        (255. * red) as u8,
+        (255. * green) as u8,
+        (255. * blue) as u8,
+    )
+}
@@ -0,0 +1,163 @@
+//! Infer omitted measurements in the data sheet, by fairing each
+//! longitudinal line through the stations on either side of the gap.
+
+use std::fmt;
+
+use scad_dots::utils::{P2, P3};
+
+use catmullrom::CentripetalCatmullRom;
+use error::{LapstrakeError, ResultExt};
+use spec::{Data, DataRow, HeightLine};
+use unit::{Length, Units};
+use util::{interpolate_y, sort_by_number};
+
+// How finely to sample the faired line when looking up a missing value.
+const FILL_SAMPLES: usize = 64;
+
+/// A measurement that was omitted from the data sheet, and the value
+/// that was inferred for it.
+#[derive(Debug, Clone)]
+pub struct FilledMeasurement {
+    pub station: String,
+    /// The row of the data sheet that the measurement belongs to.
+    pub line: String,
+    pub value: Length,
+}
+
+impl fmt::Display for FilledMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "station {}, {}: {}",
+            self.station, self.line, self.value
+        )
+    }
+}
+
+impl Data {
+    /// Fill in each omitted height and breadth that lies between two
+    /// given measurements on the same line, by fairing that line through
+    /// its neighboring stations. Omissions past the ends of a line are
+    /// left alone, since the line probably doesn't reach that far.
+    /// Returns every inferred value, so that they can be reviewed.
+    pub fn fill_missing(
+        &mut self,
+    ) -> Result<Vec<FilledMeasurement>, LapstrakeError> {
+        let positions = match self.sheer_positions() {
+            Some(positions) => positions,
+            None => return Ok(vec![]),
+        };
+
+        let mut filled = vec![];
+        let (units, stations) = (self.units, &self.stations);
+        for row in &mut self.heights {
+            fill_row(units, stations, &positions, row, &mut filled)?;
+        }
+        for row in &mut self.breadths {
+            fill_row(units, stations, &positions, row, &mut filled)?;
+        }
+        for row in &mut self.diagonals {
+            fill_row(units, stations, &positions, row, &mut filled)?;
+        }
+        Ok(filled)
+    }
+
+    // The fore-aft position of each station at the sheer, in feet, if
+    // the sheer row was given.
+    pub(crate) fn sheer_positions(&self) -> Option<Vec<Option<f32>>> {
+        self.positions
+            .iter()
+            .find(|&&(line, _)| line == HeightLine::Sheer)
+            .map(|&(_, ref row)| {
+                row.iter().map(|p| p.map(|p| p.into())).collect()
+            })
+    }
+}
+
+fn fill_row<T: fmt::Debug>(
+    units: Units,
+    stations: &[String],
+    positions: &[Option<f32>],
+    row: &mut DataRow<T>,
+    filled: &mut Vec<FilledMeasurement>,
+) -> Result<(), LapstrakeError> {
+    // The known points along this line, as (position, value).
+    let known: Vec<(usize, P2)> = row
+        .1
+        .iter()
+        .enumerate()
+        .filter_map(|(i, value)| match (positions[i], *value) {
+            (Some(x), Some(value)) => Some((i, P2::new(x, value.into()))),
+            _ => None,
+        })
+        .collect();
+
+    for i in 0..row.1.len() {
+        if row.1[i].is_some() {
+            continue;
+        }
+        let x = match positions[i] {
+            Some(x) => x,
+            None => continue,
+        };
+        let value = fair_at(&known, i, x).with_context(|| {
+            format!(
+                "Could not infer the measurement at station {}, {:?}",
+                stations[i], row.0
+            )
+        })?;
+        if let Some(value) = value {
+            let value = units.from_feet(value);
+            row.1[i] = Some(value);
+            filled.push(FilledMeasurement {
+                station: stations[i].clone(),
+                line: format!("{:?}", row.0),
+                value,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Evaluate the line through the known points at the position `x` of
+// station index `i`, using the two known points on either side of it.
+// It's None if there aren't known points on both sides.
+fn fair_at(
+    known: &[(usize, P2)],
+    i: usize,
+    x: f32,
+) -> Result<Option<f32>, LapstrakeError> {
+    let after = match known.iter().position(|&(j, _)| j > i) {
+        Some(after) if after > 0 => after,
+        _ => return Ok(None),
+    };
+    let p1 = known[after - 1].1;
+    let p2 = known[after].1;
+    // Make up control points past the ends of the line, if needed.
+    let p0 = if after >= 2 {
+        known[after - 2].1
+    } else {
+        p1 + (p1 - p2)
+    };
+    let p3 = if after + 1 < known.len() {
+        known[after + 1].1
+    } else {
+        p2 + (p2 - p1)
+    };
+
+    let to_3d = |p: P2| P3::new(p.x, p.y, 0.);
+    let spline = CentripetalCatmullRom::new([
+        to_3d(p0),
+        to_3d(p1),
+        to_3d(p2),
+        to_3d(p3),
+    ]);
+    let mut samples: Vec<P2> = spline
+        .sample(FILL_SAMPLES, true)
+        .into_iter()
+        .map(|p| P2::new(p.x, p.y))
+        .collect();
+    // Stations at the same position leave the spline undefined.
+    sort_by_number(&mut samples, |p| p.x)?;
+    Ok(interpolate_y(&samples, x))
+}
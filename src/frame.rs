@@ -0,0 +1,116 @@
+//! Bevels for sawn frames, whose edges have to be planed to follow the
+//! hull as it grows or shrinks fore and aft of each station.
+
+use std::path::Path;
+
+use csv;
+use scad_dots::utils::{Axis, V2};
+
+use error::LapstrakeError;
+use hull::{Hull, Station};
+use util::{practically_zero, project};
+
+/// How many spaces there are between the points along each frame that
+/// bevels are found at.
+const FRAME_BEVEL_SPACES: usize = 8;
+
+/// How far fore and aft of each station to look at the hull, to find how
+/// it's changing there.
+const FRAME_BEVEL_SPAN: f32 = 0.25;
+
+/// The bevel on the edge of a frame, at one point along a station.
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameBevel {
+    pub station: String,
+    /// The fore-aft position of the station.
+    pub position: f32,
+    /// Where the point is on the station.
+    pub breadth: f32,
+    pub height: f32,
+    /// The bevel angle, in degrees: how far the hull slopes away from
+    /// square to the frame. It's positive where the hull grows fuller
+    /// going aft, and negative where it shrinks.
+    pub angle: f32,
+    /// The outward normal to the station at the point, as (breadth,
+    /// height).
+    #[serde(skip)]
+    pub(crate) normal: V2,
+}
+
+impl Hull {
+    /// Find the bevel along the edge of a frame at the given station, at
+    /// points spaced evenly along it from the keel to the sheer. The
+    /// slope of the hull is measured between hallucinated stations a
+    /// little fore and aft of it.
+    pub fn frame_bevels(
+        &self,
+        station: &Station,
+    ) -> Result<Vec<FrameBevel>, LapstrakeError> {
+        let x = station.position();
+        // Fall back to the station's own position at the ends of the
+        // hull, where there's nothing past it.
+        let near = |x: f32| -> Result<(Station, f32), LapstrakeError> {
+            let posn = self.units.from_feet(x);
+            Ok((self.hallucinate_station(posn)?, x))
+        };
+        let (fore, fore_x) = near(x - FRAME_BEVEL_SPAN).or_else(|_| near(x))?;
+        let (aft, aft_x) = near(x + FRAME_BEVEL_SPAN).or_else(|_| near(x))?;
+        if practically_zero(aft_x - fore_x) {
+            return Err(LapstrakeError::General(format!(
+                "Can't find frame bevels at station {}, because the hull \
+                 doesn't reach past it either way.",
+                station.name
+            )));
+        }
+
+        let mut bevels = vec![];
+        for i in 0..FRAME_BEVEL_SPACES + 1 {
+            let t = i as f32 / FRAME_BEVEL_SPACES as f32;
+            let at = |station: &Station, t: f32| {
+                let t = t.max(0.).min(1.);
+                station.at_t(t).map(|p| project(Axis::X, p).coords)
+            };
+            let step = 0.5 / FRAME_BEVEL_SPACES as f32;
+            let tangent = at(station, t + step)? - at(station, t - step)?;
+            if practically_zero(tangent.norm()) {
+                continue;
+            }
+            // Turned clockwise, since the station runs up from the keel.
+            let normal = V2::new(tangent.y, -tangent.x) / tangent.norm();
+            let grown = (at(&aft, t)? - at(&fore, t)?).dot(&normal);
+            let point = at(station, t)?;
+            bevels.push(FrameBevel {
+                station: station.name.clone(),
+                position: x,
+                breadth: point.x,
+                height: point.y,
+                angle: (grown / (aft_x - fore_x)).atan().to_degrees(),
+                normal: normal,
+            });
+        }
+        Ok(bevels)
+    }
+
+    /// Save a table of the frame bevels at every station as a csv file.
+    pub fn write_frame_bevels(
+        &self,
+        file: &Path,
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)?;
+        for station in &self.stations {
+            let bevels = match self.frame_bevels(station) {
+                Ok(bevels) => bevels,
+                Err(err) => {
+                    println!("Warning: skipping frame bevels: {}", err);
+                    continue;
+                }
+            };
+            for bevel in bevels {
+                writer.serialize(bevel)?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,448 @@
+//! Export drawings as G-code toolpaths, for cutting planks and frames
+//! out of sheet stock on a CNC router.
+//!
+//! Every closed outline in the drawing is cut as a 2.5D contour: the tool
+//! steps down through the stock in several passes, staying outside of
+//! outlines and inside of holes, so that the parts come out the size they
+//! were drawn. Small tabs are left along each outline on the final
+//! passes, to keep the parts from coming loose in the sheet. Open paths
+//! (like grid lines and scale bars), text, and anything on the marks or
+//! labels layers (like the title block's border) are not cut.
+//!
+//! Coordinates are in inches, with the origin at the top left corner of
+//! the drawing and z=0 at the top of the stock.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use scad_dots::utils::{P2, V2};
+
+use draw::Layer;
+use error::{LapstrakeError, ResultExt};
+use render_2d::{Canvas, Export, Stroke, SvgColor, SvgDoc, TextStyle};
+use util::{
+    offset_polygon, polygon_contains, practically_zero, EQUALITY_THRESHOLD,
+};
+
+// How high above the stock to move between cuts, in inches.
+const SAFE_HEIGHT: f32 = 0.25;
+
+/// Export drawings as G-code, cutting each closed outline with an end
+/// mill. All dimensions are real inches, at the drawing's scale, except
+/// for `equality_threshold`.
+#[derive(Debug, Clone, Copy)]
+pub struct GcodeExport {
+    /// How fast to cut, in inches per minute.
+    pub feed: f32,
+    /// How fast to plunge into the stock, in inches per minute.
+    pub plunge_feed: f32,
+    pub spindle_speed: f32,
+    pub tool_diameter: f32,
+    /// The thickness of the stock, which is how deep to cut.
+    pub depth: f32,
+    /// How much deeper to cut on each pass.
+    pub step_down: f32,
+    /// How many tabs to leave along each outline.
+    pub tabs: usize,
+    pub tab_width: f32,
+    pub tab_height: f32,
+    /// How near the end of an outline can be to its start, in feet of the
+    /// drawing, before it's treated as closed.
+    pub equality_threshold: f32,
+}
+
+impl Default for GcodeExport {
+    fn default() -> GcodeExport {
+        GcodeExport {
+            feed: 60.,
+            plunge_feed: 20.,
+            spindle_speed: 18000.,
+            tool_diameter: 0.25,
+            depth: 0.5,
+            step_down: 0.125,
+            tabs: 4,
+            tab_width: 0.375,
+            tab_height: 0.125,
+            equality_threshold: EQUALITY_THRESHOLD,
+        }
+    }
+}
+
+impl Export for GcodeExport {
+    fn extension(&self) -> &'static str {
+        "nc"
+    }
+
+    fn export(
+        &self,
+        mut doc: SvgDoc,
+        filename: &Path,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        if self.tool_diameter <= 0. || self.step_down <= 0. {
+            return Err(LapstrakeError::Draw.context(
+                "the tool diameter and step down must be greater than zero",
+            ));
+        }
+        println!("Saving file: {:?}.", filename);
+        doc.add_title_block(scale_from_feet)?;
+        let scale = scale_from_feet * 12.;
+        let mut shapes = Shapes::new(scale, self.equality_threshold * scale);
+        doc.render_to(&mut shapes);
+        let gcode = self.toolpaths(&shapes);
+        fs::write(filename, gcode)
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
+impl GcodeExport {
+    fn toolpaths(&self, shapes: &Shapes) -> String {
+        let mut gcode = Gcode::new();
+        gcode.line("(Generated by lapstrake)");
+        // Inches, absolute coordinates, xy plane.
+        gcode.line("G20 G90 G17");
+        gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT));
+        gcode.line(&format!("M3 S{:.0}", self.spindle_speed));
+
+        let radius = self.tool_diameter / 2.;
+        // Cut the holes before the outlines, while the parts are still
+        // held firmly by the rest of the sheet.
+        for &(center, hole_radius) in &shapes.circles {
+            gcode.comment("hole");
+            if hole_radius > radius {
+                self.cut_circle(&mut gcode, center, hole_radius - radius);
+            } else {
+                self.drill(&mut gcode, center);
+            }
+        }
+        let (holes, outlines): (Vec<_>, Vec<_>) = shapes
+            .contours
+            .iter()
+            .partition(|contour| shapes.is_hole(contour));
+        for hole in holes {
+            gcode.comment("cutout");
+            // Go clockwise, for conventional milling.
+            let path = offset_polygon(hole, -radius);
+            self.cut_contour(&mut gcode, &orient(path, false), 0);
+        }
+        for outline in outlines {
+            gcode.comment("outline");
+            let path = offset_polygon(outline, radius);
+            self.cut_contour(&mut gcode, &orient(path, true), self.tabs);
+        }
+
+        gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT));
+        gcode.line("M5");
+        gcode.line("M2");
+        gcode.finish()
+    }
+
+    // The depth of each pass, from the shallowest to the full depth.
+    fn pass_depths(&self) -> Vec<f32> {
+        let mut depths = vec![];
+        let mut depth = 0.;
+        while depth < self.depth {
+            depth = (depth + self.step_down).min(self.depth);
+            depths.push(-depth);
+        }
+        depths
+    }
+
+    // Follow a closed toolpath down through the stock. The parts of each
+    // pass that are deeper than the top of the tabs are lifted over them.
+    fn cut_contour(&self, gcode: &mut Gcode, path: &[P2], tabs: usize) {
+        if path.len() < 2 {
+            return;
+        }
+        let mut closed = path.to_vec();
+        closed.push(path[0]);
+        let tab_top = -self.depth + self.tab_height;
+        let tab_spans = tab_spans(&closed, tabs, self.tab_width);
+
+        gcode.rapid(closed[0]);
+        for z in self.pass_depths() {
+            gcode.plunge(z, self.plunge_feed);
+            if z >= tab_top || tab_spans.is_empty() {
+                for &point in &closed[1..] {
+                    gcode.cut(point, self.feed);
+                }
+                continue;
+            }
+            let mut lifted = false;
+            for (point, in_tab) in split_at_spans(&closed, &tab_spans) {
+                gcode.cut(point, self.feed);
+                if in_tab != lifted {
+                    // Cross into or out of a tab.
+                    gcode.plunge(if in_tab { tab_top } else { z }, self.feed);
+                    lifted = in_tab;
+                }
+            }
+        }
+        gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT));
+    }
+
+    // Cut a round hole by going clockwise around it, with full circle
+    // arcs.
+    fn cut_circle(&self, gcode: &mut Gcode, center: P2, radius: f32) {
+        let start = center + V2::new(radius, 0.);
+        gcode.rapid(start);
+        for z in self.pass_depths() {
+            gcode.plunge(z, self.plunge_feed);
+            gcode.line(&format!(
+                "G2 X{:.4} Y{:.4} I{:.4} J0 F{:.1}",
+                start.x, start.y, -radius, self.feed
+            ));
+        }
+        gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT));
+    }
+
+    // Drill a hole that is no bigger than the tool, by pecking straight
+    // down.
+    fn drill(&self, gcode: &mut Gcode, center: P2) {
+        gcode.rapid(center);
+        for z in self.pass_depths() {
+            gcode.plunge(z, self.plunge_feed);
+            gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT / 2.));
+        }
+        gcode.line(&format!("G0 Z{:.4}", SAFE_HEIGHT));
+    }
+}
+
+// The closed shapes in a drawing, collected by replaying it as a canvas.
+// Points are in inches, in machine coordinates.
+struct Shapes {
+    // Inches per foot of the input drawing.
+    scale: f32,
+    // How near two points are before they're the same, in inches.
+    threshold: f32,
+    current_path: Vec<P2>,
+    contours: Vec<Vec<P2>>,
+    circles: Vec<(P2, f32)>,
+    // The layers that the current group is nested in.
+    layers: Vec<String>,
+}
+
+impl Shapes {
+    fn new(scale: f32, threshold: f32) -> Shapes {
+        Shapes {
+            scale: scale,
+            threshold: threshold,
+            current_path: vec![],
+            contours: vec![],
+            circles: vec![],
+            layers: vec![],
+        }
+    }
+
+    // Whether the current group is only marked or labeled, not cut.
+    fn is_uncut(&self) -> bool {
+        self.layers.iter().any(|layer| {
+            layer == Layer::Marks.name() || layer == Layer::Labels.name()
+        })
+    }
+
+    // Convert to inches, flipping the y axis so that the parts come out
+    // the same way up as the svg.
+    fn to_machine(&self, pos: P2) -> P2 {
+        P2::new(pos.x * self.scale, -pos.y * self.scale)
+    }
+
+    // Whether this contour lies inside of some other one.
+    fn is_hole(&self, contour: &[P2]) -> bool {
+        self.contours.iter().any(|other| {
+            other.as_slice() != contour && polygon_contains(other, contour[0])
+        })
+    }
+}
+
+impl Canvas for Shapes {
+    fn move_to(&mut self, pos: P2) {
+        self.current_path = vec![self.to_machine(pos)];
+    }
+
+    fn line_to(&mut self, pos: P2) {
+        let pos = self.to_machine(pos);
+        self.current_path.push(pos);
+    }
+
+    fn end_path(&mut self, closed: bool, _stroke: Stroke) {
+        let mut points = ::std::mem::replace(&mut self.current_path, vec![]);
+        if points.len() < 3 || self.is_uncut() {
+            return;
+        }
+        // Some outlines are drawn by returning to where they started,
+        // instead of being marked as closed.
+        let first = points[0];
+        let returns =
+            (points[points.len() - 1] - first).norm() < self.threshold;
+        if returns {
+            points.pop();
+        }
+        if closed || returns {
+            self.contours.push(points);
+        }
+    }
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        _fill: Option<SvgColor>,
+    ) {
+        // Only outlined circles, like alignment holes, are meant to be
+        // cut. Filled ones are just dots marking points.
+        if stroke.is_some() && !self.is_uncut() {
+            let center = self.to_machine(center);
+            self.circles.push((center, radius * self.scale));
+        }
+    }
+
+    fn text(
+        &mut self,
+        _pos: P2,
+        _text: &str,
+        _size: f32,
+        _color: SvgColor,
+        _style: TextStyle,
+    ) {
+    }
+
+    fn begin_layer(&mut self, name: &str) {
+        self.layers.push(name.to_owned());
+    }
+
+    fn end_layer(&mut self) {
+        self.layers.pop();
+    }
+}
+
+// G-code text, built up one line at a time.
+struct Gcode {
+    text: String,
+}
+
+impl Gcode {
+    fn new() -> Gcode {
+        Gcode {
+            text: String::new(),
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.text, "{}", line).unwrap();
+    }
+
+    fn comment(&mut self, comment: &str) {
+        writeln!(self.text, "({})", comment).unwrap();
+    }
+
+    // Move quickly to above `pos`, at a safe height.
+    fn rapid(&mut self, pos: P2) {
+        writeln!(self.text, "G0 Z{:.4}", SAFE_HEIGHT).unwrap();
+        writeln!(self.text, "G0 X{:.4} Y{:.4}", pos.x, pos.y).unwrap();
+    }
+
+    fn plunge(&mut self, z: f32, feed: f32) {
+        writeln!(self.text, "G1 Z{:.4} F{:.1}", z, feed).unwrap();
+    }
+
+    fn cut(&mut self, pos: P2, feed: f32) {
+        writeln!(self.text, "G1 X{:.4} Y{:.4} F{:.1}", pos.x, pos.y, feed)
+            .unwrap();
+    }
+
+    fn finish(self) -> String {
+        self.text
+    }
+}
+
+// Put the points of a polygon in counter-clockwise order if `ccw`, or
+// else clockwise.
+fn orient(mut polygon: Vec<P2>, ccw: bool) -> Vec<P2> {
+    let n = polygon.len();
+    let mut twice_area = 0.;
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        twice_area += p.x * q.y - q.x * p.y;
+    }
+    if (twice_area > 0.) != ccw {
+        polygon.reverse();
+    }
+    polygon
+}
+
+// The (start, end) distances along a closed path of evenly spaced tabs.
+// The tool's own width is not included, so the tabs come out `width`
+// wide as long as they're on straight parts of the outline.
+fn tab_spans(path: &[P2], tabs: usize, width: f32) -> Vec<(f32, f32)> {
+    let perimeter: f32 = path.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+    if tabs == 0 || perimeter < 2. * width * tabs as f32 {
+        return vec![];
+    }
+    let spacing = perimeter / tabs as f32;
+    (0..tabs)
+        .map(|i| {
+            let center = spacing * (i as f32 + 0.5);
+            (center - width / 2., center + width / 2.)
+        })
+        .collect()
+}
+
+// Walk along a path, adding points where it enters and leaves each span.
+// Returns each point along with whether the path is inside of a span
+// just after it.
+fn split_at_spans(path: &[P2], spans: &[(f32, f32)]) -> Vec<(P2, bool)> {
+    let mut boundaries: Vec<(f32, bool)> = vec![];
+    for &(start, end) in spans {
+        boundaries.push((start, true));
+        boundaries.push((end, false));
+    }
+    let mut boundaries = boundaries.into_iter().peekable();
+
+    let mut points = vec![];
+    let mut in_span = false;
+    let mut travelled = 0.;
+    for pair in path.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        let length = (q - p).norm();
+        while let Some(&(distance, entering)) = boundaries.peek() {
+            if distance > travelled + length {
+                break;
+            }
+            let t = if practically_zero(length) {
+                0.
+            } else {
+                (distance - travelled) / length
+            };
+            points.push((p + (q - p) * t, entering));
+            in_span = entering;
+            boundaries.next();
+        }
+        points.push((q, in_span));
+        travelled += length;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use render_2d::SvgRect;
+
+    #[test]
+    fn test_labels_not_cut() {
+        let mut doc = SvgDoc::new();
+        let part = SvgRect::new(P2::new(0., 0.), V2::new(1., 1.))
+            .stroke(SvgColor::Black, 0.01);
+        doc.append(part);
+        let border = SvgRect::new(P2::new(2., 0.), V2::new(1., 1.))
+            .stroke(SvgColor::Black, 0.01);
+        doc.append(Layer::Labels.wrap(border));
+        let mut shapes = Shapes::new(12., 0.01);
+        doc.render_to(&mut shapes);
+        assert_eq!(shapes.contours.len(), 1);
+        assert!(shapes.layers.is_empty());
+    }
+}
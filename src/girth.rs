@@ -0,0 +1,125 @@
+//! Girths: how far it is around each station, from the keel up to the
+//! sheer. Plank stock is ordered by them, and the widths in a plank
+//! schedule have to add up to them, less the laps.
+
+use std::fmt;
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use hull::{Hull, Station};
+use spec::PlankStation;
+use unit::Units;
+use util::sort_by_number;
+
+/// The girth of the hull at one station. Lengths are in feet.
+#[derive(Debug, Clone, Serialize)]
+pub struct Girth {
+    /// The station's name, or the position it was given at in the planks
+    /// sheet.
+    pub station: String,
+    /// The fore-aft position of the station.
+    pub position: f32,
+    /// The length of one side of the station, from the keel to the sheer.
+    pub girth: f32,
+    /// The units to print the lengths in.
+    #[serde(skip)]
+    pub units: Units,
+}
+
+impl Station {
+    /// The length of one side of this station's outline, from the keel
+    /// up to the sheer.
+    pub fn girth(&self) -> f32 {
+        self.spline.length()
+    }
+}
+
+impl Hull {
+    /// Find the girth at every station, and at every position that the
+    /// planks sheet gives plank lines at, from fore to aft.
+    pub fn girths(&self) -> Result<Vec<Girth>, LapstrakeError> {
+        let mut girths = vec![];
+        for station in &self.stations {
+            girths.push(Girth {
+                station: station.name.clone(),
+                position: station.position(),
+                girth: station.girth(),
+                units: self.units,
+            });
+        }
+        for station in &self.planks.stations {
+            if let PlankStation::Position(posn) = *station {
+                let station = self.hallucinate_station(posn)?;
+                girths.push(Girth {
+                    station: format!("{:?}", posn),
+                    position: station.position(),
+                    girth: station.girth(),
+                    units: self.units,
+                });
+            }
+        }
+        sort_by_number(&mut girths, |girth| girth.position)?;
+        Ok(girths)
+    }
+
+    /// Save a table of the girths as a csv file.
+    pub fn write_girths(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        for girth in self.girths()? {
+            writer.serialize(girth)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Girth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:>12}   {}",
+            self.station,
+            self.units.from_feet(self.position).to_string(),
+            self.units.from_feet(self.girth)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scad_dots::utils::P3;
+    use spline::Interpolation;
+
+    #[test]
+    fn test_girth() {
+        // A straight, slanted side, 3 ft out and 4 ft up.
+        let points = (0..4)
+            .map(|i| {
+                let t = i as f32 / 3.;
+                P3::new(0., 3. * t, 4. * t)
+            })
+            .collect();
+        let station =
+            Station::new("1".into(), points, 10, Interpolation::default())
+                .unwrap();
+        assert!((station.girth() - 5.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_display() {
+        let girth = Girth {
+            station: "3".into(),
+            position: 1.,
+            girth: 0.5,
+            units: Units::Millimeters,
+        };
+        let text = girth.to_string();
+        assert!(text.contains("304.8mm"), "{}", text);
+        assert!(text.contains("152.4mm"), "{}", text);
+    }
+}
@@ -1,15 +1,41 @@
+use std::sync::Arc;
+
+use rayon::prelude::*;
 use scad_dots::core::MinMaxCoord;
 use scad_dots::utils::{Axis, P2, P3};
 
-use error::LapstrakeError;
+use draw::{BodyPlan, DrawingPass, TemplateLayout, Theme, TitleBlock};
+use error::{LapstrakeError, ResultExt};
 use plank::{FlattenedPlank, Plank};
-use spec::{BreadthLine, HeightLine, PlankStation, Planks, Spec};
-use spline::Spline;
-use unit::Feet;
-use util::remove_duplicates;
+use render_2d::{make_scale_bar, SvgGroup, SvgUnits, PIXELS_PER_INCH};
+use spec::{
+    Backbone, BreadthLine, HeightLine, PlankStation, Planks, Spec, Stem,
+    Transom, Trunk, STEM, TRANSOM,
+};
+use spline::{EndCondition, Interpolation, Spline, MIN_SPLINE_POINTS};
+use unit::{Length, Units};
+use util::{
+    clip_below, polygon_area, practically_zero, project, project_points,
+    remove_duplicates, sort_by_number, sort_points, EQUALITY_THRESHOLD,
+};
+
+/// How far in from the bottom edge of each plank its fasteners go, if not
+/// configured: 3/8".
+const DEFAULT_FASTENER_INSET: f32 = 0.375 / 12.;
+
+/// How deep the deck beams are, if not configured: 1-1/2".
+const DEFAULT_DECK_BEAM_MOULDING: f32 = 1.5 / 12.;
+
+/// How thick the planks are, if not configured: 1/2".
+const DEFAULT_PLANK_THICKNESS: f32 = 0.5 / 12.;
 
-/// A ship's hull.
-#[derive(MinMaxCoord)]
+/// How many spaces there are between the longitudinal lines that
+/// hallucinated stations are measured from.
+const LONGITUDINAL_SPACES: usize = 10;
+
+/// A ship's hull. Lofted hulls are cached on disk, so bump `CACHE_VERSION`
+/// whenever its fields or the lofting change.
+#[derive(MinMaxCoord, Serialize, Deserialize)]
 pub struct Hull {
     pub stations: Vec<Station>,
     #[min_max_coord(ignore)]
@@ -18,14 +44,125 @@ pub struct Hull {
     pub heights: Vec<f32>,
     #[min_max_coord(ignore)]
     pub breadths: Vec<f32>,
+    /// Each diagonal, as (height at the centerline, breadth at the base).
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub diagonals: Vec<(f32, f32)>,
     #[min_max_coord(ignore)]
-    planks: Planks,
+    pub(crate) planks: Planks,
     #[min_max_coord(ignore)]
     resolution: usize,
+    /// How far the crown of the deck rises above the sheer, at the
+    /// widest station.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) deck_camber: f32,
+    /// How deep the deck beams are.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) deck_beam_moulding: f32,
+    /// Where to split the deck into separate panels, if anywhere.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) deck_seam: Option<f32>,
+    /// How far from each end the planks' laps taper away.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) gain_length: f32,
+    /// How far apart the fasteners along each lap are, if there are any.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) fastener_spacing: Option<f32>,
+    /// How far the fasteners are from the bottom edge of each plank.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) fastener_inset: f32,
+    /// How thick the planks are.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) plank_thickness: f32,
+    /// Where the alignment holes and tabs go on station templates.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) template_layout: TemplateLayout,
+    /// How to draw curves through the stations and along the hull.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) interpolation: Interpolation,
+    /// Which colors to draw with.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) theme: Theme,
+    /// Which side of the centerline to draw each station on in the body
+    /// plan.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) body_plan: BodyPlan,
+    /// What to put in the corner of each drawing, if anything.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) title_block: Option<TitleBlock>,
+    /// The units that the spec sheets were measured in.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) units: Units,
+    /// The scale that drawings will be saved at, as in `SvgDoc::save()`.
+    #[min_max_coord(ignore)]
+    #[serde(default = "default_drawing_scale")]
+    pub(crate) drawing_scale: f32,
+    /// What units to give the size of saved svg files in, and how many
+    /// pixels make an inch if they're in pixels.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) svg_units: SvgUnits,
+    #[min_max_coord(ignore)]
+    #[serde(default = "default_pixels_per_inch")]
+    pub(crate) pixels_per_inch: f32,
+    /// How near two points can be before they're treated as the same.
+    #[min_max_coord(ignore)]
+    #[serde(default = "default_equality_threshold")]
+    pub(crate) equality_threshold: f32,
+    /// How the station named Transom is leaned and curved, if there is
+    /// one.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) transom: Option<Transom>,
+    /// The size of the stem, if there's a station named Stem.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) stem: Option<Stem>,
+    /// The size of the keel, hog, and sternpost.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) backbone: Backbone,
+    /// The centerboard or daggerboard trunk, if there is one.
+    #[min_max_coord(ignore)]
+    #[serde(default)]
+    pub(crate) trunk: Option<Trunk>,
+    /// Lines running fore and aft along the hull, each at a constant
+    /// fraction of the way along every station, from the keel to the
+    /// sheer. They're made once, when the hull is lofted.
+    #[min_max_coord(ignore)]
+    longitudinals: Vec<Spline>,
+    #[min_max_coord(ignore)]
+    #[serde(skip)]
+    pub(crate) drawing_passes: Vec<Arc<DrawingPass>>,
+}
+
+fn default_equality_threshold() -> f32 {
+    EQUALITY_THRESHOLD
+}
+
+fn default_drawing_scale() -> f32 {
+    1.
+}
+
+fn default_pixels_per_inch() -> f32 {
+    PIXELS_PER_INCH
 }
 
 /// A cross-section of the hull.
-#[derive(MinMaxCoord)]
+#[derive(MinMaxCoord, Serialize, Deserialize)]
 pub struct Station {
     #[min_max_coord(ignore)]
     pub name: String,
@@ -35,6 +172,51 @@ pub struct Station {
 }
 
 impl Hull {
+    /// Register a custom pass that will be run at the end of each of the
+    /// standard drawings, to add annotations to it.
+    pub fn add_drawing_pass<P>(&mut self, pass: P)
+    where
+        P: DrawingPass + 'static,
+    {
+        self.drawing_passes.push(Arc::new(pass));
+    }
+
+    /// The scale that drawings will be saved at, as in `SvgDoc::save()`.
+    /// It's used to size scale bars and text.
+    pub fn drawing_scale(&self) -> f32 {
+        self.drawing_scale
+    }
+
+    pub fn set_drawing_scale(&mut self, scale_from_feet: f32) {
+        self.drawing_scale = scale_from_feet;
+    }
+
+    /// How near two points can be before they're treated as the same.
+    pub fn equality_threshold(&self) -> f32 {
+        self.equality_threshold
+    }
+
+    /// The units of the spec sheets, which measurements are reported in.
+    pub fn units(&self) -> Units {
+        self.units
+    }
+
+    /// The units that saved svg files give their size in, as in
+    /// `SvgDoc::set_units()`.
+    pub fn svg_units(&self) -> SvgUnits {
+        self.svg_units
+    }
+
+    /// How many pixels make an inch, if svg files are saved in pixels.
+    pub fn pixels_per_inch(&self) -> f32 {
+        self.pixels_per_inch
+    }
+
+    /// A scale bar for the drawings, in the units of the spec sheets.
+    pub fn scale_bar(&self) -> Result<SvgGroup, LapstrakeError> {
+        make_scale_bar(self.units, self.drawing_scale)
+    }
+
     /// Get a set of planks that can cover the hull.
     /// `n` is the number of planks for each side of the hull
     /// (so there will be 2n planks in total).
@@ -47,7 +229,16 @@ impl Hull {
         for i in 0..n / 2 {
             let bot_line = self.get_plank_row(2 * i)?;
             let top_line = self.get_plank_row(2 * i + 1)?;
-            planks.push(Plank::new(bot_line, top_line, self.resolution)?);
+            let mut plank = Plank::new(
+                bot_line,
+                top_line,
+                self.resolution,
+                self.interpolation,
+                self.equality_threshold,
+            )?;
+            plank.gain_length = self.gain_length;
+            plank.thickness = self.plank_thickness;
+            planks.push(plank);
         }
         Ok(planks)
     }
@@ -71,19 +262,51 @@ impl Hull {
         FlattenedPlank::flatten_planks(self.get_planks()?)
     }
 
+    /// Make a spline through the points, the way this hull's lines are
+    /// made.
+    pub(crate) fn spline_through(
+        &self,
+        points: Vec<P3>,
+    ) -> Result<Spline, LapstrakeError> {
+        Spline::with_options(
+            points,
+            self.resolution,
+            self.interpolation,
+            EndCondition::default(),
+            self.equality_threshold,
+        )
+    }
+
     /// Get a line across the hull that is a constant fraction `t`
     /// of the distance along the edge of each cross section.
-    fn get_line(&self, t: f32) -> Result<Spline, LapstrakeError> {
+    pub(crate) fn get_line(&self, t: f32) -> Result<Spline, LapstrakeError> {
         let points = self
             .stations
             .iter()
             .map(|station| station.at_t(t))
             .collect::<Result<_, LapstrakeError>>()?;
-        Spline::new(points, self.resolution)
+        self.spline_through(points)
+    }
+
+    /// Sample the hull surface on a grid. There is one row for each of
+    /// `girth_samples + 1` longitudinal lines, running from the keel to
+    /// the sheer, and each row has `length_samples` points spaced evenly
+    /// from fore to aft.
+    pub(crate) fn surface_grid(
+        &self,
+        girth_samples: usize,
+        length_samples: usize,
+    ) -> Result<Vec<Vec<P3>>, LapstrakeError> {
+        let mut grid = vec![];
+        for j in 0..girth_samples + 1 {
+            let t = j as f32 / girth_samples as f32;
+            grid.push(self.get_line(t)?.resample_even(length_samples)?);
+        }
+        Ok(grid)
     }
 
     /// Get a station by name.
-    fn get_station(
+    pub(crate) fn get_station(
         &self,
         station_name: &str,
     ) -> Result<&Station, LapstrakeError> {
@@ -115,20 +338,315 @@ impl Hull {
         }
     }
 
+    /// Find which stations to leave out, given the names of the only
+    /// stations to keep (if any are given) and of stations to leave out.
+    /// Every name must be one of the hull's stations.
+    pub fn excluded_stations(
+        &self,
+        only: &[String],
+        exclude: &[String],
+    ) -> Result<Vec<String>, LapstrakeError> {
+        for name in only.iter().chain(exclude) {
+            self.get_station(name)?;
+        }
+        Ok(self
+            .stations
+            .iter()
+            .map(|station| station.name.clone())
+            .filter(|name| {
+                exclude.contains(name)
+                    || !(only.is_empty() || only.contains(name))
+            })
+            .collect())
+    }
+
+    /// Lines running fore and aft along the hull, evenly spaced along
+    /// each station from the keel to the sheer. Each is a constant
+    /// fraction of the way along every station. It's empty if the hull
+    /// has too few stations to make them.
+    pub fn longitudinals(&self) -> &[Spline] {
+        &self.longitudinals
+    }
+
+    /// The height of the design waterline above base, if the config sheet
+    /// gives one.
+    pub fn design_waterline(&self) -> Option<f32> {
+        self.template_layout.design_waterline
+    }
+
+    /// What to put in the corner of each drawing, if the config sheet
+    /// asks for a title block.
+    pub fn title_block(&self) -> Option<&TitleBlock> {
+        self.title_block.as_ref()
+    }
+
+    // Make the longitudinal lines, from the stations.
+    fn make_longitudinals(&self) -> Result<Vec<Spline>, LapstrakeError> {
+        (0..LONGITUDINAL_SPACES + 1)
+            .map(|i| self.get_line(i as f32 / LONGITUDINAL_SPACES as f32))
+            .collect()
+    }
+
+    // Finish constructing the hull, by making its longitudinal lines. A
+    // hull that can't have them can still be drawn, so failing to make
+    // them is only an error once a station needs to be hallucinated.
+    fn with_longitudinals(mut self) -> Hull {
+        self.longitudinals = self.make_longitudinals().unwrap_or_default();
+        self
+    }
+
     /// Construct a station at the given fore-aft position.
     pub fn hallucinate_station(
         &self,
-        posn: Feet,
+        posn: Length,
     ) -> Result<Station, LapstrakeError> {
+        if self.longitudinals.is_empty() {
+            // They couldn't be made when the hull was lofted, so this
+            // reports why.
+            self.make_longitudinals()?;
+        }
         let mut points = vec![];
-        let resolution = 10;
-        for i in 0..resolution + 1 {
-            let t = i as f32 / resolution as f32;
-            let line = self.get_line(t)?;
+        for line in &self.longitudinals {
             points.push(line.at_x(posn.into())?);
         }
         let name = format!("{}", posn);
-        Station::new(name, points, self.resolution)
+        Station::with_knuckles(
+            name,
+            points,
+            self.resolution,
+            self.interpolation,
+            self.equality_threshold,
+            &[],
+        )
+    }
+
+    /// The keel line in profile: the bottom of each station, as (x, z)
+    /// points from fore to aft.
+    pub fn keel_line(&self) -> Result<Vec<P2>, LapstrakeError> {
+        let mut keel: Vec<P2> = self
+            .stations
+            .iter()
+            .map(|station| P2::new(station.points[0].x, station.points[0].z))
+            .collect();
+        sort_by_number(&mut keel, |p| p.x)?;
+        Ok(keel)
+    }
+
+    /// The wale as a line running fore and aft along the starboard side,
+    /// through the height it was given at each station, or None if it was
+    /// given at too few stations to draw a line through.
+    pub fn wale_line(&self) -> Result<Option<Spline>, LapstrakeError> {
+        let mut points = vec![];
+        for wale in &self.wale {
+            // Find the station that the wale's height was given on.
+            let mut nearest: Option<(&Station, f32)> = None;
+            for station in &self.stations {
+                let distance = (station.position() - wale.x).abs();
+                if nearest.map_or(distance.is_finite(), |(_, best)| {
+                    distance < best
+                }) {
+                    nearest = Some((station, distance));
+                }
+            }
+            let breadth = match nearest {
+                Some((station, _)) => station.breadth_at_height(wale.y)?,
+                None => None,
+            };
+            if let Some(breadth) = breadth {
+                points.push(P3::new(wale.x, breadth, wale.y));
+            }
+        }
+        if points.len() < MIN_SPLINE_POINTS {
+            if !points.is_empty() {
+                println!(
+                    "Warning: skipping the wale, which is only given at {} \
+                     stations.",
+                    points.len()
+                );
+            }
+            return Ok(None);
+        }
+        sort_points(&mut points, |p| p.x)?;
+        Ok(Some(self.spline_through(points)?))
+    }
+
+    /// Slice the hull at a constant height, giving a waterline that runs
+    /// from fore to aft along the starboard side. Only stations that reach
+    /// down to that height contribute to it.
+    pub fn get_waterline(&self, height: f32) -> Result<Spline, LapstrakeError> {
+        let mut points = vec![];
+        for station in &self.stations {
+            if let Some(breadth) = station.breadth_at_height(height)? {
+                points.push(P3::new(station.position(), breadth, height));
+            }
+        }
+        sort_points(&mut points, |p| p.x)?;
+        self.spline_through(points).with_context(|| {
+            format!("Failed to construct waterline at height {}", height)
+        })
+    }
+
+    /// Slice the hull at a constant breadth out from the centerline,
+    /// giving a buttock line that runs from fore to aft. Only stations that
+    /// reach out to that breadth contribute to it.
+    pub fn get_buttock(&self, breadth: f32) -> Result<Spline, LapstrakeError> {
+        let mut points = vec![];
+        for station in &self.stations {
+            if let Some(height) = station.height_at_breadth(breadth)? {
+                points.push(P3::new(station.position(), breadth, height));
+            }
+        }
+        sort_points(&mut points, |p| p.x)?;
+        self.spline_through(points).with_context(|| {
+            format!("Failed to construct buttock at breadth {}", breadth)
+        })
+    }
+
+    /// The fore-aft position halfway between the first and last stations.
+    pub fn midships(&self) -> f32 {
+        let positions: Vec<f32> =
+            self.stations.iter().map(|s| s.position()).collect();
+        let min = positions.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = positions.iter().cloned().fold(-f32::INFINITY, f32::max);
+        (min + max) / 2.
+    }
+
+    /// Find pairs of stations, one forward of midships and one aft, whose
+    /// cross-sections are the same shape. This is common on double-ended
+    /// hulls, and each pair can share a single mold. Returns pairs of
+    /// (forward, aft) station names.
+    pub fn find_mirrored_stations(
+        &self,
+    ) -> Result<Vec<(String, String)>, LapstrakeError> {
+        let midships = self.midships();
+
+        let mut pairs = vec![];
+        let mut used = vec![false; self.stations.len()];
+        for (i, fore) in self.stations.iter().enumerate() {
+            if fore.position() >= midships {
+                continue;
+            }
+            for (j, aft) in self.stations.iter().enumerate() {
+                if used[j] || aft.position() <= midships {
+                    continue;
+                }
+                if fore.same_shape_as(aft, self.equality_threshold)? {
+                    used[j] = true;
+                    pairs.push((fore.name.clone(), aft.name.clone()));
+                    break;
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Blend this hull with another one. `t` is how far to go towards
+    /// `other`: 0 gives this hull, and 1 gives the other. Stations are
+    /// matched up by name, so both hulls must have the same stations,
+    /// each measured at the same number of points, and the same wale and
+    /// grid lines. The planks and configuration are taken from this hull.
+    pub fn morph(&self, other: &Hull, t: f32) -> Result<Hull, LapstrakeError> {
+        if self.stations.len() != other.stations.len() {
+            return Err(LapstrakeError::General(
+                "Hulls must have the same stations to be morphed.".into(),
+            ));
+        }
+        let mut stations = vec![];
+        for station in &self.stations {
+            let other_station = other.get_station(&station.name).context(
+                "Hulls must have the same station names to be morphed.",
+            )?;
+            if station.points.len() != other_station.points.len() {
+                return Err(LapstrakeError::General(format!(
+                    "Station {} must be measured at the same number of \
+                     points in both hulls to be morphed.",
+                    station.name
+                )));
+            }
+            let points: Vec<P3> = station
+                .points
+                .iter()
+                .zip(&other_station.points)
+                .map(|(&p, &q)| blend3(t, p, q))
+                .collect();
+            // Blend the splines themselves where they line up, so that
+            // knuckles are kept. Otherwise, spline the blended points.
+            let blended = station.spline.blend(&other_station.spline, t);
+            let spline = match blended {
+                Some(spline) => spline,
+                None => Spline::with_knuckles(
+                    points.clone(),
+                    self.resolution,
+                    self.interpolation,
+                    self.equality_threshold,
+                    &[],
+                )?,
+            };
+            stations.push(Station {
+                name: station.name.clone(),
+                points: points,
+                spline: spline,
+            });
+        }
+
+        if self.wale.len() != other.wale.len() {
+            return Err(LapstrakeError::General(
+                "Hulls must have the wale at the same stations to be morphed."
+                    .into(),
+            ));
+        }
+        let wale = self
+            .wale
+            .iter()
+            .zip(&other.wale)
+            .map(|(&p, &q)| blend2(t, p, q))
+            .collect();
+        if self.diagonals.len() != other.diagonals.len() {
+            return Err(LapstrakeError::General(
+                "Hulls must have the same diagonals to be morphed.".into(),
+            ));
+        }
+        let diagonals = self
+            .diagonals
+            .iter()
+            .zip(&other.diagonals)
+            .map(|(&(h, b), &(k, c))| (lerp(t, h, k), lerp(t, b, c)))
+            .collect();
+
+        Ok(Hull {
+            stations: stations,
+            wale: wale,
+            heights: blend_lines(t, &self.heights, &other.heights)
+                .context("Hulls must have the same heights to be morphed.")?,
+            breadths: blend_lines(t, &self.breadths, &other.breadths)
+                .context("Hulls must have the same breadths to be morphed.")?,
+            diagonals: diagonals,
+            planks: self.planks.clone(),
+            resolution: self.resolution,
+            deck_camber: self.deck_camber,
+            deck_beam_moulding: self.deck_beam_moulding,
+            deck_seam: self.deck_seam,
+            gain_length: self.gain_length,
+            fastener_spacing: self.fastener_spacing,
+            fastener_inset: self.fastener_inset,
+            plank_thickness: self.plank_thickness,
+            template_layout: self.template_layout,
+            interpolation: self.interpolation,
+            theme: self.theme,
+            body_plan: self.body_plan,
+            title_block: self.title_block.clone(),
+            units: self.units,
+            drawing_scale: self.drawing_scale,
+            svg_units: self.svg_units,
+            pixels_per_inch: self.pixels_per_inch,
+            equality_threshold: self.equality_threshold,
+            transom: self.transom,
+            stem: self.stem,
+            backbone: self.backbone,
+            trunk: self.trunk,
+            longitudinals: vec![],
+            drawing_passes: self.drawing_passes.clone(),
+        }.with_longitudinals())
     }
 }
 
@@ -137,11 +655,39 @@ impl Station {
         name: String,
         points: Vec<P3>,
         resolution: usize,
+        interpolation: Interpolation,
+    ) -> Result<Station, LapstrakeError> {
+        Station::with_knuckles(
+            name,
+            points,
+            resolution,
+            interpolation,
+            EQUALITY_THRESHOLD,
+            &[],
+        )
+    }
+
+    /// Make a station with a sharp corner at each of the points with the
+    /// given indices. Points nearer than `threshold` to their neighbors
+    /// are treated as duplicates.
+    pub fn with_knuckles(
+        name: String,
+        points: Vec<P3>,
+        resolution: usize,
+        interpolation: Interpolation,
+        threshold: f32,
+        knuckles: &[usize],
     ) -> Result<Station, LapstrakeError> {
         Ok(Station {
             name: name,
             points: points.clone(),
-            spline: Spline::new(points, resolution)?,
+            spline: Spline::with_knuckles(
+                points,
+                resolution,
+                interpolation,
+                threshold,
+                knuckles,
+            )?,
         })
     }
 
@@ -150,70 +696,344 @@ impl Station {
     pub fn at_t(&self, t: f32) -> Result<P3, LapstrakeError> {
         self.spline.at_t(t)
     }
+
+    /// Find the heights at which this station's curve crosses over
+    /// itself, which usually means that an offset was mistyped.
+    pub fn self_intersection_heights(&self) -> Vec<f32> {
+        self.spline
+            .self_intersections(Axis::X)
+            .iter()
+            .map(|p| p.y)
+            .collect()
+    }
+
+    fn warn_if_self_intersecting(&self) {
+        for height in self.self_intersection_heights() {
+            println!(
+                concat!(
+                    "Warning: station {} crosses over itself near height ",
+                    "{:.2} ft. Check its offsets."
+                ),
+                self.name, height
+            );
+        }
+    }
+
+    /// The fore-aft position of this station.
+    pub fn position(&self) -> f32 {
+        self.points[0].x
+    }
+
+    /// Whether this station's cross-section matches another's, to within
+    /// `threshold` everywhere, ignoring their fore-aft positions.
+    pub fn same_shape_as(
+        &self,
+        other: &Station,
+        threshold: f32,
+    ) -> Result<bool, LapstrakeError> {
+        const MATCH_SAMPLES: usize = 20;
+        for k in 0..MATCH_SAMPLES + 1 {
+            let t = k as f32 / MATCH_SAMPLES as f32;
+            let p = project(Axis::X, self.at_t(t)?);
+            let q = project(Axis::X, other.at_t(t)?);
+            if (p - q).norm() > threshold {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The sheer point: the top of the station.
+    pub fn sheer(&self) -> P3 {
+        *self.points.last().expect("station has no points")
+    }
+
+    /// Get the half-breadth of this station at the given height, or
+    /// None if the station doesn't reach that height.
+    pub fn breadth_at_height(
+        &self,
+        height: f32,
+    ) -> Result<Option<f32>, LapstrakeError> {
+        let samples = self.spline.sample(None)?;
+        for pair in samples.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            if (p.z <= height && height <= q.z)
+                || (q.z <= height && height <= p.z)
+            {
+                if practically_zero(q.z - p.z) {
+                    return Ok(Some(p.y.max(q.y)));
+                }
+                let t = (height - p.z) / (q.z - p.z);
+                return Ok(Some(p.y + t * (q.y - p.y)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the lowest height at which this station reaches the given
+    /// half-breadth, or None if it never gets that wide.
+    pub fn height_at_breadth(
+        &self,
+        breadth: f32,
+    ) -> Result<Option<f32>, LapstrakeError> {
+        let samples = self.spline.sample(None)?;
+        for pair in samples.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            if (p.y <= breadth && breadth <= q.y)
+                || (q.y <= breadth && breadth <= p.y)
+            {
+                if practically_zero(q.y - p.y) {
+                    return Ok(Some(p.z.min(q.z)));
+                }
+                let t = (breadth - p.y) / (q.y - p.y);
+                return Ok(Some(p.z + t * (q.z - p.z)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The area of the full cross-section (both sides of the
+    /// centerline), from the bottom of the station up to the sheer.
+    pub fn area(&self) -> Result<f32, LapstrakeError> {
+        Ok(2. * polygon_area(&self.half_section()?))
+    }
+
+    /// The area of the full cross-section (both sides of the
+    /// centerline) that is below the given waterline height.
+    pub fn immersed_area(&self, waterline: f32) -> Result<f32, LapstrakeError> {
+        let immersed = clip_below(&self.half_section()?, waterline);
+        Ok(2. * polygon_area(&immersed))
+    }
+
+    // The outline of one side of the station, as (y, z) points, closed
+    // off along the centerline.
+    fn half_section(&self) -> Result<Vec<P2>, LapstrakeError> {
+        let mut outline =
+            project_points(Axis::X, &self.spline.sample(None)?);
+        let top = outline[outline.len() - 1].y;
+        let bottom = outline[0].y;
+        outline.push(P2::new(0., top));
+        outline.push(P2::new(0., bottom));
+        Ok(outline)
+    }
 }
 
 impl Spec {
     pub fn get_hull(&self) -> Result<Hull, LapstrakeError> {
+        let interpolation = self.config.spline.unwrap_or_default();
+        // The stations are independent, so loft them in parallel.
+        // Collecting them keeps them in order.
+        let lofted = (0..self.data.stations.len())
+            .into_par_iter()
+            .map(|i| {
+                self.loft_station(i, interpolation).with_context(|| {
+                    format!("Could not loft station {}", self.data.stations[i])
+                })
+            })
+            .collect::<Result<Vec<_>, LapstrakeError>>()?;
+        let mut stations = vec![];
+        let mut wale = vec![];
+        for (station, wale_points) in lofted {
+            station.warn_if_self_intersecting();
+            stations.push(station);
+            wale.extend(wale_points);
+        }
+        let beam = 2. * stations
+            .iter()
+            .map(|station| station.sheer().y)
+            .fold(0., f32::max);
+
+        Ok(Hull {
+            stations: stations,
+            breadths: self.get_breadths(),
+            heights: self.get_heights(),
+            diagonals: self
+                .data
+                .diagonals
+                .iter()
+                .map(|&(diagonal, _)| {
+                    (diagonal.height.into(), diagonal.breadth.into())
+                })
+                .collect(),
+            wale: wale,
+            planks: self.planks.clone(),
+            resolution: self.config.resolution,
+            deck_camber: self.deck_camber(beam),
+            deck_beam_moulding: self
+                .config
+                .deck_beam_moulding
+                .map_or(DEFAULT_DECK_BEAM_MOULDING, |m| m.into()),
+            deck_seam: self.config.deck_seam.map(|s| s.into()),
+            gain_length: self.config.gain_length.map_or(0., |g| g.into()),
+            fastener_spacing: self.config.fastener_spacing.map(|s| s.into()),
+            fastener_inset: self
+                .config
+                .fastener_inset
+                .map_or(DEFAULT_FASTENER_INSET, |i| i.into()),
+            plank_thickness: self
+                .config
+                .plank_thickness
+                .map_or(DEFAULT_PLANK_THICKNESS, |t| t.into()),
+            template_layout: TemplateLayout::from_config(&self.config),
+            interpolation: interpolation,
+            theme: self.config.theme.unwrap_or_default(),
+            body_plan: self.config.body_plan.unwrap_or_default(),
+            title_block: TitleBlock::from_config(&self.config),
+            units: self.config.units,
+            drawing_scale: self.config.drawing_scale.unwrap_or(1.),
+            svg_units: self.config.svg_units.unwrap_or_default(),
+            pixels_per_inch: self
+                .config
+                .pixels_per_inch
+                .unwrap_or(PIXELS_PER_INCH),
+            equality_threshold: self.equality_threshold(),
+            transom: self.transom()?,
+            stem: self.stem(),
+            backbone: self.backbone(),
+            trunk: self.trunk()?,
+            longitudinals: vec![],
+            drawing_passes: vec![],
+        }.with_longitudinals())
+    }
+
+    // Make the station with the given index, from its measurements in
+    // the data sheet. Also returns where the wale crosses it, if the
+    // data sheet says.
+    fn loft_station(
+        &self,
+        i: usize,
+        interpolation: Interpolation,
+    ) -> Result<(Station, Vec<P2>), LapstrakeError> {
         let data = &self.data;
         let resolution = self.config.resolution;
-        let mut stations = vec![];
+        let threshold = self.equality_threshold();
+        // A stem with a profile is lofted along its rabbet, on the side of
+        // the stem, so the planks end there.
+        let rabbet = data.stem_rabbet()?;
+        if data.stations[i] == STEM && !rabbet.is_empty() {
+            let half_siding = self.stem().map_or(0., |stem| stem.siding / 2.);
+            let points = rabbet
+                .into_iter()
+                .map(|(x, z)| P3::new(x, half_siding, z))
+                .collect();
+            let station = Station::with_knuckles(
+                STEM.to_string(),
+                remove_duplicates(points, threshold),
+                resolution,
+                interpolation,
+                threshold,
+                &[],
+            )?;
+            return Ok((station, vec![]));
+        }
         let mut wale = vec![];
-        for i in 0..data.stations.len() {
-            let mut points = vec![];
-            // Add the sheer point.
-            let sheer_breadth = self.get_sheer_breadth(i)?;
-            let sheer_height = self.get_sheer_height(i)?;
-            let sheer_posn = self.get_station_position(i, HeightLine::Sheer)?;
-            points.push(point(sheer_posn, sheer_breadth, sheer_height));
-            // Add the height measurements. Assume they are at the
-            // positions given by the sheer for that station.
-            for &(ref breadth, ref row) in &data.heights {
-                if let Some(height) = row[i] {
-                    let posn =
-                        self.get_station_position(i, HeightLine::Sheer)?;
-                    match *breadth {
-                        BreadthLine::Sheer => (),
-                        BreadthLine::Wale => {
-                            wale.push(P2::new(posn.into(), height.into()));
-                        }
-                        BreadthLine::ButOut(breadth) => {
-                            points.push(point(posn, breadth, height));
+        let mut points = vec![];
+        // The points on knuckle lines, where the station has a corner.
+        let mut knuckles = vec![];
+        // Add the sheer point.
+        let sheer_breadth = self.get_sheer_breadth(i)?;
+        let sheer_height = self.get_sheer_height(i)?;
+        let sheer_posn = self.get_station_position(i, HeightLine::Sheer)?;
+        points.push(point(sheer_posn, sheer_breadth, sheer_height));
+        // Add the height measurements. Assume they are at the
+        // positions given by the sheer for that station.
+        for &(ref breadth, ref row) in &data.heights {
+            if let Some(height) = row[i] {
+                let posn = self.get_station_position(i, HeightLine::Sheer)?;
+                match *breadth {
+                    BreadthLine::Sheer => (),
+                    BreadthLine::Wale => {
+                        wale.push(P2::new(posn.into(), height.into()));
+                    }
+                    BreadthLine::ButOut(breadth) => {
+                        let p = point(posn, breadth, height);
+                        let line = BreadthLine::ButOut(breadth);
+                        if data.knuckle_breadths.contains(&line) {
+                            knuckles.push(p);
                         }
+                        points.push(p);
                     }
                 }
             }
-            // Add the breadth measurements.
-            for &(ref height, ref row) in &data.breadths {
-                if let Some(breadth) = row[i] {
-                    let posn = self.get_station_position(i, *height)?;
-                    match *height {
-                        HeightLine::Sheer => (),
-                        HeightLine::WLUp(height) => {
-                            points.push(point(posn, breadth, height));
+        }
+        // Add the breadth measurements.
+        for &(ref height, ref row) in &data.breadths {
+            if let Some(breadth) = row[i] {
+                let posn = self.get_station_position(i, *height)?;
+                match *height {
+                    HeightLine::Sheer => (),
+                    HeightLine::WLUp(height) => {
+                        let p = point(posn, breadth, height);
+                        let line = HeightLine::WLUp(height);
+                        if data.knuckle_heights.contains(&line) {
+                            knuckles.push(p);
                         }
+                        points.push(p);
                     }
                 }
             }
-            // The points are out of order, and may contain duplicates.
-            // Sort them and remove the duplicates.
-            points.sort_by(|p, q| p.z.partial_cmp(&q.z).unwrap());
-            let points = remove_duplicates(points);
-            // Construct the station (cross section).
-            stations.push(Station::new(
-                data.stations[i].to_string(),
-                points,
-                resolution,
-            )?);
         }
+        // Add the diagonal measurements.
+        for &(ref diagonal, ref row) in &data.diagonals {
+            if let Some(distance) = row[i] {
+                let posn = self.get_station_position(i, HeightLine::Sheer)?;
+                let (breadth, height) = diagonal.point_at(distance.into());
+                points.push(P3::new(posn.into(), breadth, height));
+            }
+        }
+        // Lean the transom aft and curve it, from its shape in the body
+        // plan.
+        if data.stations[i] == TRANSOM {
+            if let Some(transom) = self.transom()? {
+                let posn: f32 = sheer_posn.into();
+                let top: f32 = sheer_height.into();
+                for p in points.iter_mut().chain(knuckles.iter_mut()) {
+                    p.x = posn + transom.setback(p.y, p.z - top)?;
+                }
+            }
+        }
+        // The points are out of order, and may contain duplicates.
+        // Sort them and remove the duplicates.
+        sort_points(&mut points, |p| p.z)?;
+        let points = remove_duplicates(points, threshold);
+        // The corners can't be at the ends of the station.
+        let corners: Vec<usize> = (1..points.len().saturating_sub(1))
+            .filter(|&j| {
+                knuckles.iter().any(|k| {
+                    practically_zero((points[j] - *k).norm())
+                })
+            })
+            .collect();
+        // Construct the station (cross section).
+        let station = Station::with_knuckles(
+            data.stations[i].to_string(),
+            points,
+            resolution,
+            interpolation,
+            threshold,
+            &corners,
+        )?;
+        Ok((station, wale))
+    }
 
-        Ok(Hull {
-            stations: stations,
-            breadths: self.get_breadths(),
-            heights: self.get_heights(),
-            wale: wale,
-            planks: self.planks.clone(),
-            resolution: self.config.resolution,
-        })
+    // How far the crown of the deck rises above the sheer at the widest
+    // station, which is `beam` wide.
+    fn deck_camber(&self, beam: f32) -> f32 {
+        match (self.config.deck_camber, self.config.deck_camber_per_foot) {
+            (Some(camber), _) => camber.into(),
+            (None, Some(per_foot)) => {
+                let per_foot: f32 = per_foot.into();
+                beam * per_foot
+            }
+            (None, None) => 0.,
+        }
+    }
+
+    // How near two points can be before they're treated as the same.
+    fn equality_threshold(&self) -> f32 {
+        self.config
+            .equality_threshold
+            .map_or(EQUALITY_THRESHOLD, |t| t.into())
     }
 
     fn get_breadths(&self) -> Vec<f32> {
@@ -242,6 +1062,84 @@ impl Spec {
     }
 }
 
-fn point(x: Feet, y: Feet, z: Feet) -> P3 {
+fn lerp<V>(t: f32, a: V, b: V) -> V
+where
+    V: ::std::ops::Mul<f32, Output = V> + ::std::ops::Add<Output = V>,
+{
+    a * (1.0 - t) + b * t
+}
+
+fn blend2(t: f32, p: P2, q: P2) -> P2 {
+    P2::from_coordinates(lerp(t, p.coords, q.coords))
+}
+
+fn blend3(t: f32, p: P3, q: P3) -> P3 {
+    P3::from_coordinates(lerp(t, p.coords, q.coords))
+}
+
+// Blend grid lines pairwise. Both hulls must have the same number of
+// them.
+fn blend_lines(
+    t: f32,
+    these: &[f32],
+    those: &[f32],
+) -> Result<Vec<f32>, LapstrakeError> {
+    if these.len() != those.len() {
+        return Err(LapstrakeError::General(format!(
+            "Can't blend {} grid lines with {}.",
+            these.len(),
+            those.len()
+        )));
+    }
+    Ok(these.iter().zip(those).map(|(&a, &b)| lerp(t, a, b)).collect())
+}
+
+fn point(x: Length, y: Length, z: Length) -> P3 {
     P3::new(x.into(), y.into(), z.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn example_spec() -> Spec {
+        Spec::load_from(Path::new("input")).expect("failed to load spec")
+    }
+
+    fn assert_same_hull(a: &Hull, b: &Hull) {
+        assert_eq!(a.stations.len(), b.stations.len());
+        for (p, q) in a.stations.iter().zip(&b.stations) {
+            assert_eq!(p.name, q.name);
+            assert_eq!(p.points, q.points);
+            assert_eq!(
+                p.spline.sample(None).unwrap(),
+                q.spline.sample(None).unwrap()
+            );
+        }
+        assert_eq!(a.wale, b.wale);
+        assert_eq!(a.heights, b.heights);
+        assert_eq!(a.breadths, b.breadths);
+        assert_eq!(a.diagonals, b.diagonals);
+    }
+
+    #[test]
+    fn test_morph_ends() {
+        let hull = example_spec().get_hull().unwrap();
+        // The same hull, with every breadth made a tenth wider.
+        let mut spec = example_spec();
+        let units = spec.data.units;
+        for &mut (_, ref mut row) in &mut spec.data.breadths {
+            for breadth in row.iter_mut() {
+                *breadth = breadth.map(|b| {
+                    let b: f32 = b.into();
+                    units.from_feet(1.1 * b)
+                });
+            }
+        }
+        let beamy = spec.get_hull().unwrap();
+
+        assert_same_hull(&hull.morph(&beamy, 0.).unwrap(), &hull);
+        assert_same_hull(&hull.morph(&beamy, 1.).unwrap(), &beamy);
+    }
+}
@@ -0,0 +1,366 @@
+//! Hydrostatics: how the hull floats at a given waterline.
+//!
+//! Everything is found by integrating over the stations along the length
+//! of the hull with the trapezoid rule, so the results are only as good
+//! as the station spacing allows.
+
+use std::fmt;
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use hull::{Hull, Station};
+use unit::Units;
+use util::{practically_zero, sort_by_number};
+
+/// The density of sea water, in pounds per cubic foot.
+pub const SALT_WATER_DENSITY: f32 = 64.;
+/// The density of fresh water, in pounds per cubic foot.
+pub const FRESH_WATER_DENSITY: f32 = 62.4;
+
+/// The hydrostatic properties of the hull, floating upright at some
+/// waterline. Lengths are in feet.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Hydrostatics {
+    /// The height of the waterline above base.
+    pub waterline: f32,
+    /// The immersed volume, in cubic feet.
+    pub volume: f32,
+    /// The weight of water displaced, in pounds.
+    pub displacement: f32,
+    /// The longitudinal center of buoyancy: the fore-aft position of the
+    /// center of the immersed volume.
+    pub lcb: f32,
+    /// The area enclosed by the waterline, in square feet.
+    pub waterplane_area: f32,
+    /// The area of hull below the waterline, in square feet. This uses
+    /// the immersed girth of each station, so it slightly underestimates
+    /// the area near the ends, where the hull is steeply sloped.
+    pub wetted_surface: f32,
+    /// The units to print the measurements in.
+    #[serde(skip)]
+    pub units: Units,
+}
+
+/// The dimensionless form coefficients of the hull at some waterline,
+/// which describe how full or fine it is. Lengths are in feet.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Coefficients {
+    /// The height of the waterline above base.
+    pub waterline: f32,
+    /// The length of the waterline.
+    pub lwl: f32,
+    /// The greatest beam at the waterline.
+    pub beam: f32,
+    /// The depth of the hull below the waterline.
+    pub draft: f32,
+    /// The immersed volume, relative to the box around it.
+    pub block: f32,
+    /// The immersed volume, relative to the largest immersed section
+    /// extended along the whole waterline.
+    pub prismatic: f32,
+    /// The largest immersed sectional area, relative to the rectangle
+    /// around it.
+    pub midship: f32,
+    /// The units to print the lengths in.
+    #[serde(skip)]
+    pub units: Units,
+}
+
+impl Hull {
+    /// Find how the hull floats with its waterline at the given height
+    /// above base, in water of the given density (in pounds per cubic
+    /// foot).
+    pub fn hydrostatics(
+        &self,
+        waterline: f32,
+        density: f32,
+    ) -> Result<Hydrostatics, LapstrakeError> {
+        let mut stations: Vec<&Station> = self.stations.iter().collect();
+        sort_by_number(&mut stations, |station| station.position())?;
+
+        let mut areas = vec![];
+        let mut moments = vec![];
+        let mut breadths = vec![];
+        let mut girths = vec![];
+        for station in &stations {
+            let x = station.position();
+            let area = station.immersed_area(waterline)?;
+            areas.push((x, area));
+            moments.push((x, x * area));
+            let breadth = station.breadth_at_height(waterline)?;
+            breadths.push((x, 2. * breadth.unwrap_or(0.)));
+            girths.push((x, 2. * station.immersed_girth(waterline)?));
+        }
+
+        let volume = integrate(&areas);
+        if volume <= 0. {
+            return Err(LapstrakeError::General(format!(
+                "The hull isn't immersed at a waterline of {}.",
+                self.units.from_feet(waterline)
+            )));
+        }
+        Ok(Hydrostatics {
+            waterline: waterline,
+            volume: volume,
+            displacement: volume * density,
+            lcb: integrate(&moments) / volume,
+            waterplane_area: integrate(&breadths),
+            wetted_surface: integrate(&girths),
+            units: self.units,
+        })
+    }
+
+    /// Find the form coefficients of the hull with its waterline at the
+    /// given height above base.
+    pub fn coefficients(
+        &self,
+        waterline: f32,
+    ) -> Result<Coefficients, LapstrakeError> {
+        let volume = self.hydrostatics(waterline, 1.)?.volume;
+        let mut stations: Vec<&Station> = self.stations.iter().collect();
+        sort_by_number(&mut stations, |station| station.position())?;
+        let mut beam: f32 = 0.;
+        let mut midship_area: f32 = 0.;
+        let mut bottom = waterline;
+        for station in &self.stations {
+            if let Some(breadth) = station.breadth_at_height(waterline)? {
+                beam = beam.max(2. * breadth);
+            }
+            midship_area = midship_area.max(station.immersed_area(waterline)?);
+            for point in &station.points {
+                bottom = bottom.min(point.z);
+            }
+        }
+        let fore = waterline_end(stations.iter().cloned(), waterline)?;
+        let aft = waterline_end(stations.iter().rev().cloned(), waterline)?;
+        let lwl = match (fore, aft) {
+            (Some(fore), Some(aft)) => (aft - fore).abs(),
+            _ => 0.,
+        };
+        let draft = waterline - bottom;
+        if lwl <= 0. || beam <= 0. || draft <= 0. {
+            return Err(LapstrakeError::General(format!(
+                "The hull has no waterplane at a waterline of {}.",
+                self.units.from_feet(waterline)
+            )));
+        }
+        Ok(Coefficients {
+            waterline: waterline,
+            lwl: lwl,
+            beam: beam,
+            draft: draft,
+            block: volume / (lwl * beam * draft),
+            prismatic: volume / (midship_area * lwl),
+            midship: midship_area / (beam * draft),
+            units: self.units,
+        })
+    }
+}
+
+impl Hydrostatics {
+    /// Save the hydrostatics as a one-row csv file.
+    pub fn write_csv(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        writer.serialize(self)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Coefficients {
+    /// Save the coefficients as a one-row csv file.
+    pub fn write_csv(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        writer.serialize(self)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Station {
+    /// The length of one side of this station's outline that is below
+    /// the given waterline height.
+    pub fn immersed_girth(
+        &self,
+        waterline: f32,
+    ) -> Result<f32, LapstrakeError> {
+        let samples = self.spline.sample(None)?;
+        let mut girth = 0.;
+        for pair in samples.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            let length = (q - p).norm();
+            if p.z <= waterline && q.z <= waterline {
+                girth += length;
+            } else if p.z <= waterline || q.z <= waterline {
+                // Only part of this segment is immersed.
+                let (low, high) = if p.z < q.z { (p, q) } else { (q, p) };
+                girth += length * (waterline - low.z) / (high.z - low.z);
+            }
+        }
+        Ok(girth)
+    }
+
+    // The fore-aft position where this station's outline crosses the
+    // given height, or None if it doesn't reach it.
+    fn position_at_height(
+        &self,
+        height: f32,
+    ) -> Result<Option<f32>, LapstrakeError> {
+        let samples = self.spline.sample(None)?;
+        for pair in samples.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            if (p.z <= height && height <= q.z)
+                || (q.z <= height && height <= p.z)
+            {
+                if practically_zero(q.z - p.z) {
+                    return Ok(Some(p.x));
+                }
+                let t = (height - p.z) / (q.z - p.z);
+                return Ok(Some(p.x + t * (q.x - p.x)));
+            }
+        }
+        Ok(None)
+    }
+
+    // The height of the lowest point on this station.
+    fn bottom(&self) -> f32 {
+        self.points.iter().fold(f32::INFINITY, |low, p| low.min(p.z))
+    }
+}
+
+// Find where the waterline ends, going along the stations in the given
+// order. If the first station is immersed, that's where its profile
+// crosses the waterline. Otherwise it's between the first immersed
+// station and the one before it, where the keel dips below the
+// waterline.
+fn waterline_end<'a, I>(
+    stations: I,
+    waterline: f32,
+) -> Result<Option<f32>, LapstrakeError>
+where
+    I: Iterator<Item = &'a Station>,
+{
+    let mut previous: Option<&Station> = None;
+    for station in stations {
+        if let Some(x) = station.position_at_height(waterline)? {
+            let prev = match previous {
+                None => return Ok(Some(x)),
+                Some(prev) => prev,
+            };
+            let (x0, x1) = (prev.position(), station.position());
+            let (z0, z1) = (prev.bottom(), station.bottom());
+            if z0 <= waterline || practically_zero(z0 - z1) {
+                return Ok(Some(x0));
+            }
+            let t = ((z0 - waterline) / (z0 - z1)).min(1.);
+            return Ok(Some(x0 + t * (x1 - x0)));
+        }
+        previous = Some(station);
+    }
+    Ok(None)
+}
+
+impl fmt::Display for Hydrostatics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let units = self.units;
+        writeln!(f, "Waterline:        {}", units.from_feet(self.waterline))?;
+        let volume = units.describe_volume(self.volume);
+        writeln!(f, "Volume:           {}", volume)?;
+        writeln!(f, "Displacement:     {:.0} lb", self.displacement)?;
+        writeln!(f, "LCB:              {}", units.from_feet(self.lcb))?;
+        let waterplane_area = units.describe_area(self.waterplane_area);
+        writeln!(f, "Waterplane area:  {}", waterplane_area)?;
+        let wetted_surface = units.describe_area(self.wetted_surface);
+        write!(f, "Wetted surface:   {}", wetted_surface)
+    }
+}
+
+impl fmt::Display for Coefficients {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let units = self.units;
+        writeln!(f, "Waterline:        {}", units.from_feet(self.waterline))?;
+        writeln!(f, "LWL:              {}", units.from_feet(self.lwl))?;
+        writeln!(f, "Beam (BWL):       {}", units.from_feet(self.beam))?;
+        writeln!(f, "Draft:            {}", units.from_feet(self.draft))?;
+        writeln!(f, "Block (Cb):       {:.3}", self.block)?;
+        writeln!(f, "Prismatic (Cp):   {:.3}", self.prismatic)?;
+        write!(f, "Midship (Cm):     {:.3}", self.midship)
+    }
+}
+
+// Integrate a function given as (x, value) samples, sorted by x, with
+// the trapezoid rule.
+fn integrate(samples: &[(f32, f32)]) -> f32 {
+    samples
+        .windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0) * (pair[0].1 + pair[1].1) / 2.)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scad_dots::utils::P3;
+    use spec::Spec;
+    use spline::Interpolation;
+    use std::path::Path;
+
+    // A box 4 ft long, 2 ft wide, and 1 ft deep, with a station at each
+    // end and one in the middle.
+    fn box_hull() -> Hull {
+        let mut hull = Spec::load_from(Path::new("templates"))
+            .and_then(|spec| spec.get_hull())
+            .unwrap();
+        let threshold = hull.equality_threshold();
+        let station = |x: f32| {
+            let points = vec![
+                P3::new(x, 0., 0.),
+                P3::new(x, 1., 0.),
+                P3::new(x, 1., 1.),
+            ];
+            Station::with_knuckles(
+                format!("{}", x),
+                points,
+                4,
+                Interpolation::default(),
+                threshold,
+                &[1],
+            ).unwrap()
+        };
+        hull.stations = vec![station(0.), station(2.), station(4.)];
+        hull
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_box_hydrostatics() {
+        let hydrostatics = box_hull().hydrostatics(0.5, 64.).unwrap();
+        assert_near(hydrostatics.volume, 4.);
+        assert_near(hydrostatics.displacement, 256.);
+        assert_near(hydrostatics.lcb, 2.);
+        assert_near(hydrostatics.waterplane_area, 8.);
+        // Each side is wetted across the bottom and half way up.
+        assert_near(hydrostatics.wetted_surface, 12.);
+    }
+
+    #[test]
+    fn test_box_coefficients() {
+        let coefficients = box_hull().coefficients(0.5).unwrap();
+        assert_near(coefficients.lwl, 4.);
+        assert_near(coefficients.beam, 2.);
+        assert_near(coefficients.draft, 0.5);
+        // A box fills its own box completely.
+        assert_near(coefficients.block, 1.);
+        assert_near(coefficients.prismatic, 1.);
+        assert_near(coefficients.midship, 1.);
+    }
+}
@@ -0,0 +1,41 @@
+//! Start a new project, with a small example hull to edit.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error::{LapstrakeError, ResultExt};
+
+// The starter spec sheets, as (filename, contents).
+const STARTER_FILES: &[(&str, &str)] = &[
+    ("data.csv", include_str!("../templates/data.csv")),
+    ("planks.csv", include_str!("../templates/planks.csv")),
+    ("config.csv", include_str!("../templates/config.csv")),
+];
+
+/// Create a folder holding a small but complete set of spec sheets, for
+/// a simple four-station hull, with notes explaining their layout.
+/// Refuses to overwrite any existing sheets. Returns the paths of the
+/// files that were written.
+pub fn init_project(folder: &Path) -> Result<Vec<PathBuf>, LapstrakeError> {
+    for &(filename, _) in STARTER_FILES {
+        let path = folder.join(filename);
+        if path.exists() {
+            return Err(LapstrakeError::General(format!(
+                "Refusing to overwrite existing spec sheet {:?}.",
+                path
+            )));
+        }
+    }
+    fs::create_dir_all(folder)
+        .with_context(|| format!("Failed to create folder {:?}", folder))?;
+
+    let mut written = vec![];
+    for &(filename, contents) in STARTER_FILES {
+        let path = folder.join(filename);
+        println!("Saving file: {:?}.", path);
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        written.push(path);
+    }
+    Ok(written)
+}
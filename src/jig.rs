@@ -0,0 +1,373 @@
+//! Building jigs: station molds that the hull is planked over upside
+//! down, cross-halved into the spines that hold them at their stations.
+//! The spines are either a single strongback beam, or a keel board and
+//! stringers that make an interlocking egg-crate.
+
+use scad_dots::utils::{Axis, P2, P3, V3};
+
+use draw::Drawing;
+use error::LapstrakeError;
+use hull::{Hull, Station};
+use render_2d::{
+    Bounded, PathStyle2, SvgDoc, SvgGroup, SvgPath, SvgText, TextStyle,
+};
+use render_3d::ScadSurface;
+use spec::PlankStation;
+use util::{practically_zero, project_points, reflect2, sort_by_number};
+
+/// A station mold, to be set up on the spines.
+#[derive(Debug, Clone)]
+pub struct Mold {
+    pub name: String,
+    /// The fore-aft position of the mold's face that matches the station.
+    pub position: f32,
+    /// Which way the mold's thickness goes from its face: +1 aft or -1
+    /// forward. It always goes towards midships, where the hull is
+    /// larger, so the mold doesn't stick out past the planking.
+    pub direction: f32,
+    /// The mold's outline, as (breadth, height) points, with a slot in
+    /// its top for each spine that crosses it, and one in its bottom for
+    /// the trunk if it's in the way.
+    pub outline: Vec<P2>,
+}
+
+/// A board running fore and aft, standing on edge, that the molds are
+/// slotted onto.
+#[derive(Debug, Clone)]
+pub struct Spine {
+    pub name: String,
+    /// The breadth of the spine's centerline.
+    pub breadth: f32,
+    /// How wide the spine is, across the hull.
+    pub width: f32,
+    /// The spine's side profile, as (fore-aft, height) points, with a
+    /// slot in its bottom for each mold that crosses it.
+    pub outline: Vec<P2>,
+}
+
+/// The station molds and the spines they're slotted into. The hull is
+/// built upside down on it, so the jig's base is above the sheer in the
+/// hull's coordinates.
+#[derive(Debug, Clone)]
+pub struct Jig {
+    pub molds: Vec<Mold>,
+    pub spines: Vec<Spine>,
+    /// How thick the molds are cut.
+    pub thickness: f32,
+}
+
+// Where a spine crosses a mold. The mold's slot runs from the jig's base
+// down to `middle`, and the spine's runs from `bottom` up to it.
+struct Crossing {
+    middle: f32,
+    bottom: f32,
+}
+
+impl Hull {
+    /// Lay out a building jig on a strongback, with a mold at each station
+    /// and at each position that planks.csv measures at. The molds reach
+    /// up to a strongback `clearance` above the highest sheer, which is
+    /// `strongback_width` wide and `strongback_depth` deep. The molds and
+    /// strongback are notched halfway into each other.
+    pub fn jig(
+        &self,
+        clearance: f32,
+        strongback_width: f32,
+        strongback_depth: f32,
+    ) -> Result<Jig, LapstrakeError> {
+        if clearance <= strongback_depth / 2. {
+            return Err(LapstrakeError::General(
+                "The jig's clearance must be more than half the depth of its \
+                 strongback, so the notches stay clear of the hull."
+                    .into(),
+            ));
+        }
+        let top = self.max_coord(Axis::Z) + clearance;
+        let spines = vec![("Strongback".to_string(), 0., strongback_width)];
+        // Run the strongback a little past the end molds.
+        self.slotted_jig(top, &spines, strongback_depth, |_, _| {
+            Some(Crossing {
+                middle: top - strongback_depth / 2.,
+                bottom: top - strongback_depth,
+            })
+        })
+    }
+
+    /// Lay out an interlocking egg-crate jig, with a mold at each station
+    /// and at each position that planks.csv measures at. The molds reach
+    /// up to a flat base `clearance` above the highest sheer. They're
+    /// cross-halved with a keel board along the centerline, and with a
+    /// pair of stringers at each of the `stringers` breadths. The spines
+    /// reach down to the hull, and are cut from the config sheet's
+    /// material thickness, like the molds.
+    pub fn egg_crate(
+        &self,
+        clearance: f32,
+        stringers: &[f32],
+    ) -> Result<Jig, LapstrakeError> {
+        let top = self.max_coord(Axis::Z) + clearance;
+        let width = self.template_layout.material_thickness;
+        let mut spines = vec![("Keel board".to_string(), 0., width)];
+        for &breadth in stringers {
+            let name =
+                format!("Stringer at {:?}", self.units.from_feet(breadth));
+            spines.push((format!("{} starboard", name), breadth, width));
+            spines.push((format!("{} port", name), -breadth, width));
+        }
+        self.slotted_jig(top, &spines, 0., |section, breadth| {
+            height_at_breadth(section, breadth.abs()).map(|height| Crossing {
+                middle: (height + top) / 2.,
+                bottom: height,
+            })
+        })
+    }
+
+    // Lay out a jig whose molds reach up to a base at height `top`,
+    // slotted into each of the `spines`, given as (name, breadth, width).
+    // `crossing` says where a spine at some breadth crosses a mold with
+    // the given section, if it does. The spines run `overhang` past the
+    // end molds that they cross.
+    fn slotted_jig<F>(
+        &self,
+        top: f32,
+        spines: &[(String, f32, f32)],
+        overhang: f32,
+        crossing: F,
+    ) -> Result<Jig, LapstrakeError>
+    where
+        F: Fn(&[P2], f32) -> Option<Crossing>,
+    {
+        let layout = &self.template_layout;
+        let thickness = layout.material_thickness;
+        let midships = self.midships();
+        let hallucinated = self.hallucinate_plank_stations()?;
+        let mut stations: Vec<&Station> = vec![];
+        for station in self.stations.iter().chain(&hallucinated) {
+            if !stations.iter().any(|s| s.name == station.name) {
+                stations.push(station);
+            }
+        }
+        // Drop stations at the same position as another, which would make
+        // duplicate molds. The sort is stable, so the hull's own stations
+        // come before hallucinated ones there, and they're the ones kept.
+        sort_by_number(&mut stations, |s| s.position())?;
+        let threshold = self.equality_threshold;
+        stations.dedup_by(|later, earlier| {
+            (later.position() - earlier.position()).abs() < threshold
+        });
+        if stations.is_empty() {
+            return Err(LapstrakeError::General(
+                "The jig needs at least one station.".into(),
+            ));
+        }
+
+        let mut molds = vec![];
+        // Where each spine crosses each mold, as (fore, aft, crossing).
+        let mut crossed: Vec<Vec<(f32, f32, Crossing)>> =
+            spines.iter().map(|_| vec![]).collect();
+        for station in stations {
+            // Up the starboard side from the keel.
+            let section: Vec<P2> = if layout.inside_planking {
+                station
+                    .cross_section_points(self.plank_thickness)?
+                    .into_iter()
+                    .filter(|p| p.x >= 0.)
+                    .rev()
+                    .collect()
+            } else {
+                let samples = station.spline.sample(None)?;
+                project_points(Axis::X, &samples)
+            };
+            let position = station.position();
+            let direction = if position < midships { 1. } else { -1. };
+            let far = position + direction * thickness;
+            let (fore, aft) = (position.min(far), position.max(far));
+
+            // Slots across the mold's top, from port to starboard.
+            let mut slots = vec![];
+            for (i, &(_, breadth, width)) in spines.iter().enumerate() {
+                if let Some(cross) = crossing(&section, breadth) {
+                    slots.push((breadth, width, cross.middle));
+                    crossed[i].push((fore, aft, cross));
+                }
+            }
+            sort_by_number(&mut slots, |slot| slot.0)?;
+
+            // Down the port side from the sheer, and up the starboard side.
+            let port = reflect2(Axis::X, &section);
+            let mut outline: Vec<P2> = port.into_iter().rev().collect();
+            outline.extend(section.iter());
+            let sheer = section[section.len() - 1].x;
+            outline.push(P2::new(sheer, top));
+            for &(breadth, width, middle) in slots.iter().rev() {
+                outline.extend(vec![
+                    P2::new(breadth + width / 2., top),
+                    P2::new(breadth + width / 2., middle),
+                    P2::new(breadth - width / 2., middle),
+                    P2::new(breadth - width / 2., top),
+                ]);
+            }
+            outline.push(P2::new(-sheer, top));
+            if let Some(trunk) = self.trunk_through(position) {
+                println!("Mold {} is slotted for the trunk.", station.name);
+                outline = trunk.notch(&outline);
+            }
+            molds.push(Mold {
+                name: station.name.clone(),
+                position: position,
+                direction: direction,
+                outline: outline,
+            });
+        }
+
+        let mut slotted = vec![];
+        for (&(ref name, breadth, width), crossings) in
+            spines.iter().zip(crossed)
+        {
+            if crossings.is_empty() {
+                return Err(LapstrakeError::General(format!(
+                    "The jig's {} doesn't cross any molds.",
+                    name.to_lowercase()
+                )));
+            }
+            let (start, _, ref first) = crossings[0];
+            let (_, end, ref last) = crossings[crossings.len() - 1];
+            let mut bottom_edge = vec![];
+            for &(fore, aft, ref cross) in &crossings {
+                bottom_edge.extend(vec![
+                    P2::new(fore, cross.bottom),
+                    P2::new(fore, cross.middle),
+                    P2::new(aft, cross.middle),
+                    P2::new(aft, cross.bottom),
+                ]);
+            }
+            if overhang > 0. {
+                bottom_edge.insert(0, P2::new(start - overhang, first.bottom));
+                bottom_edge.push(P2::new(end + overhang, last.bottom));
+            } else {
+                // The end slots are open to the ends of the spine.
+                bottom_edge.remove(0);
+                bottom_edge.pop();
+            }
+            let mut outline = vec![P2::new(start - overhang, top)];
+            outline.extend(bottom_edge);
+            outline.push(P2::new(end + overhang, top));
+            slotted.push(Spine {
+                name: name.clone(),
+                breadth: breadth,
+                width: width,
+                outline: outline,
+            });
+        }
+
+        Ok(Jig {
+            molds: molds,
+            spines: slotted,
+            thickness: thickness,
+        })
+    }
+
+    /// Draw cut templates for the jig: each of its molds, and the side of
+    /// each of its spines.
+    pub fn draw_jig(&self, jig: &Jig) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        let palette = self.theme.palette();
+        let part = |name: &str, outline: &[P2]| {
+            let path = SvgPath::new(outline.to_vec())
+                .stroke(palette.outline, STROKE)
+                .style(PathStyle2::Line)
+                .close();
+            let bound = path.bound().expect("jig part has no bound");
+            let mut group = SvgGroup::new();
+            group.append(path);
+            group.append(SvgText {
+                lines: vec![name.to_string()],
+                pos: bound.relative_pos(0.5, 0.8),
+                color: palette.label,
+                size: 0.25,
+                style: TextStyle::default(),
+            });
+            group
+        };
+        let molds: Vec<SvgGroup> = jig
+            .molds
+            .iter()
+            .map(|mold| part(&mold.name, &mold.outline))
+            .collect();
+        let mut parts = vec![self.scale_bar()?];
+        parts.push(SvgGroup::new_grid(molds, 1.1)?);
+        for spine in &jig.spines {
+            parts.push(part(&spine.name, &spine.outline));
+        }
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(parts, 1.1)?);
+        self.run_drawing_passes(Drawing::Jig, doc)
+    }
+
+    // Construct the stations that planks.csv measures at, other than the
+    // hull's own.
+    fn hallucinate_plank_stations(
+        &self,
+    ) -> Result<Vec<Station>, LapstrakeError> {
+        let mut stations = vec![];
+        for station in &self.planks.stations {
+            if let PlankStation::Position(posn) = *station {
+                stations.push(self.hallucinate_station(posn)?);
+            }
+        }
+        Ok(stations)
+    }
+}
+
+impl Jig {
+    /// Model the molds and spines as solid slabs of their thickness, set
+    /// up as they'll be for building.
+    pub fn render(&self) -> Result<ScadSurface, LapstrakeError> {
+        let mut parts = vec![];
+        for mold in &self.molds {
+            let outline: Vec<P3> = mold
+                .outline
+                .iter()
+                .map(|p| P3::new(mold.position, p.x, p.y))
+                .collect();
+            let direction = V3::new(mold.direction * self.thickness, 0., 0.);
+            parts.push(ScadSurface::extrude(&outline, direction)?);
+        }
+        for spine in &self.spines {
+            let side = spine.breadth - spine.width / 2.;
+            let outline: Vec<P3> = spine
+                .outline
+                .iter()
+                .map(|p| P3::new(p.x, side, p.y))
+                .collect();
+            let direction = V3::new(0., spine.width, 0.);
+            parts.push(ScadSurface::extrude(&outline, direction)?);
+        }
+        Ok(ScadSurface::join(parts))
+    }
+}
+
+// The height of a station's section at the given breadth, on the way up
+// from the keel to its greatest breadth, or None if it's never that wide.
+// The section's points are (breadth, height), starting at the keel.
+fn height_at_breadth(section: &[P2], breadth: f32) -> Option<f32> {
+    let keel = section.first()?;
+    if breadth <= keel.x {
+        return Some(keel.y);
+    }
+    for pair in section.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        if q.x < p.x {
+            // Past the greatest breadth, into the tumblehome.
+            break;
+        }
+        if p.x <= breadth && breadth <= q.x {
+            if practically_zero(q.x - p.x) {
+                return Some(p.y);
+            }
+            let t = (breadth - p.x) / (q.x - p.x);
+            return Some(p.y + t * (q.y - p.y));
+        }
+    }
+    None
+}
@@ -0,0 +1,101 @@
+extern crate csv;
+#[cfg(feature = "thumbnails")]
+extern crate image;
+extern crate nalgebra;
+#[cfg(feature = "python")]
+extern crate pyo3;
+extern crate rayon;
+#[macro_use]
+extern crate scad_dots;
+#[macro_use]
+extern crate scad_dots_derive;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate svg;
+extern crate zip;
+
+mod archive;
+mod backbone;
+mod ballast;
+mod bspline;
+mod cache;
+mod catmullrom;
+mod check;
+mod cradle;
+mod curvature;
+mod deck;
+mod draw;
+mod dump;
+mod dxf;
+mod error;
+mod export;
+mod fair;
+mod fairness;
+mod fill;
+mod frame;
+mod gcode;
+mod girth;
+mod hull;
+mod hydrostatics;
+mod init;
+mod jig;
+mod lift;
+mod lining;
+mod load;
+mod obj;
+mod offsets;
+mod pdf;
+mod plank;
+#[cfg(feature = "python")]
+mod python;
+pub mod render_2d; // only public because of doctests - can we avoid that?
+pub mod render_3d;
+mod segment;
+mod shell;
+mod spec;
+mod spline;
+mod stem;
+#[cfg(feature = "thumbnails")]
+mod thumbnail;
+mod transom;
+mod trunk;
+mod unit;
+mod util;
+
+pub use archive::write_archive;
+pub use ballast::LEAD_DENSITY;
+pub use cache::HullCache;
+pub use check::SpecProblem;
+pub use draw::{
+    BodyPlan, Drawing, DrawingPass, Layer, Palette, TemplateLayout, Theme,
+    TitleBlock,
+};
+pub use dump::{HullDump, PlankDump, StationDump};
+pub use dxf::DxfExport;
+pub use error::{LapstrakeError, ResultExt};
+pub use fair::{UnfairMeasurement, DEFAULT_FAIRING_TOLERANCE};
+pub use fill::FilledMeasurement;
+pub use frame::FrameBevel;
+pub use gcode::GcodeExport;
+pub use girth::Girth;
+pub use hull::{Hull, Station};
+pub use hydrostatics::{
+    Coefficients, Hydrostatics, FRESH_WATER_DENSITY, SALT_WATER_DENSITY,
+};
+pub use init::init_project;
+pub use jig::{Jig, Mold, Spine};
+pub use lift::Lift;
+pub use lining::PlankRules;
+pub use load::SpecSource;
+pub use pdf::{Paper, PdfExport};
+pub use plank::{
+    Bevel, ConflictKind, FlattenedPlank, Plank, PlankConflict, PlankDistortion,
+};
+pub use render_3d::preview_model;
+pub use spec::{
+    Backbone, Ballast, PlankStation, Planks, Spec, Stem, Transom, Trunk,
+};
+pub use spline::{Curvature, EndCondition, Interpolation, Spline};
+pub use unit::{Feet, Length, Units};
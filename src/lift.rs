@@ -0,0 +1,254 @@
+//! Lifts for a bread-and-butter half model: boards of equal thickness,
+//! stacked up from the keel to the sheer, that are each cut out to the
+//! hull's half-breadth across their faces, doweled together, and carved
+//! down to its shape.
+
+use std::f32;
+
+use scad_dots::utils::{Axis, P2, P3, V2};
+
+use draw::{Drawing, Layer};
+use error::LapstrakeError;
+use hull::{Hull, Station};
+use render_2d::{
+    Align, Baseline, Bounded, PathStyle2, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use spline::MIN_SPLINE_POINTS;
+use util::{practically_zero, project_points, sort_by_number};
+
+/// One board of a bread-and-butter half model.
+#[derive(Debug, Clone)]
+pub struct Lift {
+    pub name: String,
+    /// The heights of the lift's bottom and top faces.
+    pub low: f32,
+    pub high: f32,
+    /// The outline to cut the lift to, as (fore-aft, half-breadth)
+    /// points: the widest the hull gets anywhere between its faces,
+    /// closed along the centerline.
+    pub outline: Vec<P2>,
+    /// The waterlines at the lift's faces, as (fore-aft, half-breadth)
+    /// points, to carve it down to. Faces that the hull doesn't reach
+    /// all the way along are left out.
+    pub faces: Vec<Vec<P2>>,
+    /// Where each station crosses the outline, for lining up templates
+    /// while carving.
+    pub stations: Vec<(String, P2)>,
+}
+
+impl Hull {
+    /// Slice the hull into lifts of the given thickness, from the bottom
+    /// of the keel up past the highest point of the sheer. Where the
+    /// sheer dips below a lift, it's cut to the sheer's breadth there, to
+    /// be carved down to the sheer afterwards.
+    pub fn lifts(&self, thickness: f32) -> Result<Vec<Lift>, LapstrakeError> {
+        if thickness <= 0. || practically_zero(thickness) {
+            return Err(LapstrakeError::General(format!(
+                "Lifts must be thicker than {}.",
+                thickness
+            )));
+        }
+        let mut stations: Vec<&Station> = self.stations.iter().collect();
+        sort_by_number(&mut stations, |station| station.position())?;
+        let bottom = stations
+            .iter()
+            .map(|s| s.points[0].z)
+            .fold(f32::INFINITY, f32::min);
+        let top = stations
+            .iter()
+            .map(|s| s.sheer().z)
+            .fold(-f32::INFINITY, f32::max);
+        let count = ((top - bottom) / thickness).ceil().max(1.) as usize;
+
+        let mut lifts = vec![];
+        for i in 0..count {
+            let low = bottom + i as f32 * thickness;
+            let high = low + thickness;
+            let mut points = vec![];
+            let mut crossings = vec![];
+            for station in &stations {
+                if let Some(breadth) = widest_between(station, low, high)? {
+                    points.push(P3::new(station.position(), breadth, low));
+                    crossings.push((
+                        station.name.clone(),
+                        P2::new(station.position(), breadth),
+                    ));
+                }
+            }
+            let name = format!("Lift {}", i + 1);
+            if points.len() < MIN_SPLINE_POINTS {
+                println!(
+                    "Warning: skipping {}, which crosses too few stations.",
+                    name
+                );
+                continue;
+            }
+            let first = crossings[0].1;
+            let last = crossings[crossings.len() - 1].1;
+            let line = self.spline_through(points)?.sample(None)?;
+            let mut outline = vec![];
+            if !practically_zero(first.y) {
+                outline.push(P2::new(first.x, 0.));
+            }
+            outline.extend(project_points(Axis::Z, &line));
+            if !practically_zero(last.y) {
+                outline.push(P2::new(last.x, 0.));
+            }
+            let mut faces = vec![];
+            for &height in &[low, high] {
+                if let Some(face) = self.face_line(&stations, height)? {
+                    faces.push(face);
+                }
+            }
+            lifts.push(Lift {
+                name: name,
+                low: low,
+                high: high,
+                outline: outline,
+                faces: faces,
+                stations: crossings,
+            });
+        }
+        Ok(lifts)
+    }
+
+    /// Draw cut templates for the lifts, each with the waterlines at its
+    /// faces and the stations marked on it, and alignment holes for the
+    /// dowels that hold the stack together. `kerf` is the width of the
+    /// cut, in feet, that each outline is grown to make up for.
+    pub fn draw_lifts(
+        &self,
+        lifts: &[Lift],
+        kerf: f32,
+    ) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        let palette = self.theme.palette();
+        let paths: Vec<SvgPath> = lifts
+            .iter()
+            .map(|lift| {
+                SvgPath::new(lift.outline.clone())
+                    .stroke(palette.outline, STROKE)
+                    .style(PathStyle2::Line)
+                    .close()
+            })
+            .collect();
+        let bounds: Vec<_> = paths.iter().map(|path| path.bound()).collect();
+        // The dowels run down through the stack, so the holes are spread
+        // out fore and aft, along the length of the lifts.
+        let fractions = self
+            .template_layout
+            .hole_fractions()
+            .into_iter()
+            .map(|(across, up)| (up, across))
+            .collect();
+        let (holes, holes_bound) =
+            self.alignment_holes(&bounds, fractions, kerf)?;
+
+        let mut templates = vec![];
+        for (lift, path) in lifts.iter().zip(paths) {
+            let mut marks = SvgGroup::new();
+            for face in &lift.faces {
+                marks.append(
+                    SvgPath::new(face.clone())
+                        .stroke(palette.grid, STROKE / 2.)
+                        .style(PathStyle2::Dashed),
+                );
+            }
+            for &(_, crossing) in &lift.stations {
+                marks.append(
+                    SvgPath::new(vec![P2::new(crossing.x, 0.), crossing])
+                        .stroke(palette.grid, STROKE / 2.)
+                        .style(PathStyle2::Line),
+                );
+            }
+            let label = SvgText {
+                lines: vec![lift.name.clone()],
+                pos: holes_bound.center(),
+                color: palette.outline,
+                size: 2. * self.template_layout.hole_diameter,
+                style: TextStyle::default(),
+            }.anchored(Align::Middle, Baseline::Central);
+
+            let mut group = SvgGroup::new();
+            group.append(Layer::Stations.wrap(path.offset(kerf / 2.)?));
+            group.append(marks.layer(Layer::Marks.name()));
+            group.append(Layer::Labels.wrap(label));
+            group.append(Layer::Holes.wrap(holes.place(V2::new(0., 0.))));
+            templates.push(group);
+        }
+        // Top lift first, the way they stack.
+        templates.reverse();
+
+        let mut doc = SvgDoc::new();
+        doc.define(&holes);
+        let parts =
+            vec![self.scale_bar()?, SvgGroup::new_vertical(templates, 1.1)?];
+        doc.append(SvgGroup::new_vertical(parts, 1.1)?);
+        self.run_drawing_passes(Drawing::Lifts, doc)
+    }
+
+    // The waterline at the given height, as (fore-aft, half-breadth)
+    // points, or None if the hull doesn't reach that height all the way
+    // along between the first and last stations that it crosses, or if it
+    // crosses too few of them to draw a line through.
+    fn face_line(
+        &self,
+        stations: &[&Station],
+        height: f32,
+    ) -> Result<Option<Vec<P2>>, LapstrakeError> {
+        let mut breadths = vec![];
+        for station in stations {
+            breadths.push(station.breadth_at_height(height)?);
+        }
+        let first = breadths.iter().position(|b| b.is_some());
+        let last = breadths.iter().rposition(|b| b.is_some());
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(None),
+        };
+        if last + 1 < first + MIN_SPLINE_POINTS {
+            return Ok(None);
+        }
+        let mut points = vec![];
+        for i in first..last + 1 {
+            match breadths[i] {
+                Some(breadth) => points.push(P3::new(
+                    stations[i].position(),
+                    breadth,
+                    height,
+                )),
+                None => return Ok(None),
+            }
+        }
+        let line = self.spline_through(points)?.sample(None)?;
+        Ok(Some(project_points(Axis::Z, &line)))
+    }
+}
+
+// The greatest half-breadth of the station between the given heights, or
+// None if it doesn't reach down to the higher one. Where the station's
+// sheer is below both, that's its breadth at the sheer.
+fn widest_between(
+    station: &Station,
+    low: f32,
+    high: f32,
+) -> Result<Option<f32>, LapstrakeError> {
+    let floor = low.min(station.sheer().z);
+    let mut breadths: Vec<f32> = station
+        .spline
+        .sample(None)?
+        .into_iter()
+        .filter(|p| floor <= p.z && p.z <= high)
+        .map(|p| p.y)
+        .collect();
+    for &height in &[low, high] {
+        if let Some(breadth) = station.breadth_at_height(height)? {
+            breadths.push(breadth);
+        }
+    }
+    if breadths.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(breadths.into_iter().fold(0., f32::max)))
+}
@@ -0,0 +1,121 @@
+//! Lining off: dividing the hull into planks automatically, to give a
+//! starting point for planks.csv that can then be adjusted by eye.
+
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use hull::Hull;
+use spec::{PlankStation, Planks};
+use util::practically_zero;
+
+/// How to divide the hull into planks.
+#[derive(Debug, Clone, Copy)]
+pub struct PlankRules {
+    /// How many times wider the garboard (the bottom plank) should be
+    /// than the sheer plank. The planks in between change width evenly.
+    pub garboard_ratio: f32,
+    /// How far each plank laps onto the plank below it, in feet.
+    pub lap: f32,
+}
+
+impl Default for PlankRules {
+    fn default() -> PlankRules {
+        PlankRules {
+            garboard_ratio: 1.,
+            lap: 0.,
+        }
+    }
+}
+
+impl Hull {
+    /// Divide the girth of every station into `n` planks, following the
+    /// given rules. Each plank is the same fraction of the girth at every
+    /// station, so the planks narrow towards the ends of the hull along
+    /// with the stations.
+    pub fn auto_planks(
+        &self,
+        n: usize,
+        rules: &PlankRules,
+    ) -> Result<Planks, LapstrakeError> {
+        if n == 0 {
+            return Err(LapstrakeError::General(
+                "There must be at least one plank.".into(),
+            ));
+        }
+        if !(rules.garboard_ratio > 0.) {
+            return Err(LapstrakeError::General(format!(
+                "The garboard ratio must be positive, not {}.",
+                rules.garboard_ratio
+            )));
+        }
+        // The relative width of each plank, from the garboard up.
+        let weights: Vec<f32> = (0..n)
+            .map(|k| {
+                let t = if n == 1 { 1. } else { k as f32 / (n - 1) as f32 };
+                rules.garboard_ratio + t * (1. - rules.garboard_ratio)
+            })
+            .collect();
+        let total: f32 = weights.iter().sum();
+        let mut seams = vec![0.];
+        for weight in &weights {
+            let last = seams[seams.len() - 1];
+            seams.push(last + weight / total);
+        }
+
+        let mut rows = vec![vec![]; 2 * n];
+        for station in &self.stations {
+            let girth = station.girth();
+            if practically_zero(girth) {
+                return Err(LapstrakeError::General(format!(
+                    "Station {} has no girth to divide into planks.",
+                    station.name
+                )));
+            }
+            let lap = rules.lap / girth;
+            for k in 0..n {
+                let bottom = if k == 0 { 0. } else { seams[k] - lap };
+                rows[2 * k].push(Some(bottom.max(0.)));
+                rows[2 * k + 1].push(Some(seams[k + 1].min(1.)));
+            }
+        }
+        Ok(Planks {
+            stations: self
+                .stations
+                .iter()
+                .map(|station| PlankStation::Station(station.name.clone()))
+                .collect(),
+            plank_locations: rows,
+        })
+    }
+}
+
+impl Planks {
+    /// Save in the same format as the planks.csv spec sheet.
+    pub fn write_csv(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        let mut header = vec!["Plank Lines".to_string()];
+        for station in &self.stations {
+            header.push(match *station {
+                PlankStation::Station(ref name) => name.clone(),
+                PlankStation::Position(posn) => format!("{:?}", posn),
+            });
+        }
+        writer.write_record(&header)?;
+        for (i, row) in self.plank_locations.iter().enumerate() {
+            let mut record = vec![format!("{}", i / 2 + 1)];
+            for fraction in row {
+                record.push(match *fraction {
+                    Some(fraction) => format!("{:.4}", fraction),
+                    None => "x".into(),
+                });
+            }
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
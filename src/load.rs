@@ -1,22 +1,38 @@
-//! Read in ship data from csv files.
+//! Read in ship data from csv files, or from a single json file.
 
+use std::fmt;
 use std::fs;
-// use std::io;
+use std::io;
 use std::iter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use csv;
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json;
 
 use error::{LapstrakeError, ResultExt};
 use spec::*;
 use unit::*;
 
+/// The word after the name of a height or breadth line that marks it
+/// as a knuckle, like a chine.
+pub(crate) const KNUCKLE_MARK: &str = "knuckle";
+
+/// Where to read one of the spec sheets from.
+#[derive(Debug, Clone)]
+pub enum SpecSource {
+    File(PathBuf),
+    Stdin,
+}
+
 #[derive(Debug)]
 enum Section {
     Positions,
     Heights,
     Breadths,
+    Diagonals,
+    StemProfile,
 }
 
 impl Spec {
@@ -28,40 +44,87 @@ impl Spec {
         let path_to = |filename: &str| {
             let mut path = directory.to_path_buf();
             path.push(filename);
-            path
+            SpecSource::File(path)
         };
 
-        let data = Data::load_from(&path_to("data.csv"))
+        Spec::load_from_sources(
+            &path_to("data.csv"),
+            &path_to("planks.csv"),
+            &path_to("config.csv"),
+        )
+    }
+
+    /// Read the three spec sheets from wherever they happen to be. At
+    /// most one of them can come from stdin.
+    pub fn load_from_sources(
+        data: &SpecSource,
+        planks: &SpecSource,
+        config: &SpecSource,
+    ) -> Result<Spec, LapstrakeError> {
+        let num_stdin = [data, planks, config]
+            .iter()
+            .filter(|source| source.is_stdin())
+            .count();
+        if num_stdin > 1 {
+            return Err(LapstrakeError::load(
+                "Only one spec sheet can be read from stdin.",
+            ));
+        }
+
+        // The config says which units the other sheets are in.
+        let config =
+            Config::load_from(config).context("Failed to load config sheet")?;
+
+        let data = Data::load_from(data, config.units)
             .context("Failed to load data sheet")?;
 
-        let planks = Planks::load_from(&path_to("planks.csv"))
+        let planks = Planks::load_from(planks, config.units, &data.stations)
             .context("Failed to load planks sheet")?;
 
-        let config = Config::load_from(&path_to("config.csv"))
-            .context("Failed to load config sheet")?;
-
-        Ok(Spec {
+        Spec {
             data,
             planks,
             config,
-        })
+        }.prepare()
+    }
+
+    /// Read the whole spec, including the data, planks, and config, from a
+    /// single json file. This is easier than the spreadsheets for other
+    /// programs to generate. The fields match the `Spec` struct.
+    pub fn load_from_json(path: &Path) -> Result<Spec, LapstrakeError> {
+        println!("Loading from file {:?}.", path);
+        let file = fs::File::open(path)
+            .with_context(|| format!("Could not read json file: {:?}.", path))?;
+        let mut spec: Spec = serde_json::from_reader(file)
+            .with_context(|| format!("Failed to parse json spec {:?}", path))?;
+        spec.data.add_stem_station();
+        spec.prepare()
+    }
+
+    // Check that the spec is consistent, and fill in any omitted
+    // measurements if requested.
+    fn prepare(mut self) -> Result<Spec, LapstrakeError> {
+        self.config.check_units()?;
+        self.data.check_row_lengths()?;
+        if self.config.fill_missing {
+            for filled in self.data.fill_missing()? {
+                println!("Inferred missing measurement: {}", filled);
+            }
+        }
+        Ok(self)
     }
 }
 
 impl Data {
-    fn load_from(file: &Path) -> Result<Data, LapstrakeError> {
-        let mut csv = open_csv_file(file)?;
+    fn load_from(
+        file: &SpecSource,
+        units: Units,
+    ) -> Result<Data, LapstrakeError> {
+        let mut csv = open_csv(file)?;
 
         // Read stations
-        let mut stations = vec![];
-        {
-            let headers = csv.headers();
-            let headers = headers.expect("Could not read stations.");
-            let headers = headers.iter().skip(1);
-            for header in headers {
-                stations.push(header.to_string());
-            }
-        }
+        let headers = csv.headers()?.clone();
+        let stations = headers.iter().skip(1).map(String::from).collect();
 
         let mut recs = csv.records().peekable();
 
@@ -69,43 +132,151 @@ impl Data {
         let mut positions = vec![];
         let mut heights = vec![];
         let mut breadths = vec![];
+        let mut diagonals = vec![];
+        let mut knuckle_breadths = vec![];
+        let mut knuckle_heights = vec![];
+        let mut stem_profile = vec![];
+        // Keep going after a bad cell, so they can all be fixed at once.
+        let mut errors = vec![];
         loop {
             match Self::read_section_name(&mut recs)? {
                 None => break,
                 Some(section) => {
-                    match section {
-                        Section::Positions => {
-                            Self::load_section(&mut recs, &mut positions)
-                        }
-                        Section::Heights => {
-                            Self::load_section(&mut recs, &mut heights)
-                        }
-                        Section::Breadths => {
-                            Self::load_section(&mut recs, &mut breadths)
-                        }
-                    }
-                }.with_context(|| {
-                    format!("Could not parse section {:?}.", section)
-                })?,
+                    let bad_cells = match section {
+                        Section::Positions => Self::load_section(
+                            &mut recs,
+                            &mut positions,
+                            units,
+                            HeightLine::parse,
+                            file,
+                            &headers,
+                            None,
+                        ),
+                        Section::Heights => Self::load_section(
+                            &mut recs,
+                            &mut heights,
+                            units,
+                            BreadthLine::parse,
+                            file,
+                            &headers,
+                            Some(&mut knuckle_breadths),
+                        ),
+                        Section::Breadths => Self::load_section(
+                            &mut recs,
+                            &mut breadths,
+                            units,
+                            HeightLine::parse,
+                            file,
+                            &headers,
+                            Some(&mut knuckle_heights),
+                        ),
+                        Section::Diagonals => Self::load_section(
+                            &mut recs,
+                            &mut diagonals,
+                            units,
+                            DiagonalLine::parse,
+                            file,
+                            &headers,
+                            None,
+                        ),
+                        Section::StemProfile => Self::load_stem_profile(
+                            &mut recs,
+                            &mut stem_profile,
+                            units,
+                            file,
+                            &headers,
+                        ),
+                    }.with_context(|| {
+                        format!("Could not parse section {:?}.", section)
+                    })?;
+                    errors.extend(bad_cells.into_iter().map(|error| {
+                        error.with_context(|| {
+                            format!("In section {:?}", section)
+                        })
+                    }));
+                }
             }
         }
+        if !errors.is_empty() {
+            return Err(LapstrakeError::Multiple(errors));
+        }
 
-        Ok(Data {
+        let mut data = Data {
+            units,
             stations,
             positions,
             heights,
             breadths,
-        })
+            diagonals,
+            knuckle_breadths,
+            knuckle_heights,
+            stem_profile,
+        };
+        data.add_stem_station();
+        Ok(data)
+    }
+
+    // If the stem's profile is given but it has no column, add an empty
+    // one for it at the bow, so the planks sheet can refer to it.
+    fn add_stem_station(&mut self) {
+        if self.stem_profile.is_empty()
+            || self.stations.iter().any(|name| name == STEM)
+        {
+            return;
+        }
+        self.stations.insert(0, STEM.to_string());
+        let rows = self
+            .positions
+            .iter_mut()
+            .map(|row| &mut row.1)
+            .chain(self.heights.iter_mut().map(|row| &mut row.1))
+            .chain(self.breadths.iter_mut().map(|row| &mut row.1))
+            .chain(self.diagonals.iter_mut().map(|row| &mut row.1));
+        for row in rows {
+            row.insert(0, None);
+        }
+    }
+
+    // Every row must have one measurement per station.
+    fn check_row_lengths(&self) -> Result<(), LapstrakeError> {
+        let n = self.stations.len();
+        let lengths = self
+            .positions
+            .iter()
+            .map(|row| row.1.len())
+            .chain(self.heights.iter().map(|row| row.1.len()))
+            .chain(self.breadths.iter().map(|row| row.1.len()))
+            .chain(self.diagonals.iter().map(|row| row.1.len()));
+        for length in lengths {
+            if length != n {
+                return Err(LapstrakeError::Load(format!(
+                    concat!(
+                        "Found a row with {} measurements, ",
+                        "but there are {} stations."
+                    ),
+                    length, n
+                )));
+            }
+        }
+        Ok(())
     }
 
     fn load_section<CSV, T>(
         csv: &mut iter::Peekable<CSV>,
         table: &mut Vec<DataRow<T>>,
-    ) -> Result<(), LapstrakeError>
+        units: Units,
+        parse_head: fn(&str, Units) -> Result<T, LapstrakeError>,
+        source: &SpecSource,
+        headers: &csv::StringRecord,
+        mut knuckles: Option<&mut Vec<T>>,
+    ) -> Result<Vec<LapstrakeError>, LapstrakeError>
     where
         CSV: Iterator<Item = csv::Result<csv::StringRecord>>,
-        T: FromStr<Err = LapstrakeError>,
+        T: Copy,
     {
+        // Problems with individual cells are collected and returned,
+        // instead of stopping the load.
+        let mut bad_cells = vec![];
         loop {
             if !Self::is_data_row(csv) {
                 break;
@@ -113,20 +284,95 @@ impl Data {
             let csv_row = csv.next().ok_or_else(|| {
                 LapstrakeError::load("Failed to get next row.")
             })??;
-            let mut csv_row = csv_row.iter();
+            let mut cells = csv_row.iter();
 
-            let head = csv_row.next().ok_or_else(|| {
+            let head = cells.next().ok_or_else(|| {
                 LapstrakeError::load("Failed to get first column of row.")
             })?;
 
             let mut row = vec![];
-            for csv_cell in csv_row {
-                let cell = Feet::parse_opt(csv_cell)?;
-                row.push(cell);
+            for (column, csv_cell) in cells.enumerate() {
+                match units.parse_opt(csv_cell) {
+                    Ok(cell) => row.push(cell),
+                    Err(error) => {
+                        bad_cells.push(error.with_context(|| {
+                            let column = column + 1;
+                            cell_location(source, &csv_row, column, headers)
+                        }));
+                        row.push(None);
+                    }
+                }
+            }
+            // Lines can be marked as knuckles, like "1-6-0 knuckle".
+            let (head, knuckle) = split_knuckle(head);
+            let head = match (parse_head(head, units), knuckle) {
+                (Ok(head), false) => Ok(head),
+                (Ok(head), true) => match knuckles {
+                    Some(ref mut knuckles) => {
+                        knuckles.push(head);
+                        Ok(head)
+                    }
+                    None => Err(LapstrakeError::load(
+                        "Only height and breadth lines can be knuckles.",
+                    )),
+                },
+                (Err(error), _) => Err(error),
+            };
+            match head {
+                Ok(head) => table.push((head, row)),
+                Err(error) => bad_cells.push(error.with_context(|| {
+                    cell_location(source, &csv_row, 0, headers)
+                })),
             }
-            table.push((T::from_str(head)?, row));
         }
-        Ok(())
+        Ok(bad_cells)
+    }
+
+    // The stem's profile has a row per point along its rabbet, giving the
+    // height in the first column and the fore-aft position in the second.
+    fn load_stem_profile<CSV>(
+        csv: &mut iter::Peekable<CSV>,
+        profile: &mut Vec<(Length, Length)>,
+        units: Units,
+        source: &SpecSource,
+        headers: &csv::StringRecord,
+    ) -> Result<Vec<LapstrakeError>, LapstrakeError>
+    where
+        CSV: Iterator<Item = csv::Result<csv::StringRecord>>,
+    {
+        let mut bad_cells = vec![];
+        loop {
+            // The rows are too short to tell apart from section names by
+            // their length, but section names aren't measurements.
+            let is_point = match csv.peek() {
+                Some(&Ok(ref row)) => {
+                    row.get(0).map_or(false, |head| units.parse(head).is_ok())
+                }
+                _ => false,
+            };
+            if !is_point {
+                break;
+            }
+            let csv_row = csv.next().ok_or_else(|| {
+                LapstrakeError::load("Failed to get next row.")
+            })??;
+            let height = units.parse(&csv_row[0])?;
+            let position = csv_row.get(1).unwrap_or("");
+            let error = match units.parse_opt(position) {
+                Ok(Some(position)) => {
+                    profile.push((height, position));
+                    continue;
+                }
+                Ok(None) => LapstrakeError::load(
+                    "Each point on the stem needs a fore-aft position.",
+                ),
+                Err(error) => error,
+            };
+            bad_cells.push(error.with_context(|| {
+                cell_location(source, &csv_row, 1, headers)
+            }));
+        }
+        Ok(bad_cells)
     }
 
     fn is_data_row<CSV>(csv: &mut iter::Peekable<CSV>) -> bool
@@ -157,11 +403,14 @@ impl Data {
                         "fore-aft position" => Ok(Some(Section::Positions)),
                         "height" => Ok(Some(Section::Heights)),
                         "breadth" => Ok(Some(Section::Breadths)),
+                        "diagonal" => Ok(Some(Section::Diagonals)),
+                        "stem profile" => Ok(Some(Section::StemProfile)),
                         _ => Err(LapstrakeError::load(&format!(
                             concat!(
                                 "Did not recognize the name {}. ",
                                 "Expected one of these section names: ",
-                                "Height, Breadth, Fore-Aft Position."
+                                "Height, Breadth, Diagonal, ",
+                                "Fore-Aft Position, Stem Profile."
                             ),
                             name,
                         ))),
@@ -177,28 +426,43 @@ impl Data {
 }
 
 impl Planks {
-    fn load_from(file: &Path) -> Result<Planks, LapstrakeError> {
-        let mut csv = open_csv_file(file)?;
-        let mut stations = vec![];
-        {
-            let headers = csv.headers();
-            let headers = headers.expect("Could not read stations.");
-            let headers = headers.iter().skip(1);
-            for header in headers {
-                stations.push(Self::read_plank_station(header));
-            }
-        }
+    fn load_from(
+        file: &SpecSource,
+        units: Units,
+        station_names: &[String],
+    ) -> Result<Planks, LapstrakeError> {
+        let mut csv = open_csv(file)?;
+        let headers = csv.headers()?.clone();
+        let stations = headers
+            .iter()
+            .skip(1)
+            .map(|header| {
+                Self::read_plank_station(header, units, station_names)
+            })
+            .collect();
 
         // Read plank curve fractions
         let mut planks = vec![];
+        let mut errors = vec![];
         for row in csv.records() {
             let row = row?;
             let mut plank = vec![];
-            for cell in row.iter().skip(1) {
-                plank.push(Self::read_plank_curve_fraction(cell)?);
+            for (column, cell) in row.iter().enumerate().skip(1) {
+                match Self::read_plank_curve_fraction(cell) {
+                    Ok(fraction) => plank.push(fraction),
+                    Err(error) => {
+                        errors.push(error.with_context(|| {
+                            cell_location(file, &row, column, &headers)
+                        }));
+                        plank.push(None);
+                    }
+                }
             }
             planks.push(plank);
         }
+        if !errors.is_empty() {
+            return Err(LapstrakeError::Multiple(errors));
+        }
         Ok(Planks {
             stations,
             plank_locations: planks,
@@ -228,55 +492,304 @@ impl Planks {
         }
     }
 
-    fn read_plank_station(text: &str) -> PlankStation {
-        match Feet::parse(text) {
-            Ok(feet) => PlankStation::Position(feet),
+    // A column is for a station if it's named after one, and otherwise
+    // for a fore-aft position. (Station names like "3" would look like
+    // measurements in millimeters.)
+    fn read_plank_station(
+        text: &str,
+        units: Units,
+        station_names: &[String],
+    ) -> PlankStation {
+        if station_names.iter().any(|name| name == text) {
+            return PlankStation::Station(text.to_string());
+        }
+        match units.parse(text) {
+            Ok(length) => PlankStation::Position(length),
             Err(_) => PlankStation::Station(text.to_string()),
         }
     }
 }
 
+impl Ballast {
+    /// Read a ballast keel sheet. It has one row per cross-section of the
+    /// ballast, with columns for the fore-aft position, the depth below
+    /// the keel, and the widths at the top and bottom, all in the given
+    /// units (which should be the config sheet's).
+    pub fn load_from(
+        file: &SpecSource,
+        units: Units,
+    ) -> Result<Ballast, LapstrakeError> {
+        let mut csv = open_csv(file)?;
+        let mut sections = vec![];
+        for (i, row) in csv.records().enumerate() {
+            let row = row?;
+            let cells = row
+                .iter()
+                .map(|cell| units.parse(cell))
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("Bad ballast row {}.", i + 1))?;
+            match cells.as_slice() {
+                &[position, depth, top_width, bottom_width] => {
+                    sections.push(BallastSection {
+                        position,
+                        depth,
+                        top_width,
+                        bottom_width,
+                    })
+                }
+                _ => {
+                    return Err(LapstrakeError::Load(format!(
+                        concat!(
+                            "Ballast row {} should have 4 measurements: ",
+                            "position, depth, top width, and bottom width."
+                        ),
+                        i + 1
+                    )))
+                }
+            }
+        }
+        if sections.len() < 2 {
+            return Err(LapstrakeError::load(
+                "The ballast keel needs at least two sections.",
+            ));
+        }
+        Ok(Ballast { sections, units })
+    }
+}
+
 impl Config {
-    fn load_from(file: &Path) -> Result<Config, LapstrakeError> {
-        let mut csv = open_csv_file(file)?;
+    fn load_from(file: &SpecSource) -> Result<Config, LapstrakeError> {
+        let mut csv = open_csv(file)?;
         match csv.deserialize().next() {
             None => Err(LapstrakeError::load("Found no rows in config file.")),
             Some(row) => Ok(row?),
         }
     }
+
+    // Check that every measurement is in the units that the config sheet
+    // gives.
+    fn check_units(&self) -> Result<(), LapstrakeError> {
+        let lengths = [
+            ("deck_camber", self.deck_camber),
+            ("deck_camber_per_foot", self.deck_camber_per_foot),
+            ("deck_beam_moulding", self.deck_beam_moulding),
+            ("deck_seam", self.deck_seam),
+            ("gain_length", self.gain_length),
+            ("fastener_spacing", self.fastener_spacing),
+            ("fastener_inset", self.fastener_inset),
+            ("plank_thickness", self.plank_thickness),
+            ("hole_diameter", self.hole_diameter),
+            ("tab_height", self.tab_height),
+            ("design_waterline", self.design_waterline),
+            ("material_thickness", self.material_thickness),
+            ("transom_radius", self.transom_radius),
+            ("stem_siding", self.stem_siding),
+            ("stem_moulding", self.stem_moulding),
+            ("outer_stem_moulding", self.outer_stem_moulding),
+            ("keel_siding", self.keel_siding),
+            ("keel_moulding", self.keel_moulding),
+            ("hog_siding", self.hog_siding),
+            ("hog_moulding", self.hog_moulding),
+            ("post_moulding", self.post_moulding),
+            ("trunk_position", self.trunk_position),
+            ("trunk_length", self.trunk_length),
+            ("trunk_width", self.trunk_width),
+            ("trunk_top", self.trunk_top),
+            ("equality_threshold", self.equality_threshold),
+        ];
+        for &(name, length) in &lengths {
+            let matches = match (length, self.units) {
+                (Some(Length::Feet(_)), Units::Millimeters) => false,
+                (Some(Length::Millimeters(_)), Units::Feet) => false,
+                _ => true,
+            };
+            if !matches {
+                return Err(LapstrakeError::Load(format!(
+                    "The config's {} is {:?}, which isn't in the units it \
+                     gives: {}.",
+                    name,
+                    length.unwrap(),
+                    match self.units {
+                        Units::Feet => "feet, like 3-4-5",
+                        Units::Millimeters => "millimeters, like 1250",
+                    }
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// A measurement in the config, either written out as text in a cell of
+// the config sheet, or as a length in a json spec.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigLength {
+    Text(String),
+    Length(Length),
+}
+
+/// Read an optional measurement from a cell of the config sheet, like
+/// 3-4-5 in feet or 1250 in millimeters. The config sheet says which
+/// units it's in, so they're checked against it once it's all read. An
+/// empty cell or "x" means that it was omitted.
+pub(crate) fn deserialize_opt_length<'de, D>(
+    deserializer: D,
+) -> Result<Option<Length>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = match ConfigLength::deserialize(deserializer)? {
+        ConfigLength::Text(text) => text,
+        ConfigLength::Length(length) => return Ok(Some(length)),
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let units = if text.contains('-') {
+        Units::Feet
+    } else {
+        Units::Millimeters
+    };
+    units.parse_opt(text).map_err(de::Error::custom)
+}
+
+impl SpecSource {
+    /// Read from the file at `path`, or from stdin if `path` is "-".
+    pub fn from_path(path: &Path) -> SpecSource {
+        if path == Path::new("-") {
+            SpecSource::Stdin
+        } else {
+            SpecSource::File(path.to_path_buf())
+        }
+    }
+
+    fn is_stdin(&self) -> bool {
+        match self {
+            SpecSource::Stdin => true,
+            SpecSource::File(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for SpecSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecSource::File(path) => write!(f, "file {:?}", path),
+            SpecSource::Stdin => write!(f, "standard input"),
+        }
+    }
 }
 
-fn open_csv_file(path: &Path) -> Result<csv::Reader<fs::File>, LapstrakeError> {
-    println!("Loading file: {:?}.", path);
-    csv::Reader::from_path(&path)
-        .map_err(|csv_err| LapstrakeError::from(csv_err))
-        .with_context(|| format!("Could not read CSV file: {:?}.", path))
+// Split the knuckle mark off of the name of a line, if it has one.
+fn split_knuckle(head: &str) -> (&str, bool) {
+    let head = head.trim();
+    let split = head.len().saturating_sub(KNUCKLE_MARK.len());
+    if head.is_char_boundary(split)
+        && head[split..].eq_ignore_ascii_case(KNUCKLE_MARK)
+    {
+        (head[..split].trim(), true)
+    } else {
+        (head, false)
+    }
+}
+
+// Describe where a cell is, for error messages, like
+// `file "input/data.csv" line 14 (0-9-0), column '3'`.
+fn cell_location(
+    source: &SpecSource,
+    record: &csv::StringRecord,
+    column: usize,
+    headers: &csv::StringRecord,
+) -> String {
+    let line = record.position().map_or(0, |posn| posn.line());
+    let row = record.get(0).unwrap_or("");
+    match headers.get(column) {
+        Some(header) => format!(
+            "In {} line {} ({}), column '{}'",
+            source, line, row, header
+        ),
+        None => format!(
+            "In {} line {} ({}), column {}",
+            source,
+            line,
+            row,
+            column + 1
+        ),
+    }
+}
+
+fn open_csv(
+    source: &SpecSource,
+) -> Result<csv::Reader<Box<io::Read>>, LapstrakeError> {
+    println!("Loading from {}.", source);
+    let reader: Box<io::Read> = match source {
+        SpecSource::File(path) => {
+            let file = fs::File::open(path).with_context(|| {
+                format!("Could not read CSV file: {:?}.", path)
+            })?;
+            Box::new(file)
+        }
+        SpecSource::Stdin => Box::new(io::stdin()),
+    };
+    // Lines starting with # are notes for whoever edits the sheet.
+    Ok(csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(reader))
 }
 
-impl FromStr for BreadthLine {
-    type Err = LapstrakeError;
-    fn from_str(text: &str) -> Result<BreadthLine, LapstrakeError> {
+impl BreadthLine {
+    fn parse(text: &str, units: Units) -> Result<BreadthLine, LapstrakeError> {
         match text.to_lowercase().as_str() {
             "sheer" => Ok(BreadthLine::Sheer),
             "wale" => Ok(BreadthLine::Wale),
             text => {
-                let feet =
-                    Feet::parse(text).context("Was unable to read height.")?;
-                Ok(BreadthLine::ButOut(feet.into()))
+                let length =
+                    units.parse(text).context("Was unable to read height.")?;
+                Ok(BreadthLine::ButOut(length))
             }
         }
     }
 }
 
-impl FromStr for HeightLine {
-    type Err = LapstrakeError;
-    fn from_str(text: &str) -> Result<HeightLine, LapstrakeError> {
+impl DiagonalLine {
+    // Diagonals are named by where they cross the centerline and the
+    // base, like "3-0-0 / 4-6-0".
+    fn parse(
+        text: &str,
+        units: Units,
+    ) -> Result<DiagonalLine, LapstrakeError> {
+        let parts: Vec<&str> = text.split('/').map(|s| s.trim()).collect();
+        match parts.as_slice() {
+            &[height, breadth] => Ok(DiagonalLine {
+                height: units
+                    .parse(height)
+                    .context("Was unable to read diagonal height.")?,
+                breadth: units
+                    .parse(breadth)
+                    .context("Was unable to read diagonal breadth.")?,
+            }),
+            _ => Err(LapstrakeError::Load(format!(
+                concat!(
+                    "Was unable to read diagonal '{}'. Expected its height ",
+                    "at the centerline and breadth at the base, like ",
+                    "'3-0-0 / 4-6-0'."
+                ),
+                text
+            ))),
+        }
+    }
+}
+
+impl HeightLine {
+    fn parse(text: &str, units: Units) -> Result<HeightLine, LapstrakeError> {
         match text.to_lowercase().as_str() {
             "sheer" => Ok(HeightLine::Sheer),
             text => {
-                let feet =
-                    Feet::parse(text).context("Was unable to read breadth.")?;
-                Ok(HeightLine::WLUp(feet.into()))
+                let length =
+                    units.parse(text).context("Was unable to read breadth.")?;
+                Ok(HeightLine::WLUp(length))
             }
         }
     }
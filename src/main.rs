@@ -1,108 +1,1097 @@
-extern crate csv;
-extern crate nalgebra;
-#[macro_use]
-extern crate scad_dots;
-#[macro_use]
-extern crate scad_dots_derive;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate svg;
+extern crate lapstrake;
 #[macro_use]
 extern crate structopt;
 
-mod catmullrom;
-mod draw;
-mod error;
-mod hull;
-mod load;
-mod plank;
-pub mod render_2d; // only public because of doctests - can we avoid that?
-pub mod render_3d;
-mod spec;
-mod spline;
-mod unit;
-mod util;
-
-// pub use draw::*;
-// pub use hull::*;
-// pub use load::load_spec;
-pub use render_3d::preview_model;
-
-use std::path::Path;
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use structopt::StructOpt;
 
-use error::LapstrakeError;
-// use load::load_spec;
-// use render_2d::SvgDoc;
-pub use spec::Spec;
+use lapstrake::render_2d::{Export, PageSize, SvgDoc, SvgExport};
+use lapstrake::render_3d::view_3d;
+use lapstrake::{
+    init_project, write_archive, Ballast, DxfExport, Feet, GcodeExport, Hull,
+    HullCache, LapstrakeError, Paper, PdfExport, PlankRules, ResultExt, Spec,
+    SpecSource, DEFAULT_FAIRING_TOLERANCE, FRESH_WATER_DENSITY, LEAD_DENSITY,
+    SALT_WATER_DENSITY,
+};
+
+/// The size in pixels of the longest side of png thumbnails.
+#[cfg(feature = "thumbnails")]
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Where lofted hulls are cached between runs.
+const CACHE_FOLDER: &str = "./.lapstrake-cache";
+
+/// How often to check the spec sheets for changes, in watch mode.
+const WATCH_INTERVAL_MS: u64 = 500;
 
 /// Tool for model-ship building
 #[derive(StructOpt, Debug)]
 #[structopt(name = "lapstrake")]
 struct Options {
+    /// The folder to read the spec sheets from.
+    #[structopt(
+        short = "i",
+        long = "input",
+        default_value = "./input",
+        parse(from_os_str)
+    )]
+    input: PathBuf,
+
+    /// The folder to save output files in. It's created if it doesn't exist.
+    #[structopt(
+        short = "o",
+        long = "output",
+        default_value = "./output",
+        parse(from_os_str)
+    )]
+    output: PathBuf,
+
     /// The scale factor from the input dimensions to the output dimensions.
     #[structopt(short = "s", long = "scale")]
     scale: Option<f32>,
 
+    /// Read the data sheet from this file instead of the input folder.
+    /// Use "-" to read it from stdin.
+    #[structopt(long = "data", parse(from_os_str))]
+    data: Option<PathBuf>,
+
+    /// Read the planks sheet from this file instead of the input folder.
+    /// Use "-" to read it from stdin.
+    #[structopt(long = "planks", parse(from_os_str))]
+    planks: Option<PathBuf>,
+
+    /// Read the config sheet from this file instead of the input folder.
+    /// Use "-" to read it from stdin.
+    #[structopt(long = "config", parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Read the whole spec from this json file, instead of from the three spreadsheets.
+    #[structopt(long = "json", parse(from_os_str))]
+    json: Option<PathBuf>,
+
+    /// Another input folder, or a json spec file, holding a second hull design to blend with. Its sheets all come from that folder, not from --data, --planks, or --config, which are for the main hull.
+    #[structopt(long = "morph-with", parse(from_os_str))]
+    morph_with: Option<PathBuf>,
+
+    /// How far to blend towards the `--morph-with` hull, from 0 to 1.
+    #[structopt(long = "morph-factor", default_value = "0.5")]
+    morph_factor: f32,
+
+    /// Also bundle all of the files written by this run into a zip archive. Tables that would only be printed, like girths and hydrostatics, are saved as .csv files for it too.
+    #[structopt(long = "archive", parse(from_os_str))]
+    archive: Option<PathBuf>,
+
+    /// Also save a small png thumbnail of each svg drawing. Requires the thumbnails feature.
+    #[structopt(long = "thumbnails")]
+    thumbnails: bool,
+
+    /// The paper size to tile pdf output across: letter or a4.
+    #[structopt(long = "paper", default_value = "letter")]
+    paper: Paper,
+
+    /// For gcode output, the cutting feed rate in inches per minute.
+    #[structopt(long = "feed", default_value = "60")]
+    feed: f32,
+
+    /// For gcode output, the diameter of the end mill, in inches.
+    #[structopt(long = "tool-diameter", default_value = "0.25")]
+    tool_diameter: f32,
+
+    /// For gcode output, the thickness of the stock to cut through, in inches, at the drawing's scale like the toolpaths themselves (so 0.5 is half an inch of real stock, whatever --scale is).
+    #[structopt(long = "thickness", default_value = "0.5")]
+    thickness: f32,
+
+    /// For gcode output, how much deeper to cut on each pass, in inches.
+    #[structopt(long = "step-down", default_value = "0.125")]
+    step_down: f32,
+
+    /// For gcode output, how many tabs to leave on each part to hold it in the sheet.
+    #[structopt(long = "tabs", default_value = "4")]
+    tabs: usize,
+
+    /// Always re-loft the hull, instead of reusing a cached one.
+    #[structopt(long = "no-cache")]
+    no_cache: bool,
+
     #[structopt(subcommand)]
     command: Command,
 }
 
 #[derive(StructOpt, Debug)]
 enum Command {
+    /// Create an input folder with example spec sheets for a small hull, to start a new project from.
+    #[structopt(name = "init")]
+    Init {
+        /// The folder to create the spec sheets in. Defaults to the input folder.
+        #[structopt(parse(from_os_str))]
+        folder: Option<PathBuf>,
+    },
+
+    /// Keep running, and re-run the given commands whenever the spec sheets change, like `watch planks stations`. Stop with Ctrl-C.
+    #[structopt(name = "watch")]
+    Watch {
+        /// The names of the commands to re-run. They use their default options.
+        #[structopt(required = true)]
+        commands: Vec<String>,
+    },
+
+    /// Check the spec sheets for mistakes and inconsistencies, and print every problem found.
+    #[structopt(name = "check")]
+    Check,
+
+    /// Fair the lines of the data sheet, and print every measurement that's too far off its faired line.
+    #[structopt(name = "fair")]
+    Fair {
+        /// How far a measurement can be off its faired line before it's reported, in the config sheet's units, like 0-0-2 in feet or 6 in mm. Defaults to 1/4".
+        #[structopt(long = "tolerance")]
+        tolerance: Option<String>,
+
+        /// How stiff the batten is. Larger values smooth the lines more.
+        #[structopt(long = "smoothing", default_value = "1")]
+        smoothing: f32,
+
+        /// Also save a corrected data.csv sheet to the output folder, with notes marking the rows that were faired.
+        #[structopt(long = "write")]
+        write: bool,
+    },
+
+    /// Display the wireframe, and output the half-breadths, station templates, and plank templates to .svg files, all at once.
+    #[structopt(name = "all")]
+    All,
+
     /// Display a 3d model of the hull's stations and plank edges
     #[structopt(name = "wireframe")]
-    Wireframe,
+    Wireframe {
+        /// Show the planks as solids of the thickness given in the config sheet, lapped over each other, instead of as outlines.
+        #[structopt(long = "solid")]
+        solid: bool,
+
+        /// Show both sides of the hull, instead of just the starboard side. Only for the outline wireframe.
+        #[structopt(long = "full", conflicts_with = "solid")]
+        full: bool,
+
+        /// Also show the outline of the waterplane at this height above base, like 1-3-0. Defaults to the design_waterline from the config sheet, if there is one.
+        #[structopt(long = "waterline", parse(try_from_str = "Feet::parse"))]
+        waterline: Option<Feet>,
+    },
 
     /// Output various 2d diagrams of the hull shape as .svg files.
     #[structopt(name = "diagrams")]
     Diagrams,
 
+    /// Output the hull's waterlines in plan view to a .svg.
+    #[structopt(name = "waterlines")]
+    Waterlines,
+
+    /// Output the hull's buttock lines in profile view to a .svg.
+    #[structopt(name = "buttocks")]
+    Buttocks,
+
+    /// Output the hull in profile to a .svg: the sheer, wale, keel, and ends, with the stations ticked along the sheer.
+    #[structopt(name = "profile")]
+    Profile,
+
+    /// Output a complete lines plan to a .svg: the body plan, half-breadth plan, and profile.
+    #[structopt(name = "lines-plan")]
+    LinesPlan,
+
     /// Output 2d cross-sections of the hull (stations) to a .svg, suitable for assembling into a frame.
     #[structopt(name = "stations")]
-    Stations,
+    Stations {
+        /// The file format to save in: svg, dxf for CNC and laser cutting, gcode for cutting on a CNC router, or pdf for printing full size on tiled pages.
+        #[structopt(long = "format", default_value = "svg")]
+        format: Format,
+
+        /// The width of the cut made by the laser or saw, in inches. The outlines are grown by half of it so the templates come out the right size.
+        #[structopt(long = "kerf", default_value = "0")]
+        kerf: f32,
+
+        /// Leave out the station with this name. Can be given more than once. If no stations are chosen, the Stem and Post are left out.
+        #[structopt(long = "exclude")]
+        exclude: Vec<String>,
+
+        /// Only draw the station with this name. Can be given more than once.
+        #[structopt(long = "only")]
+        only: Vec<String>,
+
+        /// Save each template to its own file, named after its station, instead of laying them all out in one file.
+        #[structopt(long = "split")]
+        split: bool,
+
+        /// Lay the templates out on sheets of this size in inches, like 24x36 for a plotter, saving one file per sheet.
+        #[structopt(long = "page")]
+        page: Option<PageSize>,
+    },
 
     /// Output 2d shapes of planks to a .svg, according to the specification in the planks spreadsheet.
     #[structopt(name = "planks")]
-    Planks,
+    Planks {
+        /// The file format to save in: svg, dxf for CNC and laser cutting, gcode for cutting on a CNC router, or pdf for printing full size on tiled pages.
+        #[structopt(long = "format", default_value = "svg")]
+        format: Format,
+    },
+
+    /// Divide each station into planks of equal girth, and save the result as a planks.csv sheet in the output folder, to start from.
+    #[structopt(name = "auto-planks")]
+    AutoPlanks {
+        /// How many planks to divide each side of the hull into.
+        #[structopt(long = "count")]
+        count: usize,
+
+        /// How many times wider the bottom plank should be than the top plank.
+        #[structopt(long = "garboard-ratio", default_value = "1")]
+        garboard_ratio: f32,
+
+        /// How far each plank laps onto the one below it.
+        #[structopt(
+            long = "lap",
+            default_value = "0-0-0",
+            parse(try_from_str = "Feet::parse")
+        )]
+        lap: Feet,
+    },
+
+    /// Compute the volume and weight of an external ballast keel, from the ballast.csv sheet, and output a casting pattern to a .svg.
+    #[structopt(name = "ballast")]
+    Ballast {
+        /// The density of the ballast material, in pounds per cubic foot. Defaults to lead.
+        #[structopt(long = "density")]
+        density: Option<f32>,
+    },
+
+    /// Print the displacement, center of buoyancy, waterplane area, and wetted surface of the hull floating at a given waterline.
+    #[structopt(name = "hydrostatics")]
+    Hydrostatics {
+        /// The height of the waterline above base, in the config sheet's units, like 1-3-0 in feet or 380 in mm.
+        #[structopt(long = "waterline")]
+        waterline: String,
+
+        /// Float in fresh water, instead of salt water.
+        #[structopt(long = "fresh")]
+        fresh: bool,
+
+        /// Also save them to a .csv file.
+        #[structopt(long = "csv")]
+        csv: bool,
+    },
+
+    /// Print the block, prismatic, and midship coefficients of the hull, along with its waterline length, beam, and draft.
+    #[structopt(name = "coefficients")]
+    Coefficients {
+        /// The height of the waterline above base, in the config sheet's units, like 1-3-0 in feet or 380 in mm.
+        #[structopt(long = "waterline")]
+        waterline: String,
+
+        /// Also save them to a .csv file.
+        #[structopt(long = "csv")]
+        csv: bool,
+    },
+
+    /// Print the girth of every station, and of every position given in the planks sheet, from the keel up to the sheer.
+    #[structopt(name = "girths")]
+    Girths {
+        /// Also save them to a .csv file.
+        #[structopt(long = "csv")]
+        csv: bool,
+    },
+
+    /// Output cradle templates that fit the hull at the given positions to a .svg, for building boat stands.
+    #[structopt(name = "cradles")]
+    Cradles {
+        /// The fore-aft positions of the cradles, like 10-6-0. Can be given more than once.
+        #[structopt(long = "at", parse(try_from_str = "Feet::parse"))]
+        positions: Vec<Feet>,
+
+        /// How far the cradle should stand off from the hull, to leave room for padding.
+        #[structopt(
+            long = "padding",
+            default_value = "0-1-0",
+            parse(try_from_str = "Feet::parse")
+        )]
+        padding: Feet,
+
+        /// How far the flat base should be below the bottom of the hull.
+        #[structopt(
+            long = "base",
+            default_value = "0-4-0",
+            parse(try_from_str = "Feet::parse")
+        )]
+        base: Feet,
+
+        /// How far up the hull the cradle should reach, as a fraction of the way from the keel to the sheer.
+        #[structopt(long = "extent", default_value = "0.3")]
+        extent: f32,
+    },
+
+    /// Output cut templates for a strongback building jig to a .svg, and a 3d model of it to an OpenSCAD .scad file. There's a notched mold at each station, and at each position that planks.csv measures at, cut from the config sheet's material_thickness.
+    #[structopt(name = "jig")]
+    Jig {
+        /// How far above the highest sheer the top of the strongback should be.
+        #[structopt(
+            long = "clearance",
+            default_value = "0-6-0",
+            parse(try_from_str = "Feet::parse")
+        )]
+        clearance: Feet,
+
+        /// How wide the strongback is, across the hull.
+        #[structopt(
+            long = "strongback-width",
+            default_value = "0-1-4",
+            parse(try_from_str = "Feet::parse")
+        )]
+        strongback_width: Feet,
+
+        /// How deep the strongback is.
+        #[structopt(
+            long = "strongback-depth",
+            default_value = "0-5-4",
+            parse(try_from_str = "Feet::parse")
+        )]
+        strongback_depth: Feet,
+
+        /// Make an interlocking egg-crate jig instead, with the molds cross-halved into a keel board and stringers that are cut from the same material, and no strongback.
+        #[structopt(long = "egg-crate")]
+        egg_crate: bool,
+
+        /// The breadth of a pair of stringers for the egg-crate jig, one on each side of the keel board, like 0-9-0. Can be given more than once.
+        #[structopt(
+            long = "stringer",
+            parse(try_from_str = "Feet::parse"),
+            requires = "egg_crate"
+        )]
+        stringers: Vec<Feet>,
+    },
+
+    /// Output templates for the lifts of a bread-and-butter half model to a .svg: the hull sliced horizontally into boards, from the keel up, each outlined at its widest with alignment holes for the dowels that stack them.
+    #[structopt(name = "lifts")]
+    Lifts {
+        /// The thickness of the boards the lifts are cut from, in inches, at the drawing's scale.
+        #[structopt(long = "thickness", default_value = "0.75")]
+        thickness: f32,
+
+        /// The width of the cut made by the laser or saw, in inches. The outlines are grown by half of it so the templates come out the right size.
+        #[structopt(long = "kerf", default_value = "0")]
+        kerf: f32,
+    },
+
+    /// Output the pattern for the transom (the station named Transom) to a .svg, in its true shape as leaned aft by the config sheet's transom_rake and curved by its transom_radius.
+    #[structopt(name = "transom")]
+    Transom,
+
+    /// Output templates for the inner and outer stems to a .svg, in profile on either side of the rabbet, with the bearding line for the config sheet's plank_thickness and the plank edges marked.
+    #[structopt(name = "stem")]
+    Stem,
+
+    /// Output templates for the backbone's timbers to a .svg, in profile: the stem, hog, and sternpost along the rabbet, with the scarfs between them marked, and the keel below the keel line. Their sizes come from the config sheet.
+    #[structopt(name = "backbone")]
+    Backbone,
+
+    /// Measure the hull at new stations at the given positions, and save the measurements as a data.csv sheet in the output folder, for re-lofting at a different frame spacing.
+    #[structopt(name = "offsets")]
+    Offsets {
+        /// The fore-aft positions of the new stations, like 10-6-0. Can be given more than once.
+        #[structopt(long = "at", parse(try_from_str = "Feet::parse"))]
+        positions: Vec<Feet>,
+    },
+
+    /// Output the deck outline and flattened deck panels to a .svg, using the camber and seam from the config sheet.
+    #[structopt(name = "deck")]
+    Deck,
+
+    /// Output templates for the deck beam at each station to a .svg, cut to the deck's camber from the config sheet.
+    #[structopt(name = "deck-beams")]
+    DeckBeams,
+
+    /// Output the whole hull as a closed polyhedron surface to an OpenSCAD .scad file.
+    #[structopt(name = "surface")]
+    Surface {
+        /// Also split the hull into segments no longer than this, in millimeters at the drawing's scale, for 3d printing on a bed too small for the whole model. The hull is cut at stations, and each segment is saved as an .stl file, with a key on its aft face that fits a socket in the next one's forward face.
+        #[structopt(long = "segment")]
+        segment: Option<f32>,
+
+        /// How much bigger the sockets are than the keys, in millimeters.
+        #[structopt(
+            long = "key-clearance",
+            default_value = "0.2",
+            requires = "segment"
+        )]
+        key_clearance: f32,
+
+        /// Also save a hollow shell of the hull with walls this thick, in millimeters at the drawing's scale, to an OpenSCAD file, for printing a model that floats.
+        #[structopt(long = "shell")]
+        shell: Option<f32>,
+
+        /// Leave the shell open at the top, trimmed off at the sheer, instead of sealing it with a deck.
+        #[structopt(long = "open", requires = "shell")]
+        open: bool,
+    },
+
+    /// Output a 3d mesh of the planks and stations as a Wavefront .obj file, with a named group for each.
+    #[structopt(name = "obj")]
+    Obj,
+
+    /// Output a porcupine plot of the curvature of the stations, waterlines, and buttocks to a .svg, to show humps and hollows in the lines.
+    #[structopt(name = "curvature")]
+    Curvature,
+
+    /// Output a 3d mesh of the hull as a .ply file, colored by how unfair each area is.
+    #[structopt(name = "fairness")]
+    Fairness,
+
+    /// Output sampled curves and sectional areas as .csv files, for plotting in other tools.
+    #[structopt(name = "curves")]
+    Curves,
+
+    /// Output the lofted geometry (stations, wale, plank edges, and flattened plank outlines) to a file, for other tools to use.
+    #[structopt(name = "dump")]
+    Dump {
+        /// The file format to save in. Only json is supported.
+        #[structopt(long = "format", default_value = "json")]
+        format: DumpFormat,
+    },
+}
+
+/// A file format for the lofted geometry.
+#[derive(Debug, Clone, Copy)]
+enum DumpFormat {
+    Json,
+}
+
+impl FromStr for DumpFormat {
+    type Err = LapstrakeError;
+    fn from_str(text: &str) -> Result<DumpFormat, LapstrakeError> {
+        match text.to_lowercase().as_str() {
+            "json" => Ok(DumpFormat::Json),
+            _ => Err(LapstrakeError::General(format!(
+                "Unknown format '{}'. Expected json.",
+                text
+            ))),
+        }
+    }
+}
+
+/// A file format for 2d drawings.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Svg,
+    Dxf,
+    Gcode,
+    Pdf,
+}
+
+impl Format {
+    fn exporter(&self, options: &Options, hull: &Hull) -> Box<Export> {
+        match *self {
+            Format::Svg => Box::new(SvgExport),
+            Format::Dxf => Box::new(DxfExport),
+            Format::Gcode => Box::new(GcodeExport {
+                feed: options.feed,
+                tool_diameter: options.tool_diameter,
+                depth: options.thickness,
+                step_down: options.step_down,
+                tabs: options.tabs,
+                equality_threshold: hull.equality_threshold(),
+                ..GcodeExport::default()
+            }),
+            Format::Pdf => Box::new(PdfExport {
+                paper: options.paper,
+            }),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = LapstrakeError;
+    fn from_str(text: &str) -> Result<Format, LapstrakeError> {
+        match text.to_lowercase().as_str() {
+            "svg" => Ok(Format::Svg),
+            "dxf" => Ok(Format::Dxf),
+            "gcode" => Ok(Format::Gcode),
+            "pdf" => Ok(Format::Pdf),
+            _ => Err(LapstrakeError::General(format!(
+                "Unknown format '{}'. Expected svg, dxf, gcode, or pdf.",
+                text
+            ))),
+        }
+    }
 }
 
 fn run() -> Result<(), LapstrakeError> {
     let options = Options::from_args();
+    match options.command {
+        // Starting a new project doesn't need an existing hull.
+        Command::Init { ref folder } => {
+            let folder = folder.as_ref().unwrap_or(&options.input);
+            init_project(folder).map(|_| ())
+        }
+        Command::Watch { ref commands } => watch(&options, commands),
+        Command::Check => check(&options),
+        Command::Fair {
+            ref tolerance,
+            smoothing,
+            write,
+        } => fair(&options, tolerance, smoothing, write),
+        ref command => run_command(&options, command, &load_hull(&options)?),
+    }
+}
+
+// Load the spec sheets given in the options.
+fn load_spec(options: &Options) -> Result<Spec, LapstrakeError> {
+    if let Some(ref json) = options.json {
+        return Spec::load_from_json(json);
+    }
+    let source = |path: &Option<PathBuf>, filename: &str| match path {
+        Some(path) => SpecSource::from_path(path),
+        None => SpecSource::File(options.input.join(filename)),
+    };
+    Spec::load_from_sources(
+        &source(&options.data, "data.csv"),
+        &source(&options.planks, "planks.csv"),
+        &source(&options.config, "config.csv"),
+    )
+}
+
+// Load the hull from the spec sheets given in the options.
+fn load_hull(options: &Options) -> Result<Hull, LapstrakeError> {
+    let input_folder = options.input.as_path();
+    let source = |path: &Option<PathBuf>, filename: &str| match path {
+        Some(path) => SpecSource::from_path(path),
+        None => SpecSource::File(input_folder.join(filename)),
+    };
+    let mut hull = if options.json.is_some() {
+        load_spec(options)?.get_hull()?
+    } else {
+        loft_hull(
+            options,
+            &source(&options.data, "data.csv"),
+            &source(&options.planks, "planks.csv"),
+            &source(&options.config, "config.csv"),
+        )?
+    };
+    if let Some(ref path) = options.morph_with {
+        let other = if path.extension().map_or(false, |ext| ext == "json") {
+            Spec::load_from_json(path)?.get_hull()?
+        } else {
+            let sheet = |filename: &str| SpecSource::File(path.join(filename));
+            loft_hull(
+                options,
+                &sheet("data.csv"),
+                &sheet("planks.csv"),
+                &sheet("config.csv"),
+            ).with_context(|| format!("Could not load the hull in {:?}", path))?
+        };
+        hull = hull.morph(&other, options.morph_factor)?;
+    }
+    if let Some(scale) = options.scale {
+        hull.set_drawing_scale(scale);
+    }
+    Ok(hull)
+}
 
-    let output_folder = Path::new("./output");
-    let input_folder = Path::new("./input");
+// Loft the hull from the given sheets, reusing a cached one unless
+// that's turned off.
+fn loft_hull(
+    options: &Options,
+    data: &SpecSource,
+    planks: &SpecSource,
+    config: &SpecSource,
+) -> Result<Hull, LapstrakeError> {
+    if options.no_cache {
+        Spec::load_from_sources(data, planks, config)?.get_hull()
+    } else {
+        HullCache::new(Path::new(CACHE_FOLDER)).load(data, planks, config)
+    }
+}
+
+// Run a single command on an already loaded hull.
+fn run_command(
+    options: &Options,
+    command: &Command,
+    hull: &Hull,
+) -> Result<(), LapstrakeError> {
+    let output_folder = options.output.as_path();
+    fs::create_dir_all(output_folder).with_context(|| {
+        format!("Failed to create output folder {:?}", output_folder)
+    })?;
+
+    // Remember every file that gets written, for the archive, and number
+    // the drawings in the order they're saved, for title blocks.
+    let outputs = RefCell::new(vec![]);
+    let sheets = Cell::new(0);
+    execute(options, command, hull, &outputs, &sheets)?;
+    if let Some(ref archive) = options.archive {
+        let description = format!("Output of command: {:?}", command);
+        write_archive(archive, &outputs.borrow(), &description)?;
+    }
+    Ok(())
+}
 
-    let spec = Spec::load_from(input_folder)?;
-    let hull = spec.get_hull()?;
-    let scale = options.scale.unwrap_or(1.);
+// Run one command on the loaded hull, recording the files it writes in
+// `outputs`.
+fn execute(
+    options: &Options,
+    command: &Command,
+    hull: &Hull,
+    outputs: &RefCell<Vec<PathBuf>>,
+    sheets: &Cell<usize>,
+) -> Result<(), LapstrakeError> {
+    let output_folder = options.output.as_path();
+    let input_folder = options.input.as_path();
+    let scale = hull.drawing_scale();
+    // Tables that are usually just printed are saved for the archive.
+    let archiving = options.archive.is_some();
 
     let output_to = |filename: &str| {
         let mut path = output_folder.to_owned();
         path.push(filename);
+        outputs.borrow_mut().push(path.clone());
         path
     };
 
-    match options.command {
-        Command::Wireframe => preview_model(&hull.render_half_wireframe()?)?,
-        Command::Diagrams => hull
-            .draw_half_breadths()?
-            .save(&output_to("half-breadths.svg"), scale)?,
-        Command::Stations => hull
-            .draw_cross_sections(&["Stem".into(), "Post".into()])?
-            .save(&output_to("stations.svg"), scale)?,
-        Command::Planks => {
-            hull.draw_planks()?.save(&output_to("planks.svg"), scale)?
+    // Save a drawing in the given format, along with its thumbnail if
+    // requested. `name` doesn't include the file extension.
+    let thumbnails = options.thumbnails;
+    let save_as = |mut doc: SvgDoc,
+                   name: &str,
+                   format: &Export|
+     -> Result<(), LapstrakeError> {
+        sheets.set(sheets.get() + 1);
+        doc.set_units(hull.svg_units(), hull.pixels_per_inch());
+        doc.set_sheet(sheets.get());
+        if thumbnails {
+            // Draw the title block now, so that the thumbnail shows it.
+            doc.add_title_block(scale)?;
+            let thumbnail = format!("{}.png", name);
+            save_thumbnail(&doc, &output_to(&thumbnail))?;
+        }
+        let filename = format!("{}.{}", name, format.extension());
+        format.export(doc, &output_to(&filename), scale)
+    };
+    let save = |doc: SvgDoc, name: &str| save_as(doc, name, &SvgExport);
+
+    match *command {
+        Command::Init { .. }
+        | Command::Watch { .. }
+        | Command::Check
+        | Command::Fair { .. } => {
+            unreachable!("handled before loading the hull")
+        }
+        Command::All => {
+            // Run each command with its default options, just as if it
+            // had been given on its own.
+            for name in &["wireframe", "diagrams", "stations", "planks"] {
+                let command = Command::from_iter_safe(vec!["lapstrake", *name])
+                    .map_err(|error| LapstrakeError::General(error.message))?;
+                execute(options, &command, hull, outputs, sheets)?;
+            }
+        }
+        Command::Wireframe {
+            solid,
+            full,
+            waterline,
+        } => {
+            let mut models = vec![if solid {
+                hull.render_half_solid()?
+            } else if full {
+                hull.render_full_wireframe()?
+            } else {
+                hull.render_half_wireframe()?
+            }];
+            let waterline = waterline
+                .map(|w| w.into())
+                .or(hull.design_waterline());
+            if let Some(height) = waterline {
+                models.push(hull.render_waterplane(height)?);
+            }
+            view_3d(models)?
+        }
+        Command::Diagrams => {
+            save(hull.draw_half_breadths()?, "half-breadths")?
+        }
+        Command::Waterlines => {
+            save(hull.draw_waterlines()?, "waterlines")?
+        }
+        Command::Buttocks => save(hull.draw_buttocks()?, "buttocks")?,
+        Command::Profile => save(hull.draw_profile()?, "profile")?,
+        Command::LinesPlan => {
+            save(hull.draw_lines_plan()?, "lines-plan")?
+        }
+        Command::Stations {
+            format,
+            kerf,
+            ref exclude,
+            ref only,
+            split,
+            page,
+        } => {
+            let mirrored = hull.find_mirrored_stations()?;
+            for &(ref fore, ref aft) in &mirrored {
+                println!("Stations {} and {} can share a mold.", fore, aft);
+            }
+            let excluded = if exclude.is_empty() && only.is_empty() {
+                default_excluded()
+            } else {
+                hull.excluded_stations(only, exclude)?
+            };
+            hull.write_frame_bevels(&output_to("frame-bevels.csv"))?;
+            let exporter = format.exporter(options, hull);
+            // The templates are drawn full size, and scaled down when
+            // they're saved, but the kerf is the real width of the cut.
+            let kerf = kerf / (12. * scale);
+            if split {
+                for (name, doc) in
+                    hull.draw_cross_section_files(&excluded, &mirrored, kerf)?
+                {
+                    let name = format!("station-{}", file_stem(&name));
+                    save_as(doc, &name, &*exporter)?;
+                }
+            } else if let Some(page) = page {
+                let page = page.in_feet(scale);
+                let sheets = hull.draw_cross_section_pages(
+                    &excluded, &mirrored, kerf, page,
+                )?;
+                for (i, doc) in sheets.into_iter().enumerate() {
+                    let name = format!("stations-{}", i + 1);
+                    save_as(doc, &name, &*exporter)?;
+                }
+            } else {
+                save_as(
+                    hull.draw_cross_sections(&excluded, &mirrored, kerf)?,
+                    "stations",
+                    &*exporter,
+                )?
+            }
         }
+        Command::Planks { format } => {
+            for conflict in hull.check_planks()? {
+                println!("Warning: {}", conflict);
+            }
+            for distortion in hull.plank_distortions()? {
+                println!("{}", distortion);
+            }
+            hull.write_bevels(&output_to("bevels.csv"))?;
+            let exporter = format.exporter(options, hull);
+            save_as(hull.draw_planks()?, "planks", &*exporter)?
+        }
+        Command::AutoPlanks {
+            count,
+            garboard_ratio,
+            lap,
+        } => {
+            let rules = PlankRules {
+                garboard_ratio,
+                lap: lap.into(),
+            };
+            let planks = hull.auto_planks(count, &rules)?;
+            planks.write_csv(&output_to("planks.csv"))?
+        }
+        Command::Ballast { density } => {
+            let density = density.unwrap_or(LEAD_DENSITY);
+            let ballast = Ballast::load_from(
+                &SpecSource::File(input_folder.join("ballast.csv")),
+                hull.units(),
+            )?;
+            println!(
+                "Ballast volume: {}, weight: {:.0} lb.",
+                ballast.units.describe_volume(ballast.volume()),
+                ballast.weight(density)
+            );
+            save(hull.draw_ballast(&ballast, density)?, "ballast")?
+        }
+        Command::Hydrostatics {
+            ref waterline,
+            fresh,
+            csv,
+        } => {
+            let density = if fresh {
+                FRESH_WATER_DENSITY
+            } else {
+                SALT_WATER_DENSITY
+            };
+            let waterline = hull
+                .units()
+                .parse(waterline)
+                .context("Could not read the waterline")?;
+            let hydrostatics = hull.hydrostatics(waterline.into(), density)?;
+            println!("{}", hydrostatics);
+            if csv || archiving {
+                hydrostatics.write_csv(&output_to("hydrostatics.csv"))?;
+            }
+        }
+        Command::Coefficients { ref waterline, csv } => {
+            let waterline = hull
+                .units()
+                .parse(waterline)
+                .context("Could not read the waterline")?;
+            let coefficients = hull.coefficients(waterline.into())?;
+            println!("{}", coefficients);
+            if csv || archiving {
+                coefficients.write_csv(&output_to("coefficients.csv"))?;
+            }
+        }
+        Command::Girths { csv } => {
+            for girth in hull.girths()? {
+                println!("{}", girth);
+            }
+            if csv || archiving {
+                hull.write_girths(&output_to("girths.csv"))?;
+            }
+        }
+        Command::Cradles {
+            ref positions,
+            padding,
+            base,
+            extent,
+        } => save(
+            hull.draw_cradles(positions, padding.into(), base.into(), extent)?,
+            "cradles",
+        )?,
+        Command::Jig {
+            clearance,
+            strongback_width,
+            strongback_depth,
+            egg_crate,
+            ref stringers,
+        } => {
+            let jig = if egg_crate {
+                let stringers: Vec<f32> =
+                    stringers.iter().map(|&s| s.into()).collect();
+                hull.egg_crate(clearance.into(), &stringers)?
+            } else {
+                hull.jig(
+                    clearance.into(),
+                    strongback_width.into(),
+                    strongback_depth.into(),
+                )?
+            };
+            save(hull.draw_jig(&jig)?, "jig")?;
+            jig.render()?.save(&output_to("jig.scad"))?
+        }
+        Command::Lifts { thickness, kerf } => {
+            let lifts = hull.lifts(thickness / (12. * scale))?;
+            save(hull.draw_lifts(&lifts, kerf / (12. * scale))?, "lifts")?
+        }
+        Command::Transom => save(hull.draw_transom()?, "transom")?,
+        Command::Stem => save(hull.draw_stem()?, "stem")?,
+        Command::Backbone => save(hull.draw_backbone()?, "backbone")?,
+        Command::Offsets { ref positions } => {
+            hull.write_offsets(positions, &output_to("data.csv"))?
+        }
+        Command::Deck => save(hull.draw_deck()?, "deck")?,
+        Command::DeckBeams => save(hull.draw_deck_beams()?, "deck-beams")?,
+        Command::Surface {
+            segment,
+            key_clearance,
+            shell,
+            open,
+        } => {
+            hull.render_surface()?.save(&output_to("hull.scad"))?;
+            let millimeters = 25.4 * 12. * scale;
+            if let Some(segment) = segment {
+                let segments = hull.segments(
+                    segment / millimeters,
+                    key_clearance / millimeters,
+                )?;
+                for (i, surface) in segments.iter().enumerate() {
+                    let name = format!("hull-{}.stl", i + 1);
+                    surface.save_stl(&output_to(&name), millimeters)?;
+                }
+            }
+            if let Some(shell) = shell {
+                hull.to_solid(shell / millimeters, open)
+                    .with_context(|| {
+                        format!("Can't make a shell {} mm thick", shell)
+                    })?
+                    .save(&output_to("hull-shell.scad"))?;
+            }
+        }
+        Command::Obj => {
+            let path = output_to("hull.obj");
+            println!("Saving file: {:?}.", path);
+            fs::write(&path, hull.to_obj()?)?
+        }
+        Command::Curvature => save(hull.draw_curvature()?, "curvature")?,
+        Command::Fairness => {
+            hull.save_fairness_map(&output_to("fairness.ply"))?
+        }
+        Command::Curves => hull.export_curves(
+            &output_to("curves.csv"),
+            &output_to("sectional-areas.csv"),
+        )?,
+        Command::Dump { format } => match format {
+            DumpFormat::Json => {
+                let path = output_to("hull.json");
+                println!("Saving file: {:?}.", path);
+                fs::write(&path, hull.to_json()?)?
+            }
+        },
+    }
+    Ok(())
+}
+
+// Report every problem with the spec sheets, including plank conflicts
+// if the hull can be built from them.
+fn check(options: &Options) -> Result<(), LapstrakeError> {
+    let spec = load_spec(options)?;
+    let mut problems: Vec<String> =
+        spec.check().iter().map(|problem| problem.to_string()).collect();
+    match spec.get_hull().and_then(|hull| hull.check_planks()) {
+        Ok(conflicts) => {
+            problems.extend(conflicts.iter().map(|c| c.to_string()))
+        }
+        Err(error) => problems.push(format!("Hull: {}", error)),
+    }
+    if problems.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("Problem: {}", problem);
+    }
+    Err(LapstrakeError::General(format!(
+        "Found {} problems in the spec sheets.",
+        problems.len()
+    )))
+}
+
+// Fair the data sheet and report the measurements that were off,
+// optionally saving the corrected sheet.
+fn fair(
+    options: &Options,
+    tolerance: &Option<String>,
+    smoothing: f32,
+    write: bool,
+) -> Result<(), LapstrakeError> {
+    let mut spec = load_spec(options)?;
+    let tolerance = match *tolerance {
+        Some(ref tolerance) => spec
+            .config
+            .units
+            .parse(tolerance)
+            .context("Could not read the tolerance")?
+            .into(),
+        None => DEFAULT_FAIRING_TOLERANCE,
+    };
+    let unfair = spec.data.fair(smoothing, tolerance)?;
+    for measurement in &unfair {
+        println!("Unfair measurement: {}", measurement);
+    }
+    println!("Found {} unfair measurements.", unfair.len());
+    if write {
+        let folder = options.output.as_path();
+        fs::create_dir_all(folder).with_context(|| {
+            format!("Failed to create output folder {:?}", folder)
+        })?;
+        spec.data.write_csv(&folder.join("data.csv"), &unfair)?;
     }
     Ok(())
 }
 
+// Re-run the named commands every time one of the spec sheets is saved,
+// until the program is stopped. Mistakes in the sheets are reported
+// without stopping, so they can be fixed while watching.
+fn watch(options: &Options, names: &[String]) -> Result<(), LapstrakeError> {
+    let mut commands = vec![];
+    for name in names {
+        let args = vec!["lapstrake", name.as_str()];
+        let command = Command::from_iter_safe(args)
+            .map_err(|error| LapstrakeError::General(error.message))?;
+        match command {
+            Command::Init { .. }
+            | Command::Watch { .. }
+            | Command::Check
+            | Command::Fair { .. } => {
+                return Err(LapstrakeError::General(format!(
+                    "Can't watch the '{}' command.",
+                    name
+                )))
+            }
+            command => commands.push(command),
+        }
+    }
+
+    let files = watched_files(options);
+    let mut last_modified = None;
+    loop {
+        let modified: Vec<Option<SystemTime>> = files
+            .iter()
+            .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+            .collect();
+        if last_modified.as_ref() != Some(&modified) {
+            last_modified = Some(modified);
+            let result = load_hull(options).and_then(|hull| {
+                for command in &commands {
+                    run_command(options, command, &hull)?;
+                }
+                Ok(())
+            });
+            if let Err(error) = result {
+                println!("{}", error);
+            }
+            println!("Watching for changes to {:?}...", files);
+        }
+        thread::sleep(Duration::from_millis(WATCH_INTERVAL_MS));
+    }
+}
+
+// The spec sheet files that the hull is loaded from. Sheets read from
+// stdin can't be watched.
+fn watched_files(options: &Options) -> Vec<PathBuf> {
+    if let Some(ref json) = options.json {
+        return vec![json.clone()];
+    }
+    let sheets = [
+        (&options.data, "data.csv"),
+        (&options.planks, "planks.csv"),
+        (&options.config, "config.csv"),
+    ];
+    sheets
+        .iter()
+        .filter_map(|&(path, filename)| match *path {
+            Some(ref path) if path.as_path() == Path::new("-") => None,
+            Some(ref path) => Some(path.clone()),
+            None => Some(options.input.join(filename)),
+        })
+        .collect()
+}
+
+// The stations to leave out of the templates, unless others are chosen.
+// The stem and post aren't molds, in the usual naming.
+fn default_excluded() -> Vec<String> {
+    vec!["Stem".into(), "Post".into()]
+}
+
+// Make a name safe to use in a file name, like "3 & 7" to "3-7".
+fn file_stem(name: &str) -> String {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.join("-")
+}
+
+#[cfg(feature = "thumbnails")]
+fn save_thumbnail(doc: &SvgDoc, path: &Path) -> Result<(), LapstrakeError> {
+    doc.save_thumbnail(path, THUMBNAIL_SIZE)
+}
+
+#[cfg(not(feature = "thumbnails"))]
+fn save_thumbnail(_doc: &SvgDoc, _path: &Path) -> Result<(), LapstrakeError> {
+    Err(LapstrakeError::General(
+        "Lapstrake was built without the thumbnails feature.".into(),
+    ))
+}
+
 fn main() {
     if let Err(error) = run() {
-        println!("{}", error);
+        println!("{}", error.report());
         ::std::process::exit(1);
     } else {
         println!("Done.");
@@ -0,0 +1,90 @@
+//! Export the lofted hull as a Wavefront OBJ mesh, for viewing in
+//! Blender or other 3d tools.
+
+use std::fmt::Write;
+
+use scad_dots::utils::P3;
+
+use error::LapstrakeError;
+use hull::Hull;
+
+impl Hull {
+    /// Write the hull as an OBJ mesh. Each plank is a group named
+    /// `plank_N` holding the surface between its edges, and each station
+    /// is a group named `station_NAME` holding its curve as a polyline.
+    pub fn to_obj(&self) -> Result<String, LapstrakeError> {
+        let mut obj = ObjWriter::new();
+        for (i, plank) in self.get_planks()?.iter().enumerate() {
+            let n = plank.resolution + 1;
+            let bottom = plank.bottom_line.resample_even(n)?;
+            let top = plank.top_line.resample_even(n)?;
+            obj.group(&format!("plank_{}", i + 1));
+            obj.strip(&bottom, &top);
+        }
+        for station in &self.stations {
+            obj.group(&format!("station_{}", group_name(&station.name)));
+            obj.polyline(&station.spline.sample(None)?);
+        }
+        Ok(obj.contents)
+    }
+}
+
+// Accumulates OBJ text, keeping track of the vertex numbering.
+struct ObjWriter {
+    contents: String,
+    vertex_count: usize,
+}
+
+impl ObjWriter {
+    fn new() -> ObjWriter {
+        ObjWriter {
+            contents: "# Exported by lapstrake\n".to_string(),
+            vertex_count: 0,
+        }
+    }
+
+    fn group(&mut self, name: &str) {
+        writeln!(self.contents, "g {}", name).unwrap();
+    }
+
+    // Add the vertices, and return the (1-based) index of the first one.
+    fn vertices(&mut self, points: &[P3]) -> usize {
+        let first = self.vertex_count + 1;
+        for p in points {
+            writeln!(self.contents, "v {} {} {}", p.x, p.y, p.z).unwrap();
+        }
+        self.vertex_count += points.len();
+        first
+    }
+
+    // A strip of quad faces between two lines with the same number of
+    // points.
+    fn strip(&mut self, lower: &[P3], upper: &[P3]) {
+        let n = lower.len().min(upper.len());
+        let a = self.vertices(&lower[..n]);
+        let b = self.vertices(&upper[..n]);
+        for i in 0..n.saturating_sub(1) {
+            writeln!(
+                self.contents,
+                "f {} {} {} {}",
+                a + i,
+                a + i + 1,
+                b + i + 1,
+                b + i
+            ).unwrap();
+        }
+    }
+
+    fn polyline(&mut self, points: &[P3]) {
+        let first = self.vertices(points);
+        let indices: Vec<String> = (first..first + points.len())
+            .map(|i| i.to_string())
+            .collect();
+        writeln!(self.contents, "l {}", indices.join(" ")).unwrap();
+    }
+}
+
+// OBJ group names can't contain whitespace.
+fn group_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
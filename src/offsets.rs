@@ -0,0 +1,132 @@
+//! Tables of offsets: measurements of the hull taken at new stations, in
+//! the same layout as the data.csv spec sheet, for re-lofting the hull
+//! with a different frame spacing.
+
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use hull::{Hull, Station};
+use unit::{Feet, Length};
+use util::{practically_zero, sort_by_number};
+
+impl Hull {
+    /// Measure the hull at new stations at the given fore-aft positions,
+    /// and save the measurements as a data sheet. Each new station is
+    /// measured at the same heights and breadths as the original data
+    /// sheet, in its units, rounded to the nearest eighth of an inch or
+    /// tenth of a millimeter.
+    pub fn write_offsets(
+        &self,
+        positions: &[Feet],
+        file: &Path,
+    ) -> Result<(), LapstrakeError> {
+        if positions.is_empty() {
+            return Err(LapstrakeError::General(
+                "Give at least one position to measure the hull at.".into(),
+            ));
+        }
+        let stations = positions
+            .iter()
+            .map(|&posn| {
+                self.hallucinate_station(posn.into()).with_context(|| {
+                    format!("Could not make a station at {}", posn)
+                })
+            })
+            .collect::<Result<Vec<Station>, _>>()?;
+
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        let mut header = vec!["Stations".to_string()];
+        header.extend(positions.iter().map(|&posn| self.measurement(posn)));
+        writer.write_record(&header)?;
+
+        let blank = vec![String::new(); stations.len()];
+        let section = |writer: &mut csv::Writer<_>, name: &str| {
+            let mut record = vec![name.to_string()];
+            record.extend(blank.iter().cloned());
+            writer.write_record(&record)
+        };
+        let row = |name: String, values: Vec<Option<f32>>| {
+            let mut record = vec![name];
+            record.extend(values.into_iter().map(|value| match value {
+                Some(value) => self.measurement(value),
+                None => "x".into(),
+            }));
+            record
+        };
+        let sheer = |f: &Fn(&Station) -> f32| {
+            row(
+                "Sheer".into(),
+                stations.iter().map(|s| Some(f(s))).collect(),
+            )
+        };
+
+        section(&mut writer, "FORE-AFT POSITION")?;
+        writer.write_record(&sheer(&|s| s.position()))?;
+
+        section(&mut writer, "HEIGHT")?;
+        writer.write_record(&sheer(&|s| s.sheer().z))?;
+        if !self.wale.is_empty() {
+            let wale = self.wale_heights(&stations)?;
+            writer.write_record(&row("Wale".into(), wale))?;
+        }
+        for &breadth in &self.breadths {
+            let mut heights = vec![];
+            for station in &stations {
+                heights.push(station.height_at_breadth(breadth)?);
+            }
+            let name = self.measurement(breadth);
+            writer.write_record(&row(name, heights))?;
+        }
+
+        section(&mut writer, "BREADTH")?;
+        writer.write_record(&sheer(&|s| s.sheer().y))?;
+        for &height in &self.heights {
+            let mut breadths = vec![];
+            for station in &stations {
+                breadths.push(station.breadth_at_height(height)?);
+            }
+            let name = self.measurement(height);
+            writer.write_record(&row(name, breadths))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    // The height of the wale at each station, found between the
+    // stations it was given at. It's None beyond them.
+    fn wale_heights(
+        &self,
+        stations: &[Station],
+    ) -> Result<Vec<Option<f32>>, LapstrakeError> {
+        let mut wale = self.wale.clone();
+        sort_by_number(&mut wale, |p| p.x)?;
+        let height_at = |x: f32| {
+            for pair in wale.windows(2) {
+                let (p, q) = (pair[0], pair[1]);
+                if p.x <= x && x <= q.x {
+                    if practically_zero(q.x - p.x) {
+                        return Some(p.y);
+                    }
+                    return Some(p.y + (x - p.x) / (q.x - p.x) * (q.y - p.y));
+                }
+            }
+            None
+        };
+        Ok(stations
+            .iter()
+            .map(|station| height_at(station.position()))
+            .collect())
+    }
+
+    // Write a length, given in feet, the way the data sheet gives it.
+    fn measurement<L: Into<f32>>(&self, length: L) -> String {
+        match self.units.from_feet(length.into()) {
+            Length::Millimeters(mm) => format!("{}", (mm * 10.).round() / 10.),
+            length => format!("{:?}", length),
+        }
+    }
+}
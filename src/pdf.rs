@@ -0,0 +1,510 @@
+//! Export drawings as multi-page PDF files, tiled across sheets of
+//! printer paper, so that full-size templates can be printed at home and
+//! taped together.
+//!
+//! Neighboring pages overlap a little, and registration crosses are
+//! printed in the overlaps, at the same spot of the drawing on both pages,
+//! to line them up with.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use scad_dots::utils::{P2, V2};
+
+use error::{LapstrakeError, ResultExt};
+use render_2d::{
+    rotate, Baseline, Bounded, Canvas, Export, Stroke, SvgColor, SvgDoc,
+    TextStyle,
+};
+
+// PDF units are points, 1/72 of an inch.
+const POINTS_PER_INCH: f32 = 72.;
+const MARGIN: f32 = 0.5 * POINTS_PER_INCH;
+const OVERLAP: f32 = 0.5 * POINTS_PER_INCH;
+const CROSS_SIZE: f32 = 0.25 * POINTS_PER_INCH;
+const LABEL_SIZE: f32 = 8.;
+
+/// A size of printer paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paper {
+    Letter,
+    A4,
+}
+
+impl FromStr for Paper {
+    type Err = LapstrakeError;
+    fn from_str(text: &str) -> Result<Paper, LapstrakeError> {
+        match text.to_lowercase().as_str() {
+            "letter" => Ok(Paper::Letter),
+            "a4" => Ok(Paper::A4),
+            _ => Err(LapstrakeError::General(format!(
+                "Unknown paper size '{}'. Expected letter or a4.",
+                text
+            ))),
+        }
+    }
+}
+
+impl Paper {
+    // The (width, height) in points, in portrait orientation.
+    fn size(&self) -> (f32, f32) {
+        match *self {
+            Paper::Letter => (612., 792.),
+            Paper::A4 => (595., 842.),
+        }
+    }
+}
+
+/// Export drawings as PDF files, tiled across pages of the given paper.
+pub struct PdfExport {
+    pub paper: Paper,
+}
+
+impl Export for PdfExport {
+    fn extension(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn export(
+        &self,
+        mut doc: SvgDoc,
+        filename: &Path,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", filename);
+        doc.add_title_block(scale_from_feet)?;
+        let (shapes, size) = record(&doc, scale_from_feet)?;
+        let pdf = self.tile(&shapes, size);
+        fs::write(filename, pdf)
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
+// Record the drawing in points, along with the size of the area that
+// it covers.
+fn record(
+    doc: &SvgDoc,
+    scale_from_feet: f32,
+) -> Result<(Vec<Shape>, (f32, f32)), LapstrakeError> {
+    let bound = doc.bound().ok_or_else(|| {
+        LapstrakeError::Draw.context("Cannot make a pdf of an empty doc")
+    })?;
+    // The svg's y axis already points down, so its low corner is the
+    // top left.
+    let mut recorder = Recorder {
+        shapes: vec![],
+        current_path: vec![],
+        origin: bound.low,
+        scale: scale_from_feet * 12. * POINTS_PER_INCH,
+    };
+    doc.render_to(&mut recorder);
+    let size = (
+        bound.width() * recorder.scale,
+        bound.height() * recorder.scale,
+    );
+    Ok((recorder.shapes, size))
+}
+
+impl PdfExport {
+    // Lay out the shapes, which cover an area of the given size, across
+    // as many pages as it takes.
+    fn tile(&self, shapes: &[Shape], size: (f32, f32)) -> Vec<u8> {
+        let (page_width, page_height) = self.paper.size();
+        let printable = (page_width - 2. * MARGIN, page_height - 2. * MARGIN);
+        let step = (printable.0 - OVERLAP, printable.1 - OVERLAP);
+        let count = |length: f32, step: f32| {
+            (((length - OVERLAP) / step).ceil() as usize).max(1)
+        };
+        let (columns, rows) = (count(size.0, step.0), count(size.1, step.1));
+
+        let mut pages = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                let corner = (column as f32 * step.0, row as f32 * step.1);
+                let mut content = String::new();
+                // Flip the y axis, so that it points down like in svg,
+                // and move this page's tile into the printable area.
+                writeln!(
+                    content,
+                    "q 1 0 0 -1 {} {} cm",
+                    MARGIN - corner.0,
+                    page_height - MARGIN + corner.1
+                ).unwrap();
+                writeln!(
+                    content,
+                    "{} {} {} {} re W n",
+                    corner.0, corner.1, printable.0, printable.1
+                ).unwrap();
+                for shape in shapes {
+                    shape.write(&mut content);
+                }
+                // Registration crosses, in the middle of the overlaps.
+                for i in 0..columns + 1 {
+                    for j in 0..rows + 1 {
+                        let x = i as f32 * step.0 + OVERLAP / 2.;
+                        let y = j as f32 * step.1 + OVERLAP / 2.;
+                        write_cross(&mut content, x, y);
+                    }
+                }
+                writeln!(content, "Q").unwrap();
+                let label = format!(
+                    "Page {} of {}: row {}, column {}. Print at 100% scale.",
+                    pages.len() + 1,
+                    rows * columns,
+                    row + 1,
+                    column + 1
+                );
+                write_text(
+                    &mut content,
+                    P2::new(MARGIN, MARGIN / 2.),
+                    &label,
+                    LABEL_SIZE,
+                    0.,
+                    false,
+                );
+                pages.push(content);
+            }
+        }
+        write_pdf(&pages, (page_width, page_height))
+    }
+}
+
+// What has been drawn, in points measured right and down from the top
+// left corner of the drawing.
+enum Shape {
+    Path {
+        points: Vec<P2>,
+        closed: bool,
+        stroke: Stroke,
+    },
+    Circle {
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        fill: Option<SvgColor>,
+    },
+    Text {
+        pos: P2,
+        text: String,
+        size: f32,
+        color: SvgColor,
+        style: TextStyle,
+    },
+}
+
+impl Shape {
+    fn write(&self, content: &mut String) {
+        match *self {
+            Shape::Path {
+                ref points,
+                closed,
+                stroke,
+            } => {
+                set_stroke(content, stroke);
+                write!(content, "{} {} m ", points[0].x, points[0].y).unwrap();
+                for p in &points[1..] {
+                    write!(content, "{} {} l ", p.x, p.y).unwrap();
+                }
+                let operator = if closed { "s" } else { "S" };
+                writeln!(content, "{}", operator).unwrap();
+            }
+            Shape::Circle {
+                center,
+                radius,
+                stroke,
+                fill,
+            } => {
+                if let Some(stroke) = stroke {
+                    set_stroke(content, stroke);
+                }
+                if let Some(fill) = fill {
+                    let (r, g, b) = color_fractions(fill);
+                    write!(content, "{} {} {} rg ", r, g, b).unwrap();
+                }
+                write_circle(content, center, radius);
+                let operator = match (stroke.is_some(), fill.is_some()) {
+                    (true, true) => "B",
+                    (true, false) => "S",
+                    (false, true) => "f",
+                    (false, false) => "n",
+                };
+                writeln!(content, "{}", operator).unwrap();
+            }
+            Shape::Text {
+                pos,
+                ref text,
+                size,
+                color,
+                style,
+            } => {
+                let (r, g, b) = color_fractions(color);
+                write!(content, "{} {} {} rg ", r, g, b).unwrap();
+                // Line the text up roughly with `pos`, assuming an average
+                // character is about half as wide as it is tall.
+                let width = 0.5 * size * text.chars().count() as f32;
+                let down = match style.baseline {
+                    Baseline::Central => size / 3.,
+                    Baseline::Alphabetic => 0.,
+                };
+                let shift = V2::new(-width * style.align.fraction(), down);
+                let corner = pos + rotate(shift, style.angle);
+                write_text(content, corner, text, size, style.angle, true);
+            }
+        }
+    }
+}
+
+// Records the drawing, converted to points.
+struct Recorder {
+    shapes: Vec<Shape>,
+    current_path: Vec<P2>,
+    // The top left corner of the drawing, in feet.
+    origin: P2,
+    // Points per foot.
+    scale: f32,
+}
+
+impl Recorder {
+    fn to_points(&self, pos: P2) -> P2 {
+        P2::new(
+            (pos.x - self.origin.x) * self.scale,
+            (pos.y - self.origin.y) * self.scale,
+        )
+    }
+
+    fn scale_stroke(&self, stroke: Stroke) -> Stroke {
+        Stroke {
+            color: stroke.color,
+            width: stroke.width * self.scale,
+            dash: stroke.dash,
+        }
+    }
+}
+
+impl Canvas for Recorder {
+    fn move_to(&mut self, pos: P2) {
+        self.current_path = vec![self.to_points(pos)];
+    }
+
+    fn line_to(&mut self, pos: P2) {
+        let pos = self.to_points(pos);
+        self.current_path.push(pos);
+    }
+
+    fn end_path(&mut self, closed: bool, stroke: Stroke) {
+        let points = ::std::mem::replace(&mut self.current_path, vec![]);
+        if points.len() >= 2 {
+            let stroke = self.scale_stroke(stroke);
+            self.shapes.push(Shape::Path {
+                points,
+                closed,
+                stroke,
+            });
+        }
+    }
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        fill: Option<SvgColor>,
+    ) {
+        let shape = Shape::Circle {
+            center: self.to_points(center),
+            radius: radius * self.scale,
+            stroke: stroke.map(|s| self.scale_stroke(s)),
+            fill,
+        };
+        self.shapes.push(shape);
+    }
+
+    fn text(
+        &mut self,
+        pos: P2,
+        text: &str,
+        size: f32,
+        color: SvgColor,
+        style: TextStyle,
+    ) {
+        let shape = Shape::Text {
+            pos: self.to_points(pos),
+            text: text.to_owned(),
+            size: size * self.scale,
+            color,
+            style,
+        };
+        self.shapes.push(shape);
+    }
+}
+
+fn color_fractions(color: SvgColor) -> (f32, f32, f32) {
+    let (r, g, b) = color.rgb();
+    (r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+}
+
+fn set_stroke(content: &mut String, stroke: Stroke) {
+    let (r, g, b) = color_fractions(stroke.color);
+    write!(content, "{} {} {} RG {} w ", r, g, b, stroke.width).unwrap();
+    let lengths: Vec<String> = stroke
+        .dash
+        .pattern()
+        .iter()
+        .map(|length| (length * stroke.width).to_string())
+        .collect();
+    write!(content, "[{}] 0 d ", lengths.join(" ")).unwrap();
+}
+
+// Approximate a circle with four Bezier curves.
+fn write_circle(content: &mut String, center: P2, radius: f32) {
+    const KAPPA: f32 = 0.552_284_8;
+    let (x, y, r, k) = (center.x, center.y, radius, radius * KAPPA);
+    write!(content, "{} {} m ", x + r, y).unwrap();
+    let quarters = [
+        [x + r, y + k, x + k, y + r, x, y + r],
+        [x - k, y + r, x - r, y + k, x - r, y],
+        [x - r, y - k, x - k, y - r, x, y - r],
+        [x + k, y - r, x + r, y - k, x + r, y],
+    ];
+    for q in &quarters {
+        write!(
+            content,
+            "{} {} {} {} {} {} c ",
+            q[0], q[1], q[2], q[3], q[4], q[5]
+        ).unwrap();
+    }
+}
+
+fn write_cross(content: &mut String, x: f32, y: f32) {
+    let half = CROSS_SIZE / 2.;
+    writeln!(
+        content,
+        "0 0 0 RG 0.5 w {} {} m {} {} l {} {} m {} {} l S",
+        x - half,
+        y,
+        x + half,
+        y,
+        x,
+        y - half,
+        x,
+        y + half
+    ).unwrap();
+    write_circle(content, P2::new(x, y), half / 2.);
+    writeln!(content, "S").unwrap();
+}
+
+// Write a line of text with its lower left corner at `pos`, turned by
+// `angle` degrees. If `flipped` is true, the y axis is assumed to point
+// down, and the text turns clockwise on the page.
+fn write_text(
+    content: &mut String,
+    pos: P2,
+    text: &str,
+    size: f32,
+    angle: f32,
+    flipped: bool,
+) {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)");
+    let y_scale = if flipped { -1. } else { 1. };
+    let (sin, cos) = angle.to_radians().sin_cos();
+    writeln!(
+        content,
+        "BT /F1 {} Tf {} {} {} {} {} {} Tm ({}) Tj ET",
+        size,
+        cos,
+        sin,
+        -sin * y_scale,
+        cos * y_scale,
+        pos.x,
+        pos.y,
+        escaped
+    ).unwrap();
+}
+
+// Assemble the pages into a complete pdf file.
+fn write_pdf(pages: &[String], page_size: (f32, f32)) -> Vec<u8> {
+    let mut objects = vec![];
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + 2 * i))
+        .collect();
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    ));
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+    );
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            concat!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] ",
+                "/Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>"
+            ),
+            page_size.0,
+            page_size.1,
+            5 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+    let xref = pdf.len();
+    pdf.push_str(&format!("xref\n0 {}\n", objects.len() + 1));
+    pdf.push_str("0000000000 65535 f \n");
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use render_2d::{PathStyle2, SvgPath};
+
+    #[test]
+    fn test_tile_contains_drawing() {
+        let mut doc = SvgDoc::new();
+        doc.append(
+            SvgPath::new(vec![P2::new(1., 1.), P2::new(2., 3.)])
+                .stroke(SvgColor::Black, 0.02)
+                .style(PathStyle2::Line),
+        );
+        let (shapes, size) = record(&doc, 1. / 12.).unwrap();
+        assert_eq!(shapes.len(), 1);
+        // It fits on one page, so all of it lands inside of the first
+        // tile's clipping rectangle.
+        let (width, height) = Paper::Letter.size();
+        let printable = (width - 2. * MARGIN, height - 2. * MARGIN);
+        assert!(size.0 <= printable.0 && size.1 <= printable.1);
+        let points = match shapes[0] {
+            Shape::Path { ref points, .. } => points.clone(),
+            _ => panic!("expected a path"),
+        };
+        for p in points {
+            assert!(p.x >= -0.01 && p.x <= printable.0);
+            assert!(p.y >= -0.01 && p.y <= printable.1);
+        }
+    }
+}
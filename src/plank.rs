@@ -1,27 +1,53 @@
 use nalgebra::{normalize, Rotation2};
+use rayon::prelude::*;
 use scad_dots::core::{MinMaxCoord, Tree};
-use scad_dots::utils::{Axis, P2, P3, V2};
+use scad_dots::utils::{Axis, P2, P3, V2, V3};
 use std::iter;
 
-use error::LapstrakeError;
-use render_2d::{PathStyle2, SvgColor, SvgPath};
+use std::fmt;
+use std::path::Path;
+
+use csv;
+
+use error::{LapstrakeError, ResultExt};
+use hull::{Hull, Station};
+use render_2d::{
+    upright_angle, PathStyle2, SvgColor, SvgPath, SvgText, TextStyle,
+};
 use render_3d::{PathStyle3, ScadPath, SCAD_STROKE};
 use scad_dots::utils::distance;
-use spline::Spline;
-use util::{practically_zero, EQUALITY_THRESHOLD};
+use spec::PlankStation;
+use spline::{EndCondition, Interpolation, Spline};
+use unit::Units;
+use util::{
+    from_real, practically_zero, practically_zero_real, project, real_distance,
+    sort_by_number, Real, RealP2, RealV2,
+};
+
+/// How far apart flattened planks are laid out, in feet.
+const PLANK_SPACING: f32 = 0.1;
+
+/// How many places between each pair of neighboring stations in
+/// planks.csv to check that the planks fit together.
+const CHECKS_BETWEEN_STATIONS: usize = 4;
 
 /// A plank on the hull.
 /// This is a 3d object located at its position on the ship.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plank {
     pub top_line: Spline,
     pub bottom_line: Spline,
     pub resolution: usize,
+    /// How far from each end the lap onto this plank tapers away to
+    /// nothing. Zero means no gains.
+    pub gain_length: f32,
+    /// How thick the plank is, for rendering it as a solid.
+    pub thickness: f32,
 }
 
 /// A flattened plank.  This is a 2d object, taken originally from the
 /// hull but not located on a 2d surface.
-#[derive(Debug, Clone, MinMaxCoord)]
+#[derive(Debug, Clone, MinMaxCoord, Serialize, Deserialize)]
 pub struct FlattenedPlank {
     pub top_line: Vec<P2>,
     pub bottom_line: Vec<P2>,
@@ -35,7 +61,73 @@ impl FlattenedPlank {
             .style(PathStyle2::Line)
     }
 
-    fn get_outline(&self) -> Vec<P2> {
+    /// Label a point along the top edge of the plank, given by its index
+    /// in `top_line`. The label is placed just inside the edge, and runs
+    /// along it. Where the edges meet, there's no inside, so it's placed
+    /// right on the edge.
+    pub fn label_top_edge(&self, index: usize, text: &str) -> SvgText {
+        const FONT_SIZE: f32 = 0.08;
+        let top = self.top_line[index];
+        let across = self.bottom_line[index] - top;
+        let inward = if practically_zero(across.norm()) {
+            V2::new(0., 0.)
+        } else {
+            across.normalize() * 1.5 * FONT_SIZE
+        };
+        // Run along the edge, but never upside down.
+        let before = self.top_line[index.saturating_sub(1)];
+        let after = self.top_line[(index + 1).min(self.top_line.len() - 1)];
+        SvgText {
+            lines: vec![text.into()],
+            pos: top + inward,
+            color: SvgColor::DarkGrey,
+            size: FONT_SIZE,
+            style: TextStyle::default(),
+        }.rotated(upright_angle(after - before))
+    }
+
+    /// Where to drill for the fasteners along this plank's lap: spaced
+    /// evenly along its bottom edge, `inset` in from the edge. There are
+    /// none where the plank's edges meet, since there's no plank there
+    /// to drill through.
+    pub fn fastener_positions(&self, spacing: f32, inset: f32) -> Vec<P2> {
+        let edge = &self.bottom_line;
+        let mut lengths = vec![0.];
+        for pair in edge.windows(2) {
+            let last = lengths[lengths.len() - 1];
+            lengths.push(last + distance(&pair[0], &pair[1]));
+        }
+        let total = lengths[lengths.len() - 1];
+        if spacing <= 0. || total < spacing {
+            return vec![];
+        }
+        // Center the row of fasteners along the edge.
+        let count = (total / spacing) as usize;
+        let start = (total - (count - 1) as f32 * spacing) / 2.;
+        let mut positions = vec![];
+        let mut i = 0;
+        for j in 0..count {
+            let target = start + j as f32 * spacing;
+            while lengths[i + 1] < target {
+                i += 1;
+            }
+            let segment = lengths[i + 1] - lengths[i];
+            let t = if practically_zero(segment) {
+                0.
+            } else {
+                (target - lengths[i]) / segment
+            };
+            let point = edge[i] + (edge[i + 1] - edge[i]) * t;
+            let across = self.top_line[i] - self.bottom_line[i];
+            if practically_zero(across.norm()) {
+                continue;
+            }
+            positions.push(point + across.normalize() * inset);
+        }
+        positions
+    }
+
+    pub(crate) fn get_outline(&self) -> Vec<P2> {
         let top_line = self.top_line.clone();
         let mut bottom_line = self.bottom_line.clone();
         bottom_line.reverse();
@@ -63,18 +155,23 @@ impl FlattenedPlank {
         }
     }
 
-    // Flatten planks to 2d. Place them nicely, without overlap.
+    /// Flatten the planks, and lay them out one above the other, without
+    /// overlap.
     pub(crate) fn flatten_planks(
         planks: Vec<Plank>,
     ) -> Result<Vec<FlattenedPlank>, LapstrakeError> {
+        // Flatten the planks in parallel, then lay them out in order.
+        let flattened = planks
+            .par_iter()
+            .map(|plank| plank.flatten())
+            .collect::<Result<Vec<_>, LapstrakeError>>()?;
         let mut layed_planks = vec![];
         let mut last_y = None;
-        for mut plank in planks {
-            let mut plank = plank.flatten()?;
+        for mut plank in flattened {
             plank.orient_horizontally();
             if let Some(last_y) = last_y {
                 let y = plank.min_coord(Axis::Y);
-                plank.shift_up(last_y - y + 2.0 * EQUALITY_THRESHOLD);
+                plank.shift_up(last_y - y + PLANK_SPACING);
             }
             last_y = Some(plank.max_coord(Axis::Y));
             layed_planks.push(plank);
@@ -88,30 +185,109 @@ impl Plank {
         bot_line: Vec<P3>,
         top_line: Vec<P3>,
         resolution: usize,
+        interpolation: Interpolation,
+        threshold: f32,
     ) -> Result<Plank, LapstrakeError> {
+        let spline = |points| {
+            Spline::with_options(
+                points,
+                resolution,
+                interpolation,
+                EndCondition::default(),
+                threshold,
+            )
+        };
         Ok(Plank {
             resolution: ((bot_line.len() + top_line.len()) / 2) * resolution,
-            bottom_line: Spline::new(bot_line, resolution)?,
-            top_line: Spline::new(top_line, resolution)?,
+            bottom_line: spline(bot_line)?,
+            top_line: spline(top_line)?,
+            gain_length: 0.,
+            thickness: 0.,
         })
     }
 
+    /// Mirror the plank across the plane normal to `axis`, like to get
+    /// the matching plank on the other side of the hull.
+    pub fn reflect(&self, axis: Axis) -> Plank {
+        Plank {
+            top_line: self.top_line.reflect(axis),
+            bottom_line: self.bottom_line.reflect(axis),
+            ..self.clone()
+        }
+    }
+
+    /// How much of the full lap there is at the given fore-aft position,
+    /// from 0 at the ends of the plank to 1 once it's past the gains.
+    pub fn lap_taper(&self, x: f32) -> Result<f32, LapstrakeError> {
+        if practically_zero(self.gain_length) {
+            return Ok(1.);
+        }
+        let xs: Vec<f32> =
+            self.top_line.sample(None)?.iter().map(|p| p.x).collect();
+        let fore = xs.iter().cloned().fold(x, f32::min);
+        let aft = xs.iter().cloned().fold(x, f32::max);
+        let from_end = (x - fore).min(aft - x);
+        Ok((from_end / self.gain_length).max(0.).min(1.))
+    }
+
+    /// The lines marking the gains at each end of the flattened plank:
+    /// where the `upper` plank's bottom edge lies on this plank, as its
+    /// lap tapers away to nothing at the ends. `flat` must be this plank,
+    /// flattened.
+    pub(crate) fn gain_lines(
+        &self,
+        upper: &Plank,
+        flat: &FlattenedPlank,
+    ) -> Result<Vec<Vec<P2>>, LapstrakeError> {
+        let top_pts = self.top_line.resample_even(self.resolution + 1)?;
+        let bot_pts = self.bottom_line.resample_even(self.resolution + 1)?;
+        let mut lines = vec![];
+        let mut line = vec![];
+        for i in 0..top_pts.len() {
+            let x = top_pts[i].x;
+            let taper = self.lap_taper(x)?;
+            if taper >= 1. || !upper.bottom_line.spans_x(x) {
+                if line.len() >= 2 {
+                    lines.push(line);
+                }
+                line = vec![];
+                continue;
+            }
+            // How far across this plank the full lap would reach.
+            let width = distance(&top_pts[i], &bot_pts[i]);
+            let lap = distance(&top_pts[i], &upper.bottom_line.at_x(x)?);
+            let across = if practically_zero(width) {
+                0.
+            } else {
+                (lap / width).min(1.) * taper
+            };
+            let (top, bottom) = (flat.top_line[i], flat.bottom_line[i]);
+            line.push(top + (bottom - top) * across);
+        }
+        if line.len() >= 2 {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
     /// A plank is a 3d object. Flatten it onto a plane.
     pub fn flatten(&self) -> Result<FlattenedPlank, LapstrakeError> {
         let (first_len, triangles) = self.triangles()?;
         let mut top_line = vec![];
         let mut bottom_line = vec![];
         // Start with the leftmost points; assume WLOG they are at x=0.
-        let mut top_pt = P2::new(0.0, 0.0);
-        let mut bot_pt = P2::new(0.0, first_len);
-        top_line.push(top_pt);
-        bottom_line.push(bot_pt);
+        // Each point is placed relative to the last, so work in `Real`
+        // precision until the end.
+        let mut top_pt = RealP2::new(0.0, 0.0);
+        let mut bot_pt = RealP2::new(0.0, first_len);
+        top_line.push(from_real(top_pt));
+        bottom_line.push(from_real(bot_pt));
         // Add each triangle successively.
         for &(a, b, c, d) in &triangles {
             let new_top_pt = triangulate(top_pt, bot_pt, a, b);
             let new_bot_pt = triangulate(new_top_pt, bot_pt, c, d);
-            top_line.push(new_top_pt);
-            bottom_line.push(new_bot_pt);
+            top_line.push(from_real(new_top_pt));
+            bottom_line.push(from_real(new_bot_pt));
             top_pt = new_top_pt;
             bot_pt = new_bot_pt;
         }
@@ -121,11 +297,97 @@ impl Plank {
         })
     }
 
+    /// The angle, in degrees, of the bevel to plane along this plank's
+    /// top edge so that the `upper` plank lies flat against it: the angle
+    /// between the two planks' cross-sections. There is one for each of
+    /// the given fore-aft positions, or None where either plank doesn't
+    /// reach it.
+    pub fn bevel_angles(
+        &self,
+        upper: &Plank,
+        positions: &[f32],
+    ) -> Result<Vec<Option<f32>>, LapstrakeError> {
+        let lines = [
+            &self.bottom_line,
+            &self.top_line,
+            &upper.bottom_line,
+            &upper.top_line,
+        ];
+        let mut angles = vec![];
+        for &x in positions {
+            if !lines.iter().all(|line| line.spans_x(x)) {
+                angles.push(None);
+                continue;
+            }
+            let across = |plank: &Plank| -> Result<V2, LapstrakeError> {
+                let bottom = project(Axis::X, plank.bottom_line.at_x(x)?);
+                let top = project(Axis::X, plank.top_line.at_x(x)?);
+                Ok(top - bottom)
+            };
+            let (lower, upper) = (across(self)?, across(upper)?);
+            let lengths = lower.norm() * upper.norm();
+            if practically_zero(lengths) {
+                angles.push(None);
+                continue;
+            }
+            let cos = (lower.dot(&upper) / lengths).max(-1.).min(1.);
+            angles.push(Some(cos.acos().to_degrees()));
+        }
+        Ok(angles)
+    }
+
+    /// The index of the point of the flattened plank's top line that is
+    /// nearest to the given fore-aft position on the hull.
+    pub(crate) fn top_index_near(
+        &self,
+        x: f32,
+    ) -> Result<usize, LapstrakeError> {
+        let top_pts = self.top_line.resample_even(self.resolution + 1)?;
+        let mut nearest = 0;
+        for (i, point) in top_pts.iter().enumerate() {
+            if (point.x - x).abs() < (top_pts[nearest].x - x).abs() {
+                nearest = i;
+            }
+        }
+        Ok(nearest)
+    }
+
+    /// Measure how much the plank has to stretch or shrink to lie flat.
+    /// Flattening keeps the plank's edges and one diagonal of each
+    /// quadrilateral between neighboring points exactly the right
+    /// length, so the strain shows up in the other diagonals.
+    pub fn distortion(&self) -> Result<(f32, f32), LapstrakeError> {
+        let top_pts = self.top_line.resample_even(self.resolution + 1)?;
+        let bot_pts = self.bottom_line.resample_even(self.resolution + 1)?;
+        let flat = self.flatten()?;
+        let (flat_top, flat_bot) = (&flat.top_line, &flat.bottom_line);
+        let mut worst = (0., top_pts[0].x);
+        for i in 0..top_pts.len() - 1 {
+            // Each edge's ends, on the hull and then on the flat plank.
+            let edges = [
+                (top_pts[i], top_pts[i + 1], flat_top[i], flat_top[i + 1]),
+                (bot_pts[i], bot_pts[i + 1], flat_bot[i], flat_bot[i + 1]),
+                (top_pts[i], bot_pts[i + 1], flat_top[i], flat_bot[i + 1]),
+            ];
+            for &(a, b, flat_a, flat_b) in &edges {
+                let length = distance(&a, &b);
+                if practically_zero(length) {
+                    continue;
+                }
+                let strain = (distance(&flat_a, &flat_b) - length) / length;
+                if strain.abs() > f32::abs(worst.0) {
+                    worst = (strain, (a.x + b.x) / 2.);
+                }
+            }
+        }
+        Ok(worst)
+    }
+
     // Give the leftmost edge length, then triangle lengths from left to right.
-    fn triangles(&self) -> Result<(f32, Vec<Triangles>), LapstrakeError> {
-        let top_pts = self.top_line.sample(Some(self.resolution))?;
-        let bot_pts = self.bottom_line.sample(Some(self.resolution))?;
-        let left_len = distance(&top_pts[0], &bot_pts[0]);
+    fn triangles(&self) -> Result<(Real, Vec<Triangles>), LapstrakeError> {
+        let top_pts = self.top_line.resample_even(self.resolution + 1)?;
+        let bot_pts = self.bottom_line.resample_even(self.resolution + 1)?;
+        let left_len = real_distance(&top_pts[0], &bot_pts[0]);
         let mut triangles = vec![];
         if top_pts.len() != bot_pts.len() {
             return Err(LapstrakeError::General(format!(
@@ -137,10 +399,10 @@ impl Plank {
         let n = top_pts.len();
         for i in 0..n - 1 {
             triangles.push((
-                distance(&top_pts[i], &top_pts[i + 1]),
-                distance(&bot_pts[i], &top_pts[i + 1]),
-                distance(&top_pts[i + 1], &bot_pts[i + 1]),
-                distance(&bot_pts[i], &bot_pts[i + 1]),
+                real_distance(&top_pts[i], &top_pts[i + 1]),
+                real_distance(&bot_pts[i], &top_pts[i + 1]),
+                real_distance(&top_pts[i + 1], &bot_pts[i + 1]),
+                real_distance(&bot_pts[i], &bot_pts[i + 1]),
             ));
         }
         Ok((left_len, triangles))
@@ -164,44 +426,603 @@ impl Plank {
         // return the rendering
         Ok(Tree::union(vec![dots, solid]))
     }
+
+    /// Render as a solid, extruded outwards from the hull surface to the
+    /// plank's thickness. If it's `lapped` onto the plank below it, its
+    /// bottom edge is pushed out to sit on the outside of that plank, so
+    /// it tilts just as a real lapstrake plank does. If there's an
+    /// `upper` plank lapped onto this one, the outside of this plank's
+    /// top edge is beveled so that the upper plank lies flat against it.
+    pub fn render_solid(
+        &self,
+        lapped: bool,
+        upper: Option<&Plank>,
+    ) -> Result<Tree, LapstrakeError> {
+        // How many segments to split the plank into.
+        const SOLID_SAMPLES: usize = 40;
+        // The hull is built out of tiny spheres at its corners.
+        const CORNER_SIZE: f32 = 0.01;
+
+        let top = self.top_line.resample_even(SOLID_SAMPLES + 1)?;
+        let bottom = self.bottom_line.resample_even(SOLID_SAMPLES + 1)?;
+        // Somewhere inside the hull, to tell which way is outwards.
+        let inside_height = top
+            .iter()
+            .map(|p| p.z)
+            .fold(::std::f32::NEG_INFINITY, f32::max);
+
+        // The outward normal at each sample. Where the top and bottom
+        // edges meet, there's no surface to be normal to, so those
+        // samples borrow the nearest normal that there is.
+        let mut normals: Vec<Option<V3>> = vec![];
+        for i in 0..top.len() {
+            let along = top[(i + 1).min(SOLID_SAMPLES)] - top[i.max(1) - 1];
+            let across = top[i] - bottom[i];
+            let normal = along.cross(&across);
+            if practically_zero(normal.norm()) {
+                normals.push(None);
+                continue;
+            }
+            let mut normal = normal.normalize();
+            let inside = P3::new(top[i].x, 0., inside_height);
+            if normal.dot(&(bottom[i] - inside)) < 0. {
+                normal = -normal;
+            }
+            normals.push(Some(normal));
+        }
+        let normals = fill_gaps(&normals).ok_or_else(|| {
+            LapstrakeError::General(
+                "Can't render a plank whose edges meet everywhere.".into(),
+            )
+        })?;
+
+        let lift = if lapped { self.thickness } else { 0. };
+        let mut sections = vec![];
+        for i in 0..top.len() {
+            let outwards = normals[i] * self.thickness;
+            let inner_bottom = bottom[i] + normals[i] * lift;
+            let outer_bottom = inner_bottom + outwards;
+            let mut section = vec![top[i], inner_bottom, outer_bottom];
+            let bevel = match upper {
+                Some(upper) => self.lap_bevel(upper, top[i], normals[i])?,
+                None => None,
+            };
+            match bevel {
+                Some((land, beveled_top)) => {
+                    section.push(land);
+                    section.push(beveled_top);
+                }
+                None => section.push(top[i] + outwards),
+            }
+            sections.push(section);
+        }
+
+        let mut segments = vec![];
+        for i in 0..SOLID_SAMPLES {
+            let mut corners = sections[i].clone();
+            corners.extend(sections[i + 1].iter().cloned());
+            segments.push(
+                ScadPath::new(corners)
+                    .stroke(CORNER_SIZE)
+                    .link(PathStyle3::Solid)?,
+            );
+        }
+        Ok(Tree::union(segments))
+    }
+
+    // Where the `upper` plank's inside face crosses this plank's outside
+    // face, and where it crosses this plank's top edge, in the
+    // cross-section through the given point on the top edge. They're the
+    // ends of the bevel, or None if the upper plank doesn't reach here.
+    fn lap_bevel(
+        &self,
+        upper: &Plank,
+        top: P3,
+        normal: V3,
+    ) -> Result<Option<(P3, P3)>, LapstrakeError> {
+        let (upper_bottom, upper_top) = (&upper.bottom_line, &upper.top_line);
+        if !upper_bottom.spans_x(top.x) || !upper_top.spans_x(top.x) {
+            return Ok(None);
+        }
+        let (start, end) = (upper_bottom.at_x(top.x)?, upper_top.at_x(top.x)?);
+        let width = end - start;
+        if practically_zero(width.norm()) {
+            return Ok(None);
+        }
+        // The upper plank's inside face runs from its lifted bottom edge,
+        // on the outside of this plank, in to its top edge.
+        let land = start + normal * self.thickness;
+        let t = ((top - start).dot(&width) / width.norm_squared())
+            .max(0.)
+            .min(1.);
+        // How far out the inside face is where it passes the top edge.
+        let height = self.thickness * (1. - t);
+        Ok(Some((land, top + normal * height)))
+    }
+
+    /// Render the land: the strip where the `upper` plank overlaps this
+    /// one. It's drawn as a thin surface between this plank's top edge
+    /// and the upper plank's bottom edge.
+    pub fn render_land(&self, upper: &Plank) -> Result<Tree, LapstrakeError> {
+        // How many segments to split the strip into.
+        const LAND_SAMPLES: usize = 40;
+
+        let top = self.top_line.sample(Some(LAND_SAMPLES))?;
+        let bottom = upper.bottom_line.sample(Some(LAND_SAMPLES))?;
+        let mut segments = vec![];
+        for i in 0..LAND_SAMPLES {
+            let quad = vec![top[i], top[i + 1], bottom[i + 1], bottom[i]];
+            segments.push(
+                ScadPath::new(quad)
+                    .stroke(SCAD_STROKE / 4.)
+                    .link(PathStyle3::Solid)?,
+            );
+        }
+        Ok(Tree::union(segments))
+    }
+}
+
+/// A place where the planks specified in planks.csv don't fit together.
+#[derive(Debug, Clone)]
+pub struct PlankConflict {
+    /// Which plank has the problem, counting from 1 at the bottom.
+    pub plank: usize,
+    /// The station (or fore-aft position) where the problem is.
+    pub station: String,
+    pub kind: ConflictKind,
+    /// How big the problem is, measured along the station, in feet.
+    pub size: f32,
+    /// The units to print the size in.
+    pub units: Units,
+}
+
+/// The bevel along the top edge of a plank, where the next plank up
+/// laps onto it, at one station.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bevel {
+    /// Which plank is beveled, counting from 1 at the bottom.
+    pub plank: usize,
+    pub station: String,
+    /// The fore-aft position of the station.
+    pub position: f32,
+    /// The bevel angle, in degrees.
+    pub angle: f32,
+}
+
+/// How far a plank is from being developable: how much it must be
+/// stretched or compressed somewhere to lie on the hull, if it's cut out
+/// flat.
+#[derive(Debug, Clone, Copy)]
+pub struct PlankDistortion {
+    /// Which plank this is, counting from 1 at the bottom.
+    pub plank: usize,
+    /// The largest strain, as a fraction of the length. It's positive
+    /// where the flat plank is too long, and negative where it's too
+    /// short.
+    pub max_strain: f32,
+    /// The fore-aft position of the largest strain.
+    pub position: f32,
+    /// The units to print the position in.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The plank's bottom edge is above its top edge.
+    Inverted,
+    /// The plank doesn't reach the plank below it, leaving a gap.
+    Gap,
+    /// The plank reaches past the bottom of the plank below it.
+    Crossing,
+}
+
+impl Hull {
+    /// Check that each plank's lap actually lies on the plank below it,
+    /// within tolerance. The lofted planks are compared at each station
+    /// given in planks.csv, and at places between them, since their
+    /// edges can cross between stations even where they're in order at
+    /// the stations.
+    pub fn check_planks(&self) -> Result<Vec<PlankConflict>, LapstrakeError> {
+        let planks = self.get_planks()?;
+        let mut conflicts = vec![];
+        for check in self.plank_checks()? {
+            let hallucinated;
+            let station = match check {
+                PlankStation::Station(ref name) => self.get_station(name)?,
+                PlankStation::Position(posn) => {
+                    hallucinated = self.hallucinate_station(posn)?;
+                    &hallucinated
+                }
+            };
+            let x = station.position();
+            // How far along the station each plank's edges are, or None
+            // where the plank doesn't reach it.
+            let girth = |line: &Spline| -> Result<_, LapstrakeError> {
+                if !line.spans_x(x) {
+                    return Ok(None);
+                }
+                Ok(Some(girth_to(station, line.at_x(x)?)?))
+            };
+            let mut edges = vec![];
+            for plank in &planks {
+                edges.push((
+                    girth(&plank.bottom_line)?,
+                    girth(&plank.top_line)?,
+                ));
+            }
+            let mut conflict = |plank, kind, size: f32| {
+                if size > self.equality_threshold {
+                    conflicts.push(PlankConflict {
+                        plank: plank,
+                        station: check.to_string(),
+                        kind: kind,
+                        size: size,
+                        units: self.units,
+                    });
+                }
+            };
+
+            for (i, &(bottom, top)) in edges.iter().enumerate() {
+                if let (Some(bottom), Some(top)) = (bottom, top) {
+                    conflict(i + 1, ConflictKind::Inverted, bottom - top);
+                }
+                if i == 0 {
+                    continue;
+                }
+                let (lower_bottom, lower_top) = edges[i - 1];
+                if let (Some(bottom), Some(lower_top)) = (bottom, lower_top) {
+                    conflict(i + 1, ConflictKind::Gap, bottom - lower_top);
+                }
+                if let (Some(bottom), Some(lower)) = (bottom, lower_bottom) {
+                    conflict(i + 1, ConflictKind::Crossing, lower - bottom);
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
+    // Where to check the planks: at each station in planks.csv, and at
+    // evenly spaced positions between each pair of neighboring ones,
+    // from fore to aft.
+    fn plank_checks(&self) -> Result<Vec<PlankStation>, LapstrakeError> {
+        let mut stations = vec![];
+        for station in &self.planks.stations {
+            let x = match *station {
+                PlankStation::Station(ref name) => {
+                    self.get_station(name)?.position()
+                }
+                PlankStation::Position(posn) => posn.into(),
+            };
+            stations.push((x, station.clone()));
+        }
+        sort_by_number(&mut stations, |&(x, _)| x)?;
+        let mut checks = vec![];
+        for (i, &(x, ref station)) in stations.iter().enumerate() {
+            checks.push(station.clone());
+            let next = match stations.get(i + 1) {
+                Some(&(next, _)) => next,
+                None => break,
+            };
+            for j in 1..CHECKS_BETWEEN_STATIONS + 1 {
+                let t = j as f32 / (CHECKS_BETWEEN_STATIONS + 1) as f32;
+                let posn = self.units.from_feet(x + (next - x) * t);
+                checks.push(PlankStation::Position(posn));
+            }
+        }
+        Ok(checks)
+    }
 }
 
-type Triangles = (f32, f32, f32, f32);
+// How far along the station, from its bottom, the point on it nearest to
+// the given point is. It's measured in the y-z plane, like the fractions
+// in planks.csv.
+fn girth_to(station: &Station, point: P3) -> Result<f32, LapstrakeError> {
+    let point = project(Axis::X, point);
+    let mut along = 0.;
+    let mut nearest = (::std::f32::INFINITY, 0.);
+    for pair in station.spline.sample(None)?.windows(2) {
+        let (a, b) = (project(Axis::X, pair[0]), project(Axis::X, pair[1]));
+        let length = distance(&a, &b);
+        let t = if practically_zero(length) {
+            0.
+        } else {
+            ((point - a).dot(&(b - a)) / (length * length)).max(0.).min(1.)
+        };
+        let gap = distance(&(a + (b - a) * t), &point);
+        if gap < nearest.0 {
+            nearest = (gap, along + length * t);
+        }
+        along += length;
+    }
+    Ok(nearest.1)
+}
+
+impl Hull {
+    /// Find the bevel along the top edge of every plank (but the
+    /// topmost) at every station that it and the plank above it reach.
+    pub fn get_bevels(&self) -> Result<Vec<Bevel>, LapstrakeError> {
+        let planks = self.get_planks()?;
+        let positions: Vec<f32> =
+            self.stations.iter().map(|s| s.position()).collect();
+        let mut bevels = vec![];
+        for (i, pair) in planks.windows(2).enumerate() {
+            let angles = pair[0].bevel_angles(&pair[1], &positions)?;
+            for (station, angle) in self.stations.iter().zip(angles) {
+                if let Some(angle) = angle {
+                    bevels.push(Bevel {
+                        plank: i + 1,
+                        station: station.name.clone(),
+                        position: station.position(),
+                        angle: angle,
+                    });
+                }
+            }
+        }
+        Ok(bevels)
+    }
+
+    /// Measure how far from developable each plank is.
+    pub fn plank_distortions(
+        &self,
+    ) -> Result<Vec<PlankDistortion>, LapstrakeError> {
+        let mut distortions = vec![];
+        for (i, plank) in self.get_planks()?.iter().enumerate() {
+            let (strain, position) = plank.distortion()?;
+            distortions.push(PlankDistortion {
+                plank: i + 1,
+                max_strain: strain,
+                position: position,
+                units: self.units,
+            });
+        }
+        Ok(distortions)
+    }
+
+    /// Save a table of every plank's bevels as a csv file.
+    pub fn write_bevels(&self, file: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", file);
+        let mut writer = csv::Writer::from_path(file)
+            .with_context(|| format!("Could not save {:?}", file))?;
+        for bevel in self.get_bevels()? {
+            writer.serialize(bevel)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl PlankDistortion {
+    /// Whether the plank is strained enough that it will need steaming
+    /// or edge-setting to fit, instead of just bending into place.
+    pub fn needs_forcing(&self) -> bool {
+        // Wood will take about half a percent of strain without help.
+        const MAX_NATURAL_STRAIN: f32 = 0.005;
+        self.max_strain.abs() > MAX_NATURAL_STRAIN
+    }
+}
+
+impl fmt::Display for PlankDistortion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Plank {} is strained by up to {:.2}% at {}.",
+            self.plank,
+            100. * self.max_strain,
+            self.units.from_feet(self.position)
+        )?;
+        if self.needs_forcing() {
+            write!(f, " It will need steaming or edge-setting.")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PlankConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            ConflictKind::Inverted => "has its bottom edge above its top edge",
+            ConflictKind::Gap => "leaves a gap above the plank below it",
+            ConflictKind::Crossing => "reaches past the plank below it",
+        };
+        write!(
+            f,
+            "Plank {} {} at station {}, by {}.",
+            self.plank,
+            description,
+            self.station,
+            self.units.from_feet(self.size)
+        )
+    }
+}
+
+type Triangles = (Real, Real, Real, Real);
+
+// Replace each missing value with the nearest one that isn't missing,
+// or give None if they're all missing.
+fn fill_gaps(values: &[Option<V3>]) -> Option<Vec<V3>> {
+    let known: Vec<(usize, V3)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, value)| value.map(|v| (i, v)))
+        .collect();
+    if known.is_empty() {
+        return None;
+    }
+    Some(
+        (0..values.len())
+            .map(|i| {
+                let nearest = known
+                    .iter()
+                    .min_by_key(|&&(j, _)| (i as isize - j as isize).abs())
+                    .expect("no known values");
+                nearest.1
+            }).collect(),
+    )
+}
 
 /// Given two points and two edge lengths (and another number, for
 /// horrifying edge cases), find a third point that makes a triangle
 /// with those two points and those two edge lengths.
-fn triangulate(pt1: P2, pt2: P2, x: f32, y: f32) -> P2 {
+fn triangulate(pt1: RealP2, pt2: RealP2, x: Real, y: Real) -> RealP2 {
     // Use law of cosines.
     //    y*y = l*l + x*x -2lx*cos(pt1_angle)
     // -> pt1_angle = acos((l*l + x*x - y*y) / 2*l*x)
-    let l = distance(&pt1, &pt2);
-    if practically_zero(10.0 * l) {
+    let l = (pt1 - pt2).norm();
+    if practically_zero_real(10.0 * l) {
         // There's no orientation information, so make some up.
-        pt1 + V2::new(-x, 0.0)
-    } else if practically_zero(x) {
+        pt1 + RealV2::new(-x, 0.0)
+    } else if practically_zero_real(x) {
         // Um, x is small and we would be dividing by it.
         // Use symmetry to divide by y instead.
-        let pt2_angle =
-            Rotation2::new(-f32::acos((l * l + y * y - x * x) / (2.0 * l * y)));
+        let pt2_angle = Rotation2::new(-Real::acos(
+            (l * l + y * y - x * x) / (2.0 * l * y),
+        ));
         pt2 + y * (pt2_angle * normalize(&(pt1 - pt2)))
     } else {
-        let pt1_angle =
-            Rotation2::new(f32::acos((l * l + x * x - y * y) / (2.0 * l * x)));
+        let pt1_angle = Rotation2::new(Real::acos(
+            (l * l + x * x - y * y) / (2.0 * l * x),
+        ));
         pt1 + x * (pt1_angle * normalize(&(pt2 - pt1)))
     }
 }
 
+#[test]
+fn test_fill_gaps() {
+    let (a, b) = (V3::new(1., 0., 0.), V3::new(0., 1., 0.));
+    assert_eq!(
+        fill_gaps(&[None, Some(a), None, None, Some(b), None]),
+        Some(vec![a, a, a, b, b, b])
+    );
+    assert_eq!(fill_gaps(&[None, None]), None);
+}
+
+// The expected values are exactly what `f32` rounds to.
+#[cfg(not(feature = "f64"))]
 #[test]
 fn test_triangulate() {
-    let pt1 = P2::new(1.0, 4.0);
-    let pt2 = P2::new(1.0, 1.0);
+    let pt1 = RealP2::new(1.0, 4.0);
+    let pt2 = RealP2::new(1.0, 1.0);
     let x = 4.0;
     let y = 5.0;
-    assert_eq!(triangulate(pt1, pt2, x, y), P2::new(5.0, 4.0));
-    let pt1 = P2::new(0.0, 1.0);
-    let pt2 = P2::new(1.0, 0.0);
-    let x = f32::sqrt(2.0);
-    let y = f32::sqrt(2.0);
-    assert_eq!(triangulate(pt1, pt2, x, y), P2::new(1.3660253, 1.3660254));
+    assert_eq!(triangulate(pt1, pt2, x, y), RealP2::new(5.0, 4.0));
+    let pt1 = RealP2::new(0.0, 1.0);
+    let pt2 = RealP2::new(1.0, 0.0);
+    let x = Real::sqrt(2.0);
+    let y = Real::sqrt(2.0);
+    assert_eq!(
+        triangulate(pt1, pt2, x, y),
+        RealP2::new(1.3660253, 1.3660254)
+    );
+}
+
+// With the extra precision, the triangles close up to within rounding
+// error of `f64`.
+#[cfg(feature = "f64")]
+#[test]
+fn test_triangulate() {
+    let near = |p: RealP2, q: RealP2| (p - q).norm() < 1e-12;
+    let pt1 = RealP2::new(1.0, 4.0);
+    let pt2 = RealP2::new(1.0, 1.0);
+    assert!(near(triangulate(pt1, pt2, 4.0, 5.0), RealP2::new(5.0, 4.0)));
+    let pt1 = RealP2::new(0.0, 1.0);
+    let pt2 = RealP2::new(1.0, 0.0);
+    let x = Real::sqrt(2.0);
+    let y = Real::sqrt(2.0);
+    let expected = (1. + Real::sqrt(3.)) / 2.;
+    assert!(near(
+        triangulate(pt1, pt2, x, y),
+        RealP2::new(expected, expected)
+    ));
+}
+
+#[test]
+fn test_plank_round_trip() {
+    let line = |z: f32| {
+        (0..5)
+            .map(|i| P3::new(i as f32, 1. + 0.1 * (i * i) as f32, z))
+            .collect()
+    };
+    let plank = Plank::new(
+        line(0.),
+        line(1.),
+        4,
+        Interpolation::default(),
+        ::util::EQUALITY_THRESHOLD,
+    ).unwrap();
+    let json = ::serde_json::to_string(&plank).unwrap();
+    let copy: Plank = ::serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        plank.top_line.sample(None).unwrap(),
+        copy.top_line.sample(None).unwrap()
+    );
+    let flat = plank.flatten().unwrap();
+    let json = ::serde_json::to_string(&flat).unwrap();
+    let copy: FlattenedPlank = ::serde_json::from_str(&json).unwrap();
+    assert_eq!(flat.bottom_line, copy.bottom_line);
+}
+
+#[test]
+fn test_check_planks() {
+    use spec::Spec;
+
+    let mut spec = Spec::load_from(Path::new("templates")).unwrap();
+    assert!(spec.get_hull().unwrap().check_planks().unwrap().is_empty());
+    // Lift the second plank's bottom edge off the first plank at station
+    // 2, leaving a gap there and on either side of it.
+    spec.planks.plank_locations[2][1] = Some(0.5);
+    let conflicts = spec.get_hull().unwrap().check_planks().unwrap();
+    assert!(conflicts.iter().all(|c| c.plank == 2
+        && c.kind == ConflictKind::Gap
+        && c.station != "1"
+        && c.station != "3"));
+    assert!(conflicts.iter().any(|c| c.station == "2"));
+    assert!(conflicts.iter().any(|c| c.station != "2"));
+}
+
+#[test]
+fn test_distortion_units() {
+    let distortion = PlankDistortion {
+        plank: 1,
+        max_strain: 0.001,
+        position: 2.,
+        units: Units::Millimeters,
+    };
+    assert_eq!(
+        distortion.to_string(),
+        "Plank 1 is strained by up to 0.10% at 609.6mm."
+    );
+}
+
+#[test]
+fn test_fastener_positions_where_edges_meet() {
+    let plank = FlattenedPlank {
+        bottom_line: vec![
+            P2::new(0., 0.),
+            P2::new(0., 0.),
+            P2::new(1., 0.),
+            P2::new(2., 0.),
+            P2::new(3., 0.),
+        ],
+        top_line: vec![
+            P2::new(0., 0.),
+            P2::new(0., 0.),
+            P2::new(1., 1.),
+            P2::new(2., 1.),
+            P2::new(3., 0.),
+        ],
+    };
+    let positions = plank.fastener_positions(1., 0.1);
+    assert_eq!(positions.len(), 2);
+    assert!(positions.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+}
+
+#[test]
+fn test_label_where_edges_meet() {
+    let plank = FlattenedPlank {
+        bottom_line: vec![P2::new(0., 0.), P2::new(1., 0.), P2::new(2., 0.)],
+        top_line: vec![P2::new(0., 0.), P2::new(1., 1.), P2::new(2., 0.)],
+    };
+    assert_eq!(plank.label_top_edge(0, "A").pos, P2::new(0., 0.));
+    let label = plank.label_top_edge(1, "B");
+    assert!(label.pos.y < 1. && label.pos.x.is_finite());
 }
@@ -0,0 +1,126 @@
+//! Python bindings, enabled with the `python` feature.
+//!
+//! Build with `cargo build --release --features python`, and rename the
+//! resulting library to `lapstrake.so` (or `lapstrake.pyd` on Windows)
+//! to import it from Python.
+
+use std::path::Path;
+
+use pyo3::exceptions::RuntimeError;
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+use scad_dots::utils::{P2, P3};
+
+use error::LapstrakeError;
+use hull::Hull;
+use spec::Spec;
+
+type Point2 = (f32, f32);
+type Point3 = (f32, f32, f32);
+
+/// A lofted hull, ready for planking and drawing.
+#[pyclass(name = Hull)]
+struct PyHull {
+    hull: Hull,
+}
+
+/// Load the spec sheets (data.csv, planks.csv, config.csv) from a
+/// directory, and loft a hull from them.
+#[pyfunction]
+fn load_spec(directory: &str) -> PyResult<PyHull> {
+    let spec = Spec::load_from(Path::new(directory)).map_err(to_py_err)?;
+    let hull = spec.get_hull().map_err(to_py_err)?;
+    Ok(PyHull { hull })
+}
+
+#[pymethods]
+impl PyHull {
+    /// The name and reference points of each station.
+    fn stations(&self) -> Vec<(String, Vec<Point3>)> {
+        self.hull
+            .stations
+            .iter()
+            .map(|station| (station.name.clone(), to_tuples3(&station.points)))
+            .collect()
+    }
+
+    /// The name and sampled spline of each station.
+    fn station_curves(&self) -> PyResult<Vec<(String, Vec<Point3>)>> {
+        let mut curves = vec![];
+        for station in &self.hull.stations {
+            let samples = station.spline.sample(None).map_err(to_py_err)?;
+            curves.push((station.name.clone(), to_tuples3(&samples)));
+        }
+        Ok(curves)
+    }
+
+    /// The (bottom, top) edges of each plank, in 3d.
+    fn planks(&self) -> PyResult<Vec<(Vec<Point3>, Vec<Point3>)>> {
+        let mut edges = vec![];
+        for plank in self.hull.get_planks().map_err(to_py_err)? {
+            let bottom = plank.bottom_line.sample(None).map_err(to_py_err)?;
+            let top = plank.top_line.sample(None).map_err(to_py_err)?;
+            edges.push((to_tuples3(&bottom), to_tuples3(&top)));
+        }
+        Ok(edges)
+    }
+
+    /// The (bottom, top) edges of each plank, flattened to 2d.
+    fn flattened_planks(&self) -> PyResult<Vec<(Vec<Point2>, Vec<Point2>)>> {
+        let planks = self.hull.get_flattened_planks().map_err(to_py_err)?;
+        Ok(planks
+            .iter()
+            .map(|plank| {
+                (to_tuples2(&plank.bottom_line), to_tuples2(&plank.top_line))
+            }).collect())
+    }
+
+    /// The half-breadth drawing, as svg text.
+    fn half_breadths_svg(&self, scale: f32) -> PyResult<String> {
+        let mut doc = self.hull.draw_half_breadths().map_err(to_py_err)?;
+        doc.set_units(self.hull.svg_units(), self.hull.pixels_per_inch());
+        doc.to_svg_string(scale).map_err(to_py_err)
+    }
+
+    /// The station cross-section templates, as svg text.
+    fn cross_sections_svg(
+        &self,
+        scale: f32,
+        excluded: Vec<String>,
+    ) -> PyResult<String> {
+        let mirrored =
+            self.hull.find_mirrored_stations().map_err(to_py_err)?;
+        let mut doc = self
+            .hull
+            .draw_cross_sections(&excluded, &mirrored, 0.)
+            .map_err(to_py_err)?;
+        doc.set_units(self.hull.svg_units(), self.hull.pixels_per_inch());
+        doc.to_svg_string(scale).map_err(to_py_err)
+    }
+
+    /// The flattened plank templates, as svg text.
+    fn planks_svg(&self, scale: f32) -> PyResult<String> {
+        let mut doc = self.hull.draw_planks().map_err(to_py_err)?;
+        doc.set_units(self.hull.svg_units(), self.hull.pixels_per_inch());
+        doc.to_svg_string(scale).map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+fn lapstrake(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_wrapped(wrap_pyfunction!(load_spec))?;
+    module.add_class::<PyHull>()?;
+    Ok(())
+}
+
+fn to_py_err(err: LapstrakeError) -> PyErr {
+    RuntimeError::py_err(err.report())
+}
+
+fn to_tuples2(points: &[P2]) -> Vec<Point2> {
+    points.iter().map(|p| (p.x, p.y)).collect()
+}
+
+fn to_tuples3(points: &[P3]) -> Vec<Point3> {
+    points.iter().map(|p| (p.x, p.y, p.z)).collect()
+}
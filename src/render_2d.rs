@@ -1,23 +1,41 @@
 use std::path::Path as StdPath;
+use std::str::FromStr;
 
+use draw::TitleBlock;
 use error::LapstrakeError;
 use scad_dots::core::MinMaxCoord;
 use scad_dots::utils::{Axis, P2, V2};
+use unit::{Feet, Units, MILLIMETERS_PER_FOOT};
+use util::offset_polygon;
 
 use svg::node::element::path::Data;
-use svg::node::element::{Circle, Group, Path, Rectangle, Text};
+use svg::node::element::{
+    Circle, Definitions, Group, Path, Rectangle, Text, Use,
+};
 use svg::node::Value;
 use svg::{self, node, Document, Node};
 
-/// The PPI is not entirely standardized between svg rendering programs.
-/// Inkscape currently use 96, but Inkscape version 0.91 and before used 90. In
-/// Illustrator, it's adjustable. If the svg program assumes a different PPI
-/// than what is used here, the scale will be wrong. Scale bars are a good
-/// safety feature.
-const PIXELS_PER_INCH: f32 = 96.;
+/// How many svg user units make an inch. This is the CSS pixel, which is
+/// what most programs assume when an svg has no physical size. The
+/// `width` and `height` that a document is saved with decide its real
+/// size; see `SvgUnits`.
+pub(crate) const PIXELS_PER_INCH: f32 = 96.;
+
+/// How wide an average character of bold sans-serif text is, as a
+/// fraction of the font size. Used to estimate how much room text takes.
+const CHAR_WIDTH: f32 = 0.6;
 
 pub struct SvgDoc {
     contents: SvgGroup,
+    symbols: Vec<SvgSymbol>,
+    // The path being drawn through the `Canvas` interface, if any.
+    current_path: Vec<P2>,
+    units: SvgUnits,
+    pixels_per_inch: f32,
+    // The title block to draw when the document is saved, if any, and
+    // which sheet it's numbered as.
+    title_block: Option<TitleBlock>,
+    sheet: usize,
 }
 
 #[derive(Clone)]
@@ -25,6 +43,8 @@ pub struct SvgGroup {
     contents: Vec<Box<ToSvg>>,
     bound: Option<Bound>,
     translation: Option<V2>,
+    // The name of the layer this group is drawn on, if any.
+    layer: Option<String>,
 }
 
 /// Example:
@@ -46,6 +66,20 @@ pub struct SvgPath {
     stroke: Stroke,
     style: PathStyle2,
     is_closed: bool,
+    segments: Segments,
+}
+
+// How an `SvgPath` gets from each of its points to the next.
+#[derive(Clone, Debug)]
+enum Segments {
+    /// Straight lines.
+    Straight,
+    /// Cubic Bezier curves, fit to the points to within this distance,
+    /// skipping over points wherever a single curve will do.
+    Fitted(f32),
+    /// Cubic Bezier curves with the given control points, one pair per
+    /// segment.
+    Controlled(Vec<(P2, P2)>),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -53,6 +87,11 @@ pub enum PathStyle2 {
     Dots,
     Line,
     LineWithDots,
+    /// A line broken into evenly spaced dashes, like a waterline in a
+    /// lines plan.
+    Dashed,
+    /// A line of alternating long dashes and dots, like a centerline.
+    DashDot,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -72,18 +111,128 @@ pub struct SvgRect {
     fillet: Option<V2>,
 }
 
+/// A group that is defined once in the document's `<defs>`, and then
+/// drawn any number of times with `<use>` elements. Good for things
+/// like alignment holes that are repeated on every station.
+#[derive(Clone)]
+pub struct SvgSymbol {
+    id: String,
+    contents: SvgGroup,
+}
+
+/// A reference to an `SvgSymbol`, drawn at an offset from the symbol's
+/// own coordinates.
+#[derive(Clone)]
+pub struct SvgUse {
+    symbol: SvgSymbol,
+    offset: V2,
+}
+
 #[derive(Clone, Debug)]
 pub struct SvgText {
     pub lines: Vec<String>,
     pub pos: P2,
     pub color: SvgColor,
     pub size: f32,
+    pub style: TextStyle,
+}
+
+/// How text is turned and lined up with its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    /// How far the text is turned clockwise on the page, in degrees.
+    pub angle: f32,
+    pub align: Align,
+    pub baseline: Baseline,
+}
+
+/// Which part of each line of text goes at its position, along the line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Middle,
+    End,
+}
+
+/// Which part of the text goes at its position, across the line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Baseline {
+    /// The middle of the letters, so the text is centered on it.
+    Central,
+    /// The bottom of the letters, not counting descenders, so the text
+    /// sits on it like on a ruled line.
+    Alphabetic,
+}
+
+/// Which units to give an svg document's `width` and `height` in.
+///
+/// The PPI is not entirely standardized between svg rendering programs.
+/// Inkscape currently use 96, but Inkscape version 0.91 and before used 90. In
+/// Illustrator, it's adjustable. If the svg program assumes a different PPI
+/// than the document was saved with, the scale will be wrong. Saving in
+/// millimeters or inches avoids the question. Scale bars are a good safety
+/// feature either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvgUnits {
+    /// Unitless pixels, at the document's pixels per inch.
+    #[serde(rename = "px")]
+    Pixels,
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "in")]
+    Inches,
+}
+
+impl Default for SvgUnits {
+    fn default() -> SvgUnits {
+        SvgUnits::Pixels
+    }
 }
 
+impl SvgUnits {
+    /// Write a length, given in inches, in these units.
+    fn length(&self, inches: f32, pixels_per_inch: f32) -> String {
+        match *self {
+            SvgUnits::Pixels => format!("{}", inches * pixels_per_inch),
+            SvgUnits::Millimeters => format!("{}mm", inches * 25.4),
+            SvgUnits::Inches => format!("{}in", inches),
+        }
+    }
+}
+
+/// The size of a sheet to lay drawings out on, in inches, like a 24" by
+/// 36" plotter sheet. Parsed from text like "24x36".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A dimension, like on a shop drawing: extension lines out from two
+/// points, an arrowed line between them, and a label with the distance
+/// between them in feet and inches.
 #[derive(Clone, Copy, Debug)]
-struct Stroke {
-    color: SvgColor,
-    width: f32,
+pub struct SvgDimension {
+    start: P2,
+    end: P2,
+    offset: f32,
+    stroke: Stroke,
+    size: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stroke {
+    pub color: SvgColor,
+    pub width: f32,
+    pub dash: Dash,
+}
+
+/// How a stroke is broken up along its length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dash {
+    Solid,
+    Dashed,
+    DashDot,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -99,6 +248,8 @@ pub enum SvgColor {
     White,
     LightGrey,
     DarkGrey,
+    /// Any other color, by its red, green, and blue components.
+    Custom(u8, u8, u8),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -113,6 +264,103 @@ pub trait Bounded {
 
 pub trait ToSvg: 'static + CloneToSvg {
     fn finalize_to(&self, group: &mut Group, scale_from_feet: f32);
+
+    /// Like `finalize_to()`, but only the parts that are on `layer`, or
+    /// that aren't on any layer if it's None. Only groups can be put on
+    /// layers, so anything else is only drawn when it's None.
+    fn finalize_layer_to(
+        &self,
+        group: &mut Group,
+        scale_from_feet: f32,
+        layer: Option<&str>,
+    ) {
+        if layer.is_none() {
+            self.finalize_to(group, scale_from_feet);
+        }
+    }
+
+    /// Add the names of all of the layers this is drawn on to `names`,
+    /// in the order they're drawn, skipping any that are already there.
+    fn layer_names(&self, _names: &mut Vec<String>) {}
+
+    /// Draw onto some other kind of canvas, shifted by `offset`.
+    fn render_to(&self, canvas: &mut Canvas, offset: V2);
+}
+
+/// A 2d drawing surface, with coordinates in feet. Drawings are built up
+/// as a `SvgDoc`, and can then be replayed onto any canvas with
+/// `SvgDoc::render_to()`, so new output formats only need to implement
+/// this trait. `SvgDoc` is itself a canvas.
+pub trait Canvas {
+    /// Start a new path at `pos`.
+    fn move_to(&mut self, pos: P2);
+
+    /// Extend the current path with a straight line to `pos`.
+    fn line_to(&mut self, pos: P2);
+
+    /// Finish the current path and stroke it, closing it first if
+    /// `closed` is true.
+    fn end_path(&mut self, closed: bool, stroke: Stroke);
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        fill: Option<SvgColor>,
+    );
+
+    /// Draw a single line of text at `pos`, turned and lined up with it
+    /// as `style` says.
+    fn text(
+        &mut self,
+        pos: P2,
+        text: &str,
+        size: f32,
+        color: SvgColor,
+        style: TextStyle,
+    );
+
+    /// Start drawing the contents of a group on the named layer, until
+    /// the matching `end_layer()`. Layers can be nested. Most canvases
+    /// don't care about layers.
+    fn begin_layer(&mut self, _name: &str) {}
+
+    fn end_layer(&mut self) {}
+}
+
+/// A file format that finished drawings can be saved in. Formats other
+/// than svg are usually written by replaying the document onto a
+/// `Canvas`.
+pub trait Export {
+    /// The file extension for this format, like "svg".
+    fn extension(&self) -> &'static str;
+
+    /// Save the document to `filename`, scaled by `scale_from_feet`.
+    fn export(
+        &self,
+        doc: SvgDoc,
+        filename: &StdPath,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError>;
+}
+
+/// Export drawings as svg files.
+pub struct SvgExport;
+
+impl Export for SvgExport {
+    fn extension(&self) -> &'static str {
+        "svg"
+    }
+
+    fn export(
+        &self,
+        doc: SvgDoc,
+        filename: &StdPath,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        doc.save(filename, scale_from_feet)
+    }
 }
 
 #[doc(hidden)]
@@ -139,6 +387,59 @@ impl SvgDoc {
     pub fn new() -> SvgDoc {
         SvgDoc {
             contents: SvgGroup::new(),
+            symbols: Vec::new(),
+            current_path: Vec::new(),
+            units: SvgUnits::default(),
+            pixels_per_inch: PIXELS_PER_INCH,
+            title_block: None,
+            sheet: 1,
+        }
+    }
+
+    /// Draw the given title block in the corner of the document when it's
+    /// saved. It's numbered as the first sheet, unless `set_sheet()` says
+    /// otherwise.
+    pub fn set_title_block(&mut self, block: TitleBlock) {
+        self.title_block = Some(block);
+    }
+
+    /// Choose which sheet the title block numbers the document as.
+    pub fn set_sheet(&mut self, sheet: usize) {
+        self.sheet = sheet;
+    }
+
+    /// Draw the title block onto the document now, at the scale it will be
+    /// saved at, if it has one that hasn't been drawn yet. Saving does
+    /// this, but other exports and thumbnails have to ask for it.
+    pub fn add_title_block(
+        &mut self,
+        scale_from_feet: f32,
+    ) -> Result<(), LapstrakeError> {
+        if let Some(block) = self.title_block.take() {
+            let sheet = self.sheet;
+            block.draw_onto(self, sheet, scale_from_feet)?;
+        }
+        Ok(())
+    }
+
+    /// Choose the units that the document's size is saved in. With
+    /// `SvgUnits::Pixels`, `pixels_per_inch` should match what the program
+    /// that opens it expects. It's ignored for the other units.
+    pub fn set_units(&mut self, units: SvgUnits, pixels_per_inch: f32) {
+        self.units = units;
+        self.pixels_per_inch = pixels_per_inch;
+    }
+
+    /// Replay everything in this document onto another canvas.
+    pub fn render_to(&self, canvas: &mut Canvas) {
+        self.contents.render_to(canvas, V2::new(0., 0.));
+    }
+
+    /// Add a symbol to the document's definitions, so that it can be
+    /// referenced by `SvgUse` elements anywhere in the document.
+    pub fn define(&mut self, symbol: &SvgSymbol) {
+        if !self.symbols.iter().any(|s| s.id == symbol.id) {
+            self.symbols.push(symbol.clone());
         }
     }
 
@@ -159,31 +460,131 @@ impl SvgDoc {
     }
 
     pub fn save(
-        self,
+        mut self,
         // filename: &str,
         filename: &StdPath,
         scale_from_feet: f32,
     ) -> Result<(), LapstrakeError> {
         println!("Saving file: {:?}.", filename);
+        self.add_title_block(scale_from_feet)?;
         Ok(svg::save(filename, &self.finalize(scale_from_feet))?)
     }
 
+    /// Render the document as svg text, without saving it.
+    pub fn to_svg_string(
+        mut self,
+        scale_from_feet: f32,
+    ) -> Result<String, LapstrakeError> {
+        self.add_title_block(scale_from_feet)?;
+        Ok(self.finalize(scale_from_feet).to_string())
+    }
+
     fn finalize(self, scale_from_feet: f32) -> Document {
         let mut doc = Document::new();
+        if !self.symbols.is_empty() {
+            doc.assign("xmlns:xlink", "http://www.w3.org/1999/xlink");
+            let mut defs = Definitions::new();
+            for symbol in &self.symbols {
+                defs.append(symbol.finalize(scale_from_feet));
+            }
+            doc.append(defs);
+        }
         let mut group = Group::new();
         if let Some(bound) = self.bound() {
-            let background =
-                SvgRect::new(bound.low, bound.size()).fill(SvgColor::White);
+            let background = SvgRect::from_bound(bound).fill(SvgColor::White);
             background.finalize_to(&mut group, scale_from_feet);
             // doc.append(background);
             doc.assign("viewBox", bound.view_box(scale_from_feet));
+            let inches = |feet: f32| feet * scale_from_feet * 12.;
+            let ppi = self.pixels_per_inch;
+            doc.assign("width", self.units.length(inches(bound.width()), ppi));
+            doc.assign(
+                "height",
+                self.units.length(inches(bound.height()), ppi),
+            );
         }
-        self.contents.finalize_to(&mut group, scale_from_feet);
+        self.contents
+            .finalize_layer_to(&mut group, scale_from_feet, None);
         doc.append(group);
+
+        let mut layers = vec![];
+        self.contents.layer_names(&mut layers);
+        if !layers.is_empty() {
+            doc.assign(
+                "xmlns:inkscape",
+                "http://www.inkscape.org/namespaces/inkscape",
+            );
+        }
+        for layer in layers {
+            let mut group = Group::new()
+                .set("id", layer.clone())
+                .set("inkscape:groupmode", "layer")
+                .set("inkscape:label", layer.clone());
+            self.contents.finalize_layer_to(
+                &mut group,
+                scale_from_feet,
+                Some(&layer),
+            );
+            doc.append(group);
+        }
         doc
     }
 }
 
+impl Canvas for SvgDoc {
+    fn move_to(&mut self, pos: P2) {
+        self.current_path = vec![pos];
+    }
+
+    fn line_to(&mut self, pos: P2) {
+        self.current_path.push(pos);
+    }
+
+    fn end_path(&mut self, closed: bool, stroke: Stroke) {
+        let points = ::std::mem::replace(&mut self.current_path, Vec::new());
+        if points.is_empty() {
+            return;
+        }
+        let mut path = SvgPath::new(points)
+            .stroke(stroke.color, stroke.width)
+            .style(stroke.dash.style());
+        if closed {
+            path = path.close();
+        }
+        self.append(path);
+    }
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        fill: Option<SvgColor>,
+    ) {
+        let mut circle = SvgCircle::new(center, radius);
+        circle.stroke = stroke;
+        circle.fill = fill;
+        self.append(circle);
+    }
+
+    fn text(
+        &mut self,
+        pos: P2,
+        text: &str,
+        size: f32,
+        color: SvgColor,
+        style: TextStyle,
+    ) {
+        self.append(SvgText {
+            lines: vec![text.to_owned()],
+            pos: pos,
+            color: color,
+            size: size,
+            style: style,
+        });
+    }
+}
+
 impl Bounded for SvgDoc {
     fn bound(&self) -> Option<Bound> {
         self.contents.bound
@@ -196,9 +597,19 @@ impl SvgGroup {
             contents: Vec::new(),
             bound: None,
             translation: None,
+            layer: None,
         }
     }
 
+    /// Put this group on a layer, which svg editors like Inkscape let
+    /// you show, hide, and edit separately. Everything on a layer is
+    /// drawn together, in one group at the top of the svg document, even
+    /// if it was laid out in separate groups.
+    pub fn layer(mut self, name: &str) -> Self {
+        self.layer = Some(name.to_owned());
+        self
+    }
+
     pub fn new_grid(
         contents: Vec<SvgGroup>,
         spacing: f32,
@@ -222,6 +633,44 @@ impl SvgGroup {
         SvgGroup::grid_helper(contents, spacing, 1)
     }
 
+    /// Lay the groups out in rows on pages of the given size, starting
+    /// new rows and new pages as they fill up. Returns one group per
+    /// page. A group too big to fit on a page gets a page to itself.
+    pub fn new_pages(
+        contents: Vec<SvgGroup>,
+        spacing: f32,
+        page: V2,
+    ) -> Result<Vec<SvgGroup>, LapstrakeError> {
+        let mut pages = vec![];
+        let mut current = SvgGroup::new();
+        let mut is_empty = true;
+        let (mut x, mut y, mut row_height) = (0., 0., 0_f32);
+        for mut sub_group in contents {
+            let size = sub_group.bound().map_or(V2::new(0., 0.), |b| b.size());
+            if x > 0. && x + size.x > page.x {
+                x = 0.;
+                y += row_height + spacing;
+                row_height = 0.;
+            }
+            if y > 0. && y + size.y > page.y {
+                pages.push(current);
+                current = SvgGroup::new();
+                x = 0.;
+                y = 0.;
+                row_height = 0.;
+            }
+            sub_group.translate_to(P2::new(x, y))?;
+            current.append(sub_group);
+            is_empty = false;
+            x += size.x + spacing;
+            row_height = row_height.max(size.y);
+        }
+        if !is_empty {
+            pages.push(current);
+        }
+        Ok(pages)
+    }
+
     fn grid_helper(
         contents: Vec<SvgGroup>,
         spacing: f32,
@@ -260,6 +709,26 @@ impl SvgGroup {
         self.contents.push(Box::new(thing));
     }
 
+    /// Draw a border rectangle around this group, `margin` away from its
+    /// contents on every side.
+    pub fn framed(
+        self,
+        margin: f32,
+        color: SvgColor,
+        width: f32,
+    ) -> Result<SvgGroup, LapstrakeError> {
+        let bound = self.bound().ok_or_else(|| {
+            LapstrakeError::Draw
+                .context("Cannot frame group because its bound is not known")
+        })?;
+        let border = SvgRect::from_bound(bound.expand(margin + width / 2.))
+            .stroke(color, width);
+        let mut group = SvgGroup::new();
+        group.append(self);
+        group.append(border);
+        Ok(group)
+    }
+
     pub fn translate_to(&mut self, new_low: P2) -> Result<(), LapstrakeError> {
         let bound = self.bound().ok_or_else(|| {
             LapstrakeError::Draw.context(
@@ -278,12 +747,17 @@ impl SvgGroup {
     }
 
     fn finalize(&self, scale_from_feet: f32) -> Group {
-        let scale = scale(scale_from_feet);
-
-        let mut group = Group::new();
+        let mut group = self.empty_group(scale_from_feet);
         for item in &self.contents {
             item.finalize_to(&mut group, scale_from_feet);
         }
+        group
+    }
+
+    // A group that's translated like this one, but has nothing in it.
+    fn empty_group(&self, scale_from_feet: f32) -> Group {
+        let scale = scale(scale_from_feet);
+        let mut group = Group::new();
         if let Some(trans_vec) = self.translation {
             group.assign(
                 "transform",
@@ -302,6 +776,64 @@ impl ToSvg for SvgGroup {
     fn finalize_to(&self, group: &mut Group, scale_from_feet: f32) {
         group.append(self.finalize(scale_from_feet));
     }
+
+    fn finalize_layer_to(
+        &self,
+        group: &mut Group,
+        scale_from_feet: f32,
+        layer: Option<&str>,
+    ) {
+        match (self.layer.as_ref(), layer) {
+            (Some(mine), Some(wanted)) if mine == wanted => {
+                self.finalize_to(group, scale_from_feet)
+            }
+            (Some(_), _) => {}
+            (None, _) => {
+                // Skip groups with nothing on the layer, to avoid
+                // cluttering the document with empty groups.
+                if let Some(wanted) = layer {
+                    let mut names = vec![];
+                    self.layer_names(&mut names);
+                    if !names.iter().any(|name| name == wanted) {
+                        return;
+                    }
+                }
+                let mut sub_group = self.empty_group(scale_from_feet);
+                for item in &self.contents {
+                    item.finalize_layer_to(
+                        &mut sub_group,
+                        scale_from_feet,
+                        layer,
+                    );
+                }
+                group.append(sub_group);
+            }
+        }
+    }
+
+    fn layer_names(&self, names: &mut Vec<String>) {
+        if let Some(ref layer) = self.layer {
+            if !names.contains(layer) {
+                names.push(layer.clone());
+            }
+        }
+        for item in &self.contents {
+            item.layer_names(names);
+        }
+    }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        let offset = offset + self.translation.unwrap_or(V2::new(0., 0.));
+        if let Some(ref layer) = self.layer {
+            canvas.begin_layer(layer);
+        }
+        for item in &self.contents {
+            item.render_to(canvas, offset);
+        }
+        if self.layer.is_some() {
+            canvas.end_layer();
+        }
+    }
 }
 
 impl Bounded for SvgGroup {
@@ -325,12 +857,35 @@ impl SvgPath {
             stroke: Stroke {
                 color: SvgColor::Black,
                 width: 1.,
+                dash: Dash::Solid,
             },
             style: PathStyle2::Line,
             is_closed: false,
+            segments: Segments::Straight,
         }
     }
 
+    /// A path made of cubic Bezier curves. Each curve goes from one of
+    /// the `points` to the next, steered by the corresponding pair of
+    /// `controls`.
+    pub fn bezier(
+        points: Vec<P2>,
+        controls: Vec<(P2, P2)>,
+    ) -> Result<SvgPath, LapstrakeError> {
+        if controls.len() + 1 != points.len() {
+            return Err(LapstrakeError::Draw.context(&format!(
+                "a bezier path through {} points needs {} pairs of control \
+                 points, not {}",
+                points.len(),
+                points.len().saturating_sub(1),
+                controls.len()
+            )));
+        }
+        let mut path = SvgPath::new(points);
+        path.segments = Segments::Controlled(controls);
+        Ok(path)
+    }
+
     pub fn new_segment(start: P2, end: P2) -> SvgPath {
         SvgPath::new(vec![start, end])
     }
@@ -339,12 +894,14 @@ impl SvgPath {
         self.stroke = Stroke {
             color: color,
             width: width,
+            dash: self.stroke.dash,
         };
         self
     }
 
     pub fn style(mut self, style: PathStyle2) -> Self {
         self.style = style;
+        self.stroke.dash = style.dash();
         self
     }
 
@@ -353,10 +910,35 @@ impl SvgPath {
         self
     }
 
+    /// Draw smooth curves through the points, instead of straight lines
+    /// between them. Svg output fits as few cubic Bezier curves as it
+    /// can while staying within `tolerance` of every point, which is far
+    /// smaller than a dense polyline.
+    pub fn curved(mut self, tolerance: f32) -> Self {
+        self.segments = Segments::Fitted(tolerance);
+        self
+    }
+
     pub fn append(&mut self, new_points: Vec<P2>) {
         self.points.extend(new_points)
     }
 
+    /// Grow this closed path outwards by `distance`, or shrink it if the
+    /// distance is negative. Open paths have no inside or outside, so
+    /// they can't be offset.
+    pub fn offset(mut self, distance: f32) -> Result<SvgPath, LapstrakeError> {
+        if !self.is_closed {
+            return Err(LapstrakeError::Draw
+                .context("only closed paths can be offset"));
+        }
+        if let Segments::Controlled(_) = self.segments {
+            return Err(LapstrakeError::Draw
+                .context("paths with control points can't be offset"));
+        }
+        self.points = offset_polygon(&self.points, distance);
+        Ok(self)
+    }
+
     pub fn save(
         self,
         filename: &StdPath,
@@ -385,8 +967,20 @@ impl SvgPath {
         let mut points = self.points.iter().map(|p| p * scale);
         let first = points.next().expect("path is empty");
         data = data.move_to(to_tuple(&first));
-        for p in points {
-            data = data.line_to(to_tuple(&p));
+        match self.curves() {
+            None => {
+                for p in points {
+                    data = data.line_to(to_tuple(&p));
+                }
+            }
+            Some(curves) => {
+                for (c1, c2, end) in curves {
+                    let (c1, c2, end) = (c1 * scale, c2 * scale, end * scale);
+                    data = data.cubic_curve_to(vec![
+                        c1.x, c1.y, c2.x, c2.y, end.x, end.y,
+                    ]);
+                }
+            }
         }
         if self.is_closed {
             data = data.close();
@@ -403,6 +997,9 @@ impl ToSvg for SvgPath {
             path.assign("d", self.path_data(scale_from_feet));
             path.assign("stroke", self.stroke.color);
             path.assign("stroke-width", self.stroke.width * scale);
+            if let Some(dashes) = self.stroke.dasharray(scale) {
+                path.assign("stroke-dasharray", dashes);
+            }
             path.assign("fill", "none");
             group.append(path);
         }
@@ -411,6 +1008,37 @@ impl ToSvg for SvgPath {
             group.append(self.dots().finalize(scale_from_feet));
         }
     }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        // Canvases only draw straight lines, so curves are flattened.
+        const STEPS_PER_CURVE: usize = 8;
+        if self.style.has_line() && !self.points.is_empty() {
+            let mut start = self.points[0];
+            canvas.move_to(start + offset);
+            match self.curves() {
+                None => {
+                    for &point in &self.points[1..] {
+                        canvas.line_to(point + offset);
+                    }
+                }
+                Some(curves) => {
+                    for (c1, c2, end) in curves {
+                        for step in 1..(STEPS_PER_CURVE + 1) {
+                            let t = step as f32 / STEPS_PER_CURVE as f32;
+                            let point = cubic_bezier(start, c1, c2, end, t);
+                            canvas.line_to(point + offset);
+                        }
+                        start = end;
+                    }
+                }
+            }
+            canvas.end_path(self.is_closed, self.stroke);
+        }
+
+        if self.style.has_dots() {
+            self.dots().render_to(canvas, offset);
+        }
+    }
 }
 
 impl Bounded for SvgPath {
@@ -429,12 +1057,98 @@ impl Bounded for SvgPath {
     }
 }
 
+impl SvgPath {
+    // The curves that make up the path, as the two control points and
+    // the end of each, or None if it's made of straight lines.
+    fn curves(&self) -> Option<Vec<(P2, P2, P2)>> {
+        match self.segments {
+            Segments::Straight => None,
+            Segments::Fitted(tolerance) => {
+                Some(fit_curves(&self.points, tolerance))
+            }
+            Segments::Controlled(ref controls) => Some(
+                self.points
+                    .windows(2)
+                    .enumerate()
+                    .map(|(i, pair)| {
+                        // Points appended later go straight across.
+                        let straight = (pair[0], pair[1]);
+                        let (c1, c2) =
+                            controls.get(i).cloned().unwrap_or(straight);
+                        (c1, c2, pair[1])
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Fit cubic Bezier curves through `points`, as few as possible while
+// passing within `tolerance` of each of them. Each curve starts and ends
+// on one of the points, heading the way the points do there.
+fn fit_curves(points: &[P2], tolerance: f32) -> Vec<(P2, P2, P2)> {
+    if points.len() < 2 {
+        return vec![];
+    }
+    let last = points.len() - 1;
+    // The direction of travel at each point, per point passed.
+    let tangents: Vec<V2> = (0..points.len())
+        .map(|i| {
+            let before = points[i.saturating_sub(1)];
+            let after = points[(i + 1).min(last)];
+            let span = (i + 1).min(last) - i.saturating_sub(1);
+            (after - before) / span as f32
+        })
+        .collect();
+    let curve = |start: usize, end: usize| {
+        let length = (end - start) as f32 / 3.;
+        (
+            points[start] + tangents[start] * length,
+            points[end] - tangents[end] * length,
+            points[end],
+        )
+    };
+    let fits = |start: usize, end: usize| {
+        let (c1, c2, p) = curve(start, end);
+        (start + 1..end).all(|i| {
+            let t = (i - start) as f32 / (end - start) as f32;
+            let on_curve = cubic_bezier(points[start], c1, c2, p, t);
+            (on_curve - points[i]).norm() <= tolerance
+        })
+    };
+
+    let mut curves = Vec::new();
+    let mut start = 0;
+    while start < last {
+        let mut end = start + 1;
+        while end < last && fits(start, end + 1) {
+            end += 1;
+        }
+        curves.push(curve(start, end));
+        start = end;
+    }
+    curves
+}
+
+// The point a fraction `t` of the way along a cubic Bezier curve.
+fn cubic_bezier(start: P2, c1: P2, c2: P2, end: P2, t: f32) -> P2 {
+    let s = 1. - t;
+    let weights = [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t];
+    let mut sum = V2::new(0., 0.);
+    for (weight, point) in weights.iter().zip(&[start, c1, c2, end]) {
+        sum += point.coords * *weight;
+    }
+    P2::from_coordinates(sum)
+}
+
 impl PathStyle2 {
     fn has_line(&self) -> bool {
         match *self {
             PathStyle2::Dots => false,
             PathStyle2::Line => true,
             PathStyle2::LineWithDots => true,
+            PathStyle2::Dashed => true,
+            PathStyle2::DashDot => true,
         }
     }
 
@@ -443,7 +1157,53 @@ impl PathStyle2 {
             PathStyle2::Dots => true,
             PathStyle2::Line => false,
             PathStyle2::LineWithDots => true,
+            PathStyle2::Dashed => false,
+            PathStyle2::DashDot => false,
+        }
+    }
+
+    fn dash(&self) -> Dash {
+        match *self {
+            PathStyle2::Dashed => Dash::Dashed,
+            PathStyle2::DashDot => Dash::DashDot,
+            _ => Dash::Solid,
+        }
+    }
+}
+
+impl Dash {
+    /// The lengths of the dashes and the gaps between them, alternating,
+    /// as multiples of the stroke width.
+    pub fn pattern(&self) -> &'static [f32] {
+        match *self {
+            Dash::Solid => &[],
+            Dash::Dashed => &[6., 3.],
+            Dash::DashDot => &[12., 3., 1., 3.],
+        }
+    }
+
+    fn style(&self) -> PathStyle2 {
+        match *self {
+            Dash::Solid => PathStyle2::Line,
+            Dash::Dashed => PathStyle2::Dashed,
+            Dash::DashDot => PathStyle2::DashDot,
+        }
+    }
+}
+
+impl Stroke {
+    // The stroke's dash pattern, scaled into an svg attribute value, if
+    // it isn't solid.
+    fn dasharray(&self, scale: f32) -> Option<String> {
+        let pattern = self.dash.pattern();
+        if pattern.is_empty() {
+            return None;
         }
+        let lengths: Vec<String> = pattern
+            .iter()
+            .map(|length| (length * self.width * scale).to_string())
+            .collect();
+        Some(lengths.join(" "))
     }
 }
 
@@ -461,6 +1221,7 @@ impl SvgCircle {
         self.stroke = Some(Stroke {
             color: color,
             width: width,
+            dash: Dash::Solid,
         });
         self
     }
@@ -492,6 +1253,10 @@ impl ToSvg for SvgCircle {
 
         group.append(element);
     }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        canvas.circle(self.pos + offset, self.radius, self.stroke, self.fill);
+    }
 }
 
 impl Bounded for SvgCircle {
@@ -515,10 +1280,16 @@ impl SvgRect {
         }
     }
 
+    /// A rectangle covering exactly the given bound.
+    pub fn from_bound(bound: Bound) -> Self {
+        SvgRect::new(bound.low, bound.size())
+    }
+
     pub fn stroke(mut self, color: SvgColor, width: f32) -> Self {
         self.stroke = Some(Stroke {
             color: color,
             width: width,
+            dash: Dash::Solid,
         });
         self
     }
@@ -573,6 +1344,18 @@ impl ToSvg for SvgRect {
         }
         group.append(element);
     }
+
+    // Only the outline is drawn, without fill or fillets.
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        if let Some(stroke) = self.stroke {
+            let low = self.pos + offset;
+            canvas.move_to(low);
+            canvas.line_to(low + V2::new(self.size.x, 0.));
+            canvas.line_to(low + self.size);
+            canvas.line_to(low + V2::new(0., self.size.y));
+            canvas.end_path(true, stroke);
+        }
+    }
 }
 
 impl Bounded for SvgRect {
@@ -584,6 +1367,55 @@ impl Bounded for SvgRect {
     }
 }
 
+impl SvgSymbol {
+    /// Make a symbol with the given id. The id must be unique within
+    /// the document it gets defined in.
+    pub fn new(id: &str, contents: SvgGroup) -> SvgSymbol {
+        SvgSymbol {
+            id: id.to_owned(),
+            contents: contents,
+        }
+    }
+
+    /// Reference this symbol, shifted by `offset`.
+    pub fn place(&self, offset: V2) -> SvgUse {
+        SvgUse {
+            symbol: self.clone(),
+            offset: offset,
+        }
+    }
+
+    fn finalize(&self, scale_from_feet: f32) -> Group {
+        let mut group = self.contents.finalize(scale_from_feet);
+        group.assign("id", self.id.clone());
+        group
+    }
+}
+
+impl ToSvg for SvgUse {
+    fn finalize_to(&self, group: &mut Group, scale_from_feet: f32) {
+        let scale = scale(scale_from_feet);
+        let element = Use::new()
+            .set("xlink:href", format!("#{}", self.symbol.id))
+            .set("x", self.offset.x * scale)
+            .set("y", self.offset.y * scale);
+        group.append(element);
+    }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        self.symbol.contents.render_to(canvas, offset + self.offset);
+    }
+}
+
+impl Bounded for SvgUse {
+    fn bound(&self) -> Option<Bound> {
+        self.symbol
+            .contents
+            .bound()
+            .map(|bound| bound.translate(self.offset))
+    }
+}
+
 impl SvgText {
     fn line_height(&self) -> f32 {
         self.size
@@ -592,38 +1424,233 @@ impl SvgText {
     fn total_height(&self) -> f32 {
         self.line_height() * ((self.lines.len() as f32) - 1.)
     }
+
+    /// Turn the text clockwise on the page, by `angle` degrees.
+    pub fn rotated(mut self, angle: f32) -> Self {
+        self.style.angle = angle;
+        self
+    }
+
+    /// Line up the text with its position differently than centered.
+    pub fn anchored(mut self, align: Align, baseline: Baseline) -> Self {
+        self.style.align = align;
+        self.style.baseline = baseline;
+        self
+    }
+
+    // Where each line of text goes, turned along with the text.
+    fn line_positions(&self) -> Vec<P2> {
+        let first = -self.total_height() / 2.;
+        (0..self.lines.len())
+            .map(|i| {
+                let down = first + i as f32 * self.line_height();
+                self.pos + rotate(V2::new(0., down), self.style.angle)
+            })
+            .collect()
+    }
+
+    /// Roughly how wide the widest line is, from its character count.
+    pub fn width(&self) -> f32 {
+        let chars = self.lines.iter().map(|line| line.chars().count()).max();
+        CHAR_WIDTH * self.size * chars.unwrap_or(0) as f32
+    }
 }
 
 impl ToSvg for SvgText {
     fn finalize_to(&self, group: &mut Group, scale_from_feet: f32) {
         let scale = scale(scale_from_feet);
-        let mut y = (self.pos.y - self.total_height() / 2.) * scale;
-        let line_height = self.line_height() * scale;
-        for line in &self.lines {
-            let text = Text::new()
-                .set("x", self.pos.x * scale)
-            .set("y", y)
-            .set("font-size", self.size * scale)
-            .set("font-style", "normal")
-            .set("font-weight",  "bold")
-            .set("font-family",  "sans-serif")
-            .set("dominant-baseline", "central") // center vertically
-            .set("text-anchor", "middle") // center horizontally
-            .set("fill", self.color)
-            .add(node::Text::new(line.to_owned()));
+        for (line, pos) in self.lines.iter().zip(self.line_positions()) {
+            let (x, y) = (pos.x * scale, pos.y * scale);
+            let mut text = Text::new()
+                .set("x", x)
+                .set("y", y)
+                .set("font-size", self.size * scale)
+                .set("font-style", "normal")
+                .set("font-weight", "bold")
+                .set("font-family", "sans-serif")
+                .set("dominant-baseline", self.style.baseline.name())
+                .set("text-anchor", self.style.align.name())
+                .set("fill", self.color)
+                .add(node::Text::new(line.to_owned()));
+            if self.style.angle != 0. {
+                let turn = format!("rotate({} {} {})", self.style.angle, x, y);
+                text.assign("transform", turn);
+            }
 
             group.append(text);
-            y += line_height;
+        }
+    }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        for (line, pos) in self.lines.iter().zip(self.line_positions()) {
+            canvas.text(pos + offset, line, self.size, self.color, self.style);
         }
     }
 }
 
 impl Bounded for SvgText {
     fn bound(&self) -> Option<Bound> {
-        // We don't know how big text is, because rendering it is complicated :(
-        None
+        // Rendering text is complicated, and depends on which font the
+        // viewer picks, so this is only an estimate.
+        if self.lines.is_empty() {
+            return None;
+        }
+        // The corners of the box around the text, before it's turned.
+        let width = self.width();
+        let left = -width * self.style.align.fraction();
+        let right = left + width;
+        let half_height = self.total_height() / 2.;
+        let (top, bottom) = match self.style.baseline {
+            Baseline::Central => {
+                (-half_height - self.size / 2., half_height + self.size / 2.)
+            }
+            // Most letters rise about 0.8 of the font size above the
+            // baseline, and descenders drop about 0.2 below it.
+            Baseline::Alphabetic => {
+                (-half_height - 0.8 * self.size, half_height + 0.2 * self.size)
+            }
+        };
+        let corners =
+            [(left, top), (right, top), (left, bottom), (right, bottom)];
+        let mut bound = Bound::empty_at(self.pos);
+        for &(x, y) in &corners {
+            let corner = self.pos + rotate(V2::new(x, y), self.style.angle);
+            bound = bound.union(Some(Bound::empty_at(corner)));
+        }
+        Some(bound)
+    }
+}
+impl PageSize {
+    /// How big the page is in the drawing, which is in feet, when it's
+    /// saved at the given scale.
+    pub fn in_feet(&self, scale_from_feet: f32) -> V2 {
+        V2::new(self.width, self.height) / (12. * scale_from_feet)
+    }
+}
+
+impl FromStr for PageSize {
+    type Err = LapstrakeError;
+    fn from_str(text: &str) -> Result<PageSize, LapstrakeError> {
+        let sizes: Vec<Option<f32>> = text
+            .to_lowercase()
+            .split('x')
+            .map(|size| size.trim().parse().ok())
+            .collect();
+        match sizes.as_slice() {
+            [Some(width), Some(height)] if *width > 0. && *height > 0. => {
+                Ok(PageSize {
+                    width: *width,
+                    height: *height,
+                })
+            }
+            _ => Err(LapstrakeError::General(format!(
+                "Unknown page size '{}'. Expected inches wide by inches \
+                 high, like 24x36.",
+                text
+            ))),
+        }
+    }
+}
+
+impl SvgDimension {
+    /// Dimension the distance from `start` to `end`. The dimension line
+    /// is drawn `offset` away from them, to the left when facing from
+    /// `start` to `end`, or to the right if the offset is negative.
+    pub fn new(start: P2, end: P2, offset: f32) -> SvgDimension {
+        SvgDimension {
+            start: start,
+            end: end,
+            offset: offset,
+            stroke: Stroke {
+                color: SvgColor::Black,
+                width: 0.01,
+                dash: Dash::Solid,
+            },
+            size: 0.25,
+        }
+    }
+
+    pub fn stroke(mut self, color: SvgColor, width: f32) -> Self {
+        self.stroke.color = color;
+        self.stroke.width = width;
+        self
+    }
+
+    /// Set the font size of the label. The arrowheads and the gaps
+    /// around the extension lines are scaled to match.
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    // Draw the dimension out of simpler elements.
+    fn parts(&self) -> SvgGroup {
+        let mut group = SvgGroup::new();
+        let span = self.end - self.start;
+        let length = span.norm();
+        if length == 0. {
+            return group;
+        }
+        let along = span / length;
+        let left = rotate(along, -90.);
+        let side = left * self.offset.signum();
+        let reach = self.offset.abs();
+        let line = |points: Vec<P2>| {
+            SvgPath::new(points)
+                .stroke(self.stroke.color, self.stroke.width)
+                .style(PathStyle2::Line)
+        };
+
+        // Leave a gap between each point and its extension line, and run
+        // the line on a little past the dimension line.
+        let (gap, overshoot) = (self.size / 4., self.size / 2.);
+        for &point in &[self.start, self.end] {
+            group.append(line(vec![
+                point + side * gap,
+                point + side * (reach + overshoot),
+            ]));
+        }
+
+        let start = self.start + left * self.offset;
+        let end = self.end + left * self.offset;
+        group.append(line(vec![start, end]));
+        let arrow = self.size / 2.;
+        for &(tip, back) in &[(start, along), (end, -along)] {
+            group.append(line(vec![
+                tip + rotate(back, 20.) * arrow,
+                tip,
+                tip + rotate(back, -20.) * arrow,
+            ]));
+        }
+
+        group.append(SvgText {
+            lines: vec![Feet::from_f32(length).to_string()],
+            pos: P2::from_coordinates((start.coords + end.coords) / 2.)
+                + side * 0.75 * self.size,
+            color: self.stroke.color,
+            size: self.size,
+            style: TextStyle::default(),
+        }.rotated(upright_angle(along)));
+        group
+    }
+}
+
+impl ToSvg for SvgDimension {
+    fn finalize_to(&self, group: &mut Group, scale_from_feet: f32) {
+        self.parts().finalize_to(group, scale_from_feet);
+    }
+
+    fn render_to(&self, canvas: &mut Canvas, offset: V2) {
+        self.parts().render_to(canvas, offset);
+    }
+}
+
+impl Bounded for SvgDimension {
+    fn bound(&self) -> Option<Bound> {
+        self.parts().bound()
     }
 }
+
 impl Bound {
     pub fn new() -> Bound {
         Bound::from_origin(0., 0.)
@@ -681,6 +1708,16 @@ impl Bound {
         self.low + offset
     }
 
+    /// Grow the bound by `margin` on every side. A negative margin
+    /// shrinks it instead.
+    pub fn expand(&self, margin: f32) -> Bound {
+        let offset = V2::new(margin, margin);
+        Bound {
+            low: self.low - offset,
+            high: self.high + offset,
+        }
+    }
+
     fn translate(&self, trans_vec: V2) -> Bound {
         Bound {
             low: self.low + trans_vec,
@@ -782,21 +1819,90 @@ impl Bound {
 //     );
 // }
 
+impl SvgColor {
+    /// The red, green, and blue components of the color.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            // SvgColor::Red => (0xfa, 0x99, 0xb7),
+            SvgColor::Red => (0xff, 0x00, 0x00),
+            SvgColor::Yellow => (0xeb, 0xa6, 0x76),
+            SvgColor::Green => (0xa7, 0xbe, 0x74),
+            SvgColor::Cyan => (0x48, 0xc9, 0xb4),
+            SvgColor::Blue => (0x3a, 0xc3, 0xf5),
+            SvgColor::Magenta => (0xb9, 0xac, 0xf6),
+            SvgColor::Black => (0x00, 0x00, 0x00),
+            SvgColor::White => (0xff, 0xff, 0xff),
+            SvgColor::LightGrey => (0xee, 0xee, 0xee),
+            SvgColor::DarkGrey => (0xb6, 0xb6, 0xb6),
+            SvgColor::Custom(r, g, b) => (r, g, b),
+        }
+    }
+}
+
 impl Into<Value> for SvgColor {
     fn into(self) -> Value {
-        match self {
-            // SvgColor::Red => "#fa99b7",
-            SvgColor::Red => "red",
-            SvgColor::Yellow => "#eba676",
-            SvgColor::Green => "#a7be74",
-            SvgColor::Cyan => "#48c9b4",
-            SvgColor::Blue => "#3ac3f5",
-            SvgColor::Magenta => "#b9acf6",
-            SvgColor::Black => "#000000",
-            SvgColor::White => "#ffffff",
-            SvgColor::LightGrey => "#eeeeee",
-            SvgColor::DarkGrey => "#b6b6b6",
-        }.into()
+        let (r, g, b) = self.rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b).into()
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> TextStyle {
+        TextStyle {
+            angle: 0.,
+            align: Align::Middle,
+            baseline: Baseline::Central,
+        }
+    }
+}
+
+impl Align {
+    /// How much of the text's width is before its position.
+    pub fn fraction(&self) -> f32 {
+        match *self {
+            Align::Start => 0.,
+            Align::Middle => 0.5,
+            Align::End => 1.,
+        }
+    }
+
+    // The value of svg's text-anchor attribute.
+    fn name(&self) -> &'static str {
+        match *self {
+            Align::Start => "start",
+            Align::Middle => "middle",
+            Align::End => "end",
+        }
+    }
+}
+
+impl Baseline {
+    // The value of svg's dominant-baseline attribute.
+    fn name(&self) -> &'static str {
+        match *self {
+            Baseline::Central => "central",
+            Baseline::Alphabetic => "alphabetic",
+        }
+    }
+}
+
+/// Turn a vector clockwise on the page, by `angle` degrees. Svg's y axis
+/// points down, so this is the usual rotation.
+pub fn rotate(v: V2, angle: f32) -> V2 {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    V2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+/// The angle to turn text by so that it runs along `direction`, turned
+/// around if need be so that it isn't upside down.
+pub fn upright_angle(direction: V2) -> f32 {
+    let angle = direction.y.atan2(direction.x).to_degrees();
+    if angle > 90. {
+        angle - 180.
+    } else if angle <= -90. {
+        angle + 180.
+    } else {
+        angle
     }
 }
 
@@ -808,12 +1914,30 @@ fn scale(scale_from_feet: f32) -> f32 {
     scale_from_feet * 12. * PIXELS_PER_INCH
 }
 
-pub fn make_scale_bar() -> Result<SvgGroup, LapstrakeError> {
-    let stroke = 0.05;
-    let short_length = 1.;
-    let long_length = 10.;
-    let cap_length = short_length / 10.;
-    let font_size = short_length / 4.;
+/// Make a pair of scale bars, a long one and a short one, labeled in
+/// `units`. Their lengths are round numbers, picked so that the long one
+/// comes out about 4" long on paper when saved at `scale_from_feet`.
+pub fn make_scale_bar(
+    units: Units,
+    scale_from_feet: f32,
+) -> Result<SvgGroup, LapstrakeError> {
+    const PAPER_LENGTH: f32 = 4. / 12.;
+    let lengths = scale_bar_lengths(units);
+    let longest_under = |limit: f32| {
+        lengths
+            .iter()
+            .filter(|&&(length, _)| length <= limit)
+            .last()
+            .unwrap_or(&lengths[0])
+            .clone()
+    };
+    let (long_length, long_text) =
+        longest_under(PAPER_LENGTH / scale_from_feet);
+    let (short_length, short_text) = longest_under(long_length / 5.);
+    // Sized like the 1 ft and 10 ft bars that used to be the only ones.
+    let stroke = long_length / 200.;
+    let cap_length = long_length / 100.;
+    let font_size = long_length / 40.;
 
     let short = make_capped_line(short_length - stroke, cap_length)
         .stroke(SvgColor::Black, stroke);
@@ -822,16 +1946,18 @@ pub fn make_scale_bar() -> Result<SvgGroup, LapstrakeError> {
         .stroke(SvgColor::Black, stroke);
 
     let short_label = SvgText {
-        lines: vec!["1 ft".into()],
+        lines: vec![short_text],
         pos: short.bound().unwrap().center() + V2::new(0., font_size),
         color: SvgColor::Black,
         size: font_size,
+        style: TextStyle::default(),
     };
     let long_label = SvgText {
-        lines: vec!["10 ft".into()],
+        lines: vec![long_text],
         pos: long.bound().unwrap().center() + V2::new(0., font_size),
         color: SvgColor::Black,
         size: font_size,
+        style: TextStyle::default(),
     };
 
     let mut short_group = SvgGroup::new();
@@ -843,6 +1969,41 @@ pub fn make_scale_bar() -> Result<SvgGroup, LapstrakeError> {
     SvgGroup::new_vertical(vec![long_group, short_group], cap_length)
 }
 
+// The round lengths that a scale bar can be, shortest first, in feet and
+// labeled in `units`.
+fn scale_bar_lengths(units: Units) -> Vec<(f32, String)> {
+    match units {
+        Units::Feet => {
+            let inches = [
+                0.125, 0.25, 0.5, 1., 2., 3., 6., 12., 24., 60., 120., 240.,
+                600., 1200.,
+            ];
+            inches
+                .iter()
+                .map(|inches| {
+                    let feet = inches / 12.;
+                    (feet, Feet::from_f32(feet).to_string())
+                })
+                .collect()
+        }
+        Units::Millimeters => {
+            let mut lengths = vec![];
+            for power in 0..6 {
+                for &digit in &[1., 2., 5.] {
+                    let mm = digit * 10_f32.powi(power);
+                    let label = if mm >= 1000. {
+                        format!("{} m", mm / 1000.)
+                    } else {
+                        format!("{} mm", mm)
+                    };
+                    lengths.push((mm / MILLIMETERS_PER_FOOT, label));
+                }
+            }
+            lengths
+        }
+    }
+}
+
 fn make_capped_line(length: f32, cap_length: f32) -> SvgPath {
     let pos = P2::origin();
     let cap_offset = V2::new(0., cap_length);
@@ -855,3 +2016,103 @@ fn make_capped_line(length: f32, cap_length: f32) -> SvgPath {
         pos + line_offset + cap_offset,
     ])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(width: f32, height: f32) -> SvgGroup {
+        let mut group = SvgGroup::new();
+        group.append(SvgRect::new(P2::origin(), V2::new(width, height)));
+        group
+    }
+
+    #[test]
+    fn test_new_pages() {
+        let page = V2::new(10., 10.);
+        // Two fit across the first row and one starts the second, but the
+        // last is too tall for the rest of the page.
+        let groups =
+            vec![block(4., 3.), block(4., 3.), block(4., 3.), block(4., 8.)];
+        let pages = SvgGroup::new_pages(groups, 1., page).unwrap();
+        assert_eq!(pages.len(), 2);
+        let first = pages[0].bound().unwrap();
+        assert_eq!(first.low, P2::new(0., 0.));
+        assert_eq!(first.high, P2::new(9., 7.));
+        // It starts the new page at the top left.
+        let second = pages[1].bound().unwrap();
+        assert_eq!(second.low, P2::new(0., 0.));
+        assert_eq!(second.high, P2::new(4., 8.));
+    }
+
+    #[test]
+    fn test_new_pages_oversized() {
+        let page = V2::new(10., 10.);
+        let groups = vec![block(12., 3.), block(4., 12.), block(4., 3.)];
+        let pages = SvgGroup::new_pages(groups, 1., page).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].bound().unwrap().low, P2::new(0., 0.));
+    }
+
+    #[test]
+    fn test_page_size() {
+        assert_eq!(
+            "24x36".parse::<PageSize>().unwrap(),
+            PageSize {
+                width: 24.,
+                height: 36.,
+            }
+        );
+        assert_eq!(
+            " 8.5 X 11 ".parse::<PageSize>().unwrap(),
+            PageSize {
+                width: 8.5,
+                height: 11.,
+            }
+        );
+        for bad in &["24", "24x36x48", "0x36", "24x-1", "ax36", ""] {
+            assert!(bad.parse::<PageSize>().is_err(), "parsed {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_cubic_bezier_ends() {
+        let (start, end) = (P2::new(1., 2.), P2::new(5., -3.));
+        let (c1, c2) = (P2::new(2., 7.), P2::new(-4., 0.));
+        assert_eq!(cubic_bezier(start, c1, c2, end, 0.), start);
+        assert_eq!(cubic_bezier(start, c1, c2, end, 1.), end);
+    }
+
+    #[test]
+    fn test_fit_curves() {
+        use draw::CURVE_TOLERANCE;
+        // A finely sampled quarter circle, 2 feet across.
+        let points: Vec<P2> = (0..51)
+            .map(|i| {
+                let angle = i as f32 / 50. * ::std::f32::consts::PI / 2.;
+                P2::new(2. * angle.cos(), 2. * angle.sin())
+            })
+            .collect();
+        let curves = fit_curves(&points, CURVE_TOLERANCE);
+        assert!(curves.len() < points.len() - 1);
+
+        // Each curve ends on one of the points, and passes near the
+        // points between that and the last curve's end, evenly spaced.
+        let mut start = 0;
+        for &(c1, c2, end_point) in &curves {
+            let end = points
+                .iter()
+                .position(|&p| p == end_point)
+                .expect("curve doesn't end on a point");
+            assert!(end > start);
+            for i in start..end + 1 {
+                let t = (i - start) as f32 / (end - start) as f32;
+                let p = cubic_bezier(points[start], c1, c2, end_point, t);
+                let off = (p - points[i]).norm();
+                assert!(off <= CURVE_TOLERANCE, "point {} is {} off", i, off);
+            }
+            start = end;
+        }
+        assert_eq!(start, points.len() - 1);
+    }
+}
@@ -1,7 +1,13 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
 use scad_dots::core::{chain, Dot, DotAlign, DotShape, DotSpec, Tree};
 use scad_dots::errors::ScadDotsError;
 pub use scad_dots::harness::preview_model;
-use scad_dots::utils::{P3, R3};
+use scad_dots::utils::{P3, R3, V3};
+
+use error::{LapstrakeError, ResultExt};
 
 pub const SCAD_STROKE: f32 = 0.1;
 
@@ -87,6 +93,215 @@ impl ScadPath {
     }
 }
 
+/// A closed surface, made of flat polygonal faces, that is written to
+/// OpenSCAD as a single `polyhedron()`. This is much smaller and faster
+/// to render than building surfaces out of hulls of spheres.
+#[derive(Debug, Clone)]
+pub struct ScadSurface {
+    pub points: Vec<P3>,
+    /// Each face, as indices into `points`, going clockwise when seen
+    /// from outside (as OpenSCAD expects).
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl ScadSurface {
+    /// Loft a closed surface through a series of rings, which must all
+    /// have the same number of points. Each ring should go
+    /// counter-clockwise when seen looking back along the direction that
+    /// the rings advance in. The first and last rings are capped off
+    /// with flat faces.
+    pub fn loft(rings: &[Vec<P3>]) -> Result<ScadSurface, LapstrakeError> {
+        let mut surface = ScadSurface::tube(rings)?;
+        let k = rings[0].len();
+        let last = (rings.len() - 1) * k;
+        surface.faces.push((0..k).collect());
+        surface.faces.push((0..k).rev().map(|j| last + j).collect());
+        Ok(surface)
+    }
+
+    /// Like `loft()`, but with the ends left open, for capping off some
+    /// other way.
+    pub fn tube(rings: &[Vec<P3>]) -> Result<ScadSurface, LapstrakeError> {
+        let k = rings.first().map_or(0, |ring| ring.len());
+        if rings.len() < 2 || k < 3 || rings.iter().any(|r| r.len() != k) {
+            return Err(LapstrakeError::General(
+                "Can only loft through 2 or more rings of equal size.".into(),
+            ));
+        }
+        let mut points = vec![];
+        for ring in rings {
+            points.extend(ring);
+        }
+        let index = |i: usize, j: usize| i * k + j % k;
+        let mut faces = vec![];
+        for i in 0..rings.len() - 1 {
+            for j in 0..k {
+                faces.push(vec![
+                    index(i, j),
+                    index(i + 1, j),
+                    index(i + 1, j + 1),
+                    index(i, j + 1),
+                ]);
+            }
+        }
+        Ok(ScadSurface { points, faces })
+    }
+
+    /// Make a surface out of separate faces, each going clockwise when
+    /// seen from outside. Points that the faces share are repeated.
+    pub fn from_faces(polygons: Vec<Vec<P3>>) -> ScadSurface {
+        let mut points = vec![];
+        let mut faces = vec![];
+        for polygon in polygons {
+            let start = points.len();
+            faces.push((start..start + polygon.len()).collect());
+            points.extend(polygon);
+        }
+        ScadSurface { points, faces }
+    }
+
+    /// Extrude a flat polygon by `direction`, making a slab with the
+    /// polygon for each of its faces. The polygon can go either way
+    /// around.
+    pub fn extrude(
+        outline: &[P3],
+        direction: V3,
+    ) -> Result<ScadSurface, LapstrakeError> {
+        let mut near = outline.to_vec();
+        // Newell's method, for the normal of the polygon. The rings must
+        // go counter-clockwise when seen from the direction they advance
+        // in, so the normal has to point that way.
+        let mut normal = V3::new(0., 0., 0.);
+        for (i, p) in near.iter().enumerate() {
+            let q = near[(i + 1) % near.len()];
+            normal.x += (p.y - q.y) * (p.z + q.z);
+            normal.y += (p.z - q.z) * (p.x + q.x);
+            normal.z += (p.x - q.x) * (p.y + q.y);
+        }
+        if normal.dot(&direction) < 0. {
+            near.reverse();
+        }
+        let far = near.iter().map(|p| p + direction).collect();
+        ScadSurface::loft(&[near, far])
+    }
+
+    /// Combine several surfaces into one, to write them to the same file.
+    pub fn join(surfaces: Vec<ScadSurface>) -> ScadSurface {
+        let mut points = vec![];
+        let mut faces = vec![];
+        for surface in surfaces {
+            let start = points.len();
+            points.extend(surface.points);
+            for face in surface.faces {
+                faces.push(face.into_iter().map(|i| i + start).collect());
+            }
+        }
+        ScadSurface { points, faces }
+    }
+
+    /// Write as OpenSCAD source.
+    pub fn to_scad(&self) -> String {
+        let mut scad = String::new();
+        scad.push_str("polyhedron(\n  points=[\n");
+        for p in &self.points {
+            writeln!(scad, "    [{}, {}, {}],", p.x, p.y, p.z).unwrap();
+        }
+        scad.push_str("  ],\n  faces=[\n");
+        for face in &self.faces {
+            let indices: Vec<String> =
+                face.iter().map(|i| i.to_string()).collect();
+            writeln!(scad, "    [{}],", indices.join(", ")).unwrap();
+        }
+        scad.push_str("  ]\n);\n");
+        scad
+    }
+
+    pub fn save(&self, filename: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", filename);
+        fs::write(filename, self.to_scad())
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+
+    /// Write as an ASCII STL mesh, for 3d printing, with every point
+    /// multiplied by `scale`. Faces with more than three sides are split
+    /// into fans of triangles, so they should be convex.
+    pub fn to_stl(&self, name: &str, scale: f32) -> String {
+        let mut stl = String::new();
+        writeln!(stl, "solid {}", name).unwrap();
+        for face in &self.faces {
+            // STL wants them counter-clockwise when seen from outside.
+            let corners: Vec<P3> = face
+                .iter()
+                .rev()
+                .map(|&i| P3::from_coordinates(self.points[i].coords * scale))
+                .collect();
+            for i in 1..corners.len().saturating_sub(1) {
+                let (p, q, r) = (corners[0], corners[i], corners[i + 1]);
+                let normal = (q - p).cross(&(r - p));
+                let normal = if normal.norm() > 0. {
+                    normal.normalize()
+                } else {
+                    normal
+                };
+                writeln!(
+                    stl,
+                    "  facet normal {} {} {}",
+                    normal.x, normal.y, normal.z
+                ).unwrap();
+                stl.push_str("    outer loop\n");
+                for v in &[p, q, r] {
+                    writeln!(stl, "      vertex {} {} {}", v.x, v.y, v.z)
+                        .unwrap();
+                }
+                stl.push_str("    endloop\n  endfacet\n");
+            }
+        }
+        writeln!(stl, "endsolid {}", name).unwrap();
+        stl
+    }
+
+    pub fn save_stl(
+        &self,
+        filename: &Path,
+        scale: f32,
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", filename);
+        let name = filename
+            .file_stem()
+            .map_or("lapstrake".into(), |stem| stem.to_string_lossy());
+        fs::write(filename, self.to_stl(&name, scale))
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
+/// A solid with others cut out of it, written to OpenSCAD as a
+/// `difference()`.
+#[derive(Debug, Clone)]
+pub struct ScadSolid {
+    pub outside: ScadSurface,
+    pub cutouts: Vec<ScadSurface>,
+}
+
+impl ScadSolid {
+    /// Write as OpenSCAD source.
+    pub fn to_scad(&self) -> String {
+        let mut scad = String::new();
+        scad.push_str("difference() {\n");
+        scad.push_str(&self.outside.to_scad());
+        for cutout in &self.cutouts {
+            scad.push_str(&cutout.to_scad());
+        }
+        scad.push_str("}\n");
+        scad
+    }
+
+    pub fn save(&self, filename: &Path) -> Result<(), LapstrakeError> {
+        println!("Saving file: {:?}.", filename);
+        fs::write(filename, self.to_scad())
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,6 +349,41 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_loft_box() {
+        let square = |x| {
+            vec![
+                P3::new(x, 0., 0.),
+                P3::new(x, 1., 0.),
+                P3::new(x, 1., 1.),
+                P3::new(x, 0., 1.),
+            ]
+        };
+        let surface = ScadSurface::loft(&[square(0.), square(1.)]).unwrap();
+        assert_eq!(surface.points.len(), 8);
+        assert_eq!(surface.faces.len(), 6);
+        assert_eq!(surface.faces[0], vec![0, 4, 5, 1]);
+        assert_eq!(surface.faces[5], vec![7, 6, 5, 4]);
+    }
+
+    #[test]
+    fn test_box_stl() {
+        let square = |x| {
+            vec![
+                P3::new(x, 0., 0.),
+                P3::new(x, 1., 0.),
+                P3::new(x, 1., 1.),
+                P3::new(x, 0., 1.),
+            ]
+        };
+        let surface = ScadSurface::loft(&[square(0.), square(1.)]).unwrap();
+        let stl = surface.to_stl("box", 2.);
+        assert_eq!(stl.matches("facet normal").count(), 12);
+        // The cap at x = 0 faces back along -x.
+        assert!(stl.contains("facet normal -1 0 0"));
+        assert!(stl.contains("vertex 2 2 2"));
+    }
+
     #[test]
     fn test_surface() {
         check_model("test_surface", Action::Test, || {
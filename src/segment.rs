@@ -0,0 +1,322 @@
+//! Splitting the solid hull into segments, for 3d printing a model that's
+//! longer than the printer's bed. The cuts are made at stations, and each
+//! one has a square key sticking out of the forward segment that fits a
+//! socket in the aft one, to line them up for gluing.
+
+use std::f32;
+use std::f32::consts::PI;
+
+use scad_dots::utils::{Axis, P3, V3};
+
+use error::LapstrakeError;
+use hull::Hull;
+use render_3d::ScadSurface;
+use util::{
+    both_sides, polygon_contains, practically_zero, project, project_points,
+    sort_by_number,
+};
+
+/// How many points to sample up each side of the hull's sections.
+const GIRTH_SAMPLES: usize = 20;
+
+/// How many sections to loft through along the whole length of the hull,
+/// shared out between the segments.
+const LENGTH_SAMPLES: usize = 60;
+
+/// How wide and deep the keys are, as a fraction of the height of the
+/// section they're in.
+const KEY_SIZE: f32 = 0.25;
+
+// The key at a cut: a square centered on the centerline.
+struct Key {
+    center: P3,
+    half_width: f32,
+}
+
+impl Hull {
+    /// Split the closed surface of the hull into segments, from fore to
+    /// aft, that are no longer than `max_length`, not counting their keys.
+    /// The sockets are `clearance` bigger than the keys all around, and
+    /// that much deeper.
+    pub fn segments(
+        &self,
+        max_length: f32,
+        clearance: f32,
+    ) -> Result<Vec<ScadSurface>, LapstrakeError> {
+        let cuts = self.segment_cuts(max_length)?;
+        let length = cuts[cuts.len() - 1] - cuts[0];
+        let mut sections = vec![];
+        for pair in cuts.windows(2) {
+            let (fore, aft) = (pair[0], pair[1]);
+            let count = (LENGTH_SAMPLES as f32 * (aft - fore) / length).ceil();
+            let count = count.max(2.) as usize;
+            let mut rings = vec![];
+            for j in 0..count {
+                let x = fore + (aft - fore) * j as f32 / (count - 1) as f32;
+                rings.push(self.section_ring(x)?);
+            }
+            sections.push(rings);
+        }
+        close_segments(&sections, clearance)
+    }
+
+    // The positions to cut the hull at, including its ends: as few
+    // stations as will keep each segment within `max_length`.
+    fn segment_cuts(
+        &self,
+        max_length: f32,
+    ) -> Result<Vec<f32>, LapstrakeError> {
+        let mut positions: Vec<f32> =
+            self.stations.iter().map(|s| s.position()).collect();
+        sort_by_number(&mut positions, |&x| x)?;
+        positions.dedup_by(|a, b| practically_zero(*a - *b));
+        if positions.len() < 2 {
+            return Err(LapstrakeError::General(
+                "Can't split a hull with fewer than two stations.".into(),
+            ));
+        }
+        let mut cuts = vec![positions[0]];
+        for pair in positions.windows(2) {
+            let last = cuts[cuts.len() - 1];
+            if pair[1] - last > max_length {
+                if practically_zero(pair[0] - last) {
+                    return Err(LapstrakeError::General(format!(
+                        "Can't split the hull into segments {} ft long, \
+                         because the stations at {} and {} are farther \
+                         apart than that.",
+                        max_length, pair[0], pair[1]
+                    )));
+                }
+                cuts.push(pair[0]);
+            }
+        }
+        cuts.push(positions[positions.len() - 1]);
+        Ok(cuts)
+    }
+
+    // The section through the whole hull at the given fore-aft position:
+    // up the starboard side from the keel, then back down the port side,
+    // as `render_surface()` lofts them.
+    fn section_ring(&self, x: f32) -> Result<Vec<P3>, LapstrakeError> {
+        let station = self.hallucinate_station(self.units.from_feet(x))?;
+        Ok(both_sides(station.spline.resample_even(GIRTH_SAMPLES + 1)?))
+    }
+}
+
+// Close off the ends of each segment, given the sections to loft through
+// it, keying each one into the next.
+fn close_segments(
+    sections: &[Vec<Vec<P3>>],
+    clearance: f32,
+) -> Result<Vec<ScadSurface>, LapstrakeError> {
+    let mut segments = vec![];
+    let mut socket: Option<Key> = None;
+    for (i, rings) in sections.iter().enumerate() {
+        let mut parts = vec![ScadSurface::tube(rings)?];
+        let (first, last) = (&rings[0], &rings[rings.len() - 1]);
+        parts.push(match socket.take() {
+            None => cap(first, section_center(first), None, -1.)?,
+            Some(key) => {
+                let depth = 2. * key.half_width + clearance;
+                let rim = square(key.center, key.half_width + clearance);
+                let mut hole = peg(&rim, depth)?;
+                for face in &mut hole.faces {
+                    face.reverse();
+                }
+                ScadSurface::join(vec![
+                    cap(first, key.center, Some(&rim), -1.)?,
+                    hole,
+                ])
+            }
+        });
+        parts.push(if i + 1 == sections.len() {
+            cap(last, section_center(last), None, 1.)?
+        } else {
+            let key = key_at(last)?;
+            let rim = square(key.center, key.half_width);
+            let surface = ScadSurface::join(vec![
+                cap(last, key.center, Some(&rim), 1.)?,
+                peg(&rim, 2. * key.half_width)?,
+            ]);
+            socket = Some(key);
+            surface
+        });
+        segments.push(ScadSurface::join(parts));
+    }
+    Ok(segments)
+}
+
+// The starboard sheer of a section, which is halfway around it.
+fn sheer(ring: &[P3]) -> P3 {
+    ring[ring.len() / 2]
+}
+
+// The middle of a section, on the centerline halfway from the keel to the
+// sheer.
+fn section_center(ring: &[P3]) -> P3 {
+    let (keel, sheer) = (ring[0], sheer(ring));
+    P3::new(keel.x, 0., (keel.z + sheer.z) / 2.)
+}
+
+// Size the key at a cut to the section there, making sure that it fits
+// with room to spare.
+fn key_at(ring: &[P3]) -> Result<Key, LapstrakeError> {
+    let center = section_center(ring);
+    let height = sheer(ring).z - ring[0].z;
+    let half_width = KEY_SIZE * height / 2.;
+    let section = project_points(Axis::X, ring);
+    let fits = square(center, 2. * half_width)
+        .into_iter()
+        .all(|corner| polygon_contains(&section, project(Axis::X, corner)));
+    if !fits {
+        return Err(LapstrakeError::General(format!(
+            "The hull is too thin at {} to key the segments together.",
+            center.x
+        )));
+    }
+    Ok(Key { center, half_width })
+}
+
+// A square in the plane of a section, going counter-clockwise in (y, z).
+fn square(center: P3, half_width: f32) -> Vec<P3> {
+    [(1., -1.), (1., 1.), (-1., 1.), (-1., -1.)]
+        .iter()
+        .map(|&(y, z)| center + V3::new(0., y, z) * half_width)
+        .collect()
+}
+
+// A square peg sticking aft out of the section that the rim is in, open
+// where it meets the section.
+fn peg(rim: &[P3], depth: f32) -> Result<ScadSurface, LapstrakeError> {
+    let mut peg = ScadSurface::extrude(rim, V3::new(depth, 0., 0.))?;
+    // The end that's in the section's plane.
+    let near = peg.faces.len() - 2;
+    peg.faces.remove(near);
+    Ok(peg)
+}
+
+// Cap off the end of a segment, across the section `ring`, facing forward
+// (-1) or aft (1). If there's a rim, the cap is left open inside of it, for
+// a key or socket. Triangles are fanned out from the center to the
+// section (and rim), so the section has to be visible all the way around
+// from there.
+fn cap(
+    ring: &[P3],
+    center: P3,
+    rim: Option<&[P3]>,
+    facing: f32,
+) -> Result<ScadSurface, LapstrakeError> {
+    let angle = |p: &P3| (p.z - center.z).atan2(p.y - center.y);
+    // Go counter-clockwise in (y, z), starting anywhere on the section.
+    let mut outer = ring.to_vec();
+    let twice_area: f32 = (0..outer.len())
+        .map(|i| {
+            let (p, q) = (outer[i], outer[(i + 1) % outer.len()]);
+            p.y * q.z - q.y * p.z
+        })
+        .sum();
+    if twice_area < 0. {
+        outer.reverse();
+    }
+    let start = angle(&outer[0]);
+    let around = |p: &P3| {
+        let mut a = angle(p) - start;
+        while a < 0. {
+            a += 2. * PI;
+        }
+        a
+    };
+    // And the same for the rim, starting from just past the section's
+    // start.
+    let mut inner = rim.map_or(vec![center], |rim| rim.to_vec());
+    let mut order: Vec<usize> = (0..inner.len()).collect();
+    sort_by_number(&mut order, |&i| around(&inner[i]))?;
+    inner.rotate_left(order[0]);
+
+    let (n, m) = (outer.len(), inner.len());
+    let mut outer_angles: Vec<f32> = outer.iter().map(&around).collect();
+    outer_angles[0] = 0.;
+    outer_angles.push(2. * PI);
+    let mut inner_angles: Vec<f32> = inner.iter().map(&around).collect();
+    inner_angles.push(inner_angles[0] + 2. * PI);
+    if m == 1 {
+        inner_angles[1] = f32::INFINITY;
+    }
+
+    // Zip the two together, each triangle counter-clockwise in (y, z).
+    let mut triangles = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < n || (j < m && m > 1) {
+        if j == m || (i < n && outer_angles[i + 1] <= inner_angles[j + 1]) {
+            triangles.push(vec![outer[i], outer[(i + 1) % n], inner[j % m]]);
+            i += 1;
+        } else {
+            triangles.push(vec![outer[i % n], inner[(j + 1) % m], inner[j]]);
+            j += 1;
+        }
+    }
+    // Counter-clockwise in (y, z) is counter-clockwise seen from aft, so
+    // clockwise seen from forward, which is what the surface wants for a
+    // cap facing forward.
+    if facing > 0. {
+        for triangle in &mut triangles {
+            triangle.reverse();
+        }
+    }
+    Ok(ScadSurface::from_faces(triangles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec::Spec;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use util::both_sides;
+
+    #[test]
+    fn test_segment_cuts() {
+        // Its stations are at 2, 5, 8, and 11 ft.
+        let hull = Spec::load_from(Path::new("templates"))
+            .and_then(|spec| spec.get_hull())
+            .unwrap();
+        assert_eq!(hull.segment_cuts(4.).unwrap(), vec![2., 5., 8., 11.]);
+        assert_eq!(hull.segment_cuts(6.5).unwrap(), vec![2., 8., 11.]);
+        assert_eq!(hull.segment_cuts(20.).unwrap(), vec![2., 11.]);
+        assert!(hull.segment_cuts(2.).is_err());
+    }
+
+    #[test]
+    fn test_segments_closed() {
+        // A box, 2 ft long, 2 ft wide, and 1 ft high, split in the middle.
+        let ring = |x: f32| {
+            both_sides(vec![
+                P3::new(x, 0., 0.),
+                P3::new(x, 1., 0.),
+                P3::new(x, 1., 1.),
+            ])
+        };
+        let sections =
+            vec![vec![ring(0.), ring(1.)], vec![ring(1.), ring(2.)]];
+        let segments = close_segments(&sections, 0.01).unwrap();
+        assert_eq!(segments.len(), 2);
+        for segment in &segments {
+            // Every edge is shared by exactly two faces. Faces don't
+            // always share their points, so match them up by position.
+            let key = |i: usize| {
+                let p = segment.points[i];
+                (p.x.to_bits(), p.y.to_bits(), p.z.to_bits())
+            };
+            let mut edges = HashMap::new();
+            for face in &segment.faces {
+                let n = face.len();
+                for j in 0..n {
+                    let (a, b) = (key(face[j]), key(face[(j + 1) % n]));
+                    let edge = if a < b { (a, b) } else { (b, a) };
+                    *edges.entry(edge).or_insert(0) += 1;
+                }
+            }
+            assert!(edges.values().all(|&count| count == 2));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Hollowing out the solid hull, to 3d print a model that floats: a shell
+//! of even thickness inside the planking, either sealed across the sheer
+//! or open at the top like the boat itself.
+
+use scad_dots::utils::{Axis, P2, P3};
+
+use error::LapstrakeError;
+use hull::Hull;
+use render_3d::{ScadSolid, ScadSurface};
+use util::{
+    both_sides, offset_polyline, polygon_area, polygon_contains,
+    practically_zero, project_points, sort_by_number,
+};
+
+/// How many points to sample up each side of the hull's sections.
+const GIRTH_SAMPLES: usize = 20;
+
+/// How many sections to loft the inside of the shell through.
+const LENGTH_SAMPLES: usize = 60;
+
+impl Hull {
+    /// Make the hull into a shell whose sides and bottom are `thickness`
+    /// thick, measured in the plane of each section, with bulkheads at
+    /// least that thick at the ends. If `open`, the shell is trimmed off
+    /// at the sheer, like the hull itself; otherwise it's sealed with a
+    /// deck of the same thickness.
+    pub fn to_solid(
+        &self,
+        thickness: f32,
+        open: bool,
+    ) -> Result<ScadSolid, LapstrakeError> {
+        if thickness <= 0. || practically_zero(thickness) {
+            return Err(LapstrakeError::General(format!(
+                "The shell must be thicker than {}.",
+                self.units.from_feet(thickness)
+            )));
+        }
+        let mut positions: Vec<f32> =
+            self.stations.iter().map(|s| s.position()).collect();
+        sort_by_number(&mut positions, |&x| x)?;
+        let (fore, aft) = (positions[0], positions[positions.len() - 1]);
+
+        // Find how far the hollow can reach towards each end, where the
+        // hull gets too fine to hollow out.
+        let step = (aft - fore) / LENGTH_SAMPLES as f32;
+        let mut start = fore + thickness;
+        while self.inner_ring(start, thickness, false)?.is_none() {
+            start += step;
+            if start > aft - thickness {
+                return Err(self.too_thin(thickness));
+            }
+        }
+        let mut end = aft - thickness;
+        while self.inner_ring(end, thickness, false)?.is_none() {
+            end -= step;
+            if end < start {
+                return Err(self.too_thin(thickness));
+            }
+        }
+
+        let mut rings = vec![];
+        for i in 0..LENGTH_SAMPLES {
+            let t = i as f32 / (LENGTH_SAMPLES - 1) as f32;
+            let x = start + (end - start) * t;
+            match self.inner_ring(x, thickness, open)? {
+                Some(ring) => rings.push(ring),
+                None => return Err(self.too_thin(thickness)),
+            }
+        }
+        Ok(ScadSolid {
+            outside: self.render_surface()?,
+            cutouts: vec![ScadSurface::loft(&rings)?],
+        })
+    }
+
+    // The inside of the shell at the given fore-aft position, going
+    // around the section the same way as `render_surface()`, or None if
+    // it's too small there to fit inside of the planking. If `open`, it
+    // reaches up past the sheer, to cut the top off.
+    fn inner_ring(
+        &self,
+        x: f32,
+        thickness: f32,
+        open: bool,
+    ) -> Result<Option<Vec<P3>>, LapstrakeError> {
+        let station = self.hallucinate_station(self.units.from_feet(x))?;
+        let outside = station.spline.resample_even(GIRTH_SAMPLES + 1)?;
+        let sheer = outside[GIRTH_SAMPLES];
+        let section_half = project_points(Axis::X, &outside);
+        let mut half = section_half.clone();
+        if open {
+            half.push(P2::new(sheer.y, sheer.z + 2. * thickness));
+        }
+        // Going up from the keel, the inside is to the left.
+        let mut inside = offset_polyline(&half, thickness);
+        // Slide the ends along their edges, to the centerline at the keel,
+        // and below the deck at the top.
+        let n = inside.len();
+        let keel = inside[0];
+        let along = half[1] - half[0];
+        if keel.x < 0. && !practically_zero(along.x) {
+            inside[0] = keel - along * (keel.x / along.x);
+        }
+        if !open {
+            let top = inside[n - 1];
+            let along = half[n - 1] - half[n - 2];
+            let deck = sheer.z - thickness;
+            if top.y > deck && !practically_zero(along.y) {
+                inside[n - 1] = top - along * ((top.y - deck) / along.y);
+            }
+        }
+
+        // Check that it fits, below the sheer.
+        let mut section = section_half.clone();
+        section.extend(section_half.iter().rev().map(|p| P2::new(-p.x, p.y)));
+        let fits = inside
+            .iter()
+            .filter(|p| p.y < sheer.z)
+            .all(|&p| {
+                (p.x >= 0. || practically_zero(p.x))
+                    && polygon_contains(&section, p)
+            });
+        let mut whole = inside.clone();
+        whole.extend(inside.iter().rev().map(|p| P2::new(-p.x, p.y)));
+        if !fits || practically_zero(polygon_area(&whole)) {
+            return Ok(None);
+        }
+
+        let starboard = inside.iter().map(|p| P3::new(x, p.x, p.y)).collect();
+        Ok(Some(both_sides(starboard)))
+    }
+
+    fn too_thin(&self, thickness: f32) -> LapstrakeError {
+        LapstrakeError::General(format!(
+            "The hull is too small to hollow out with a shell {} thick.",
+            self.units.from_feet(thickness)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec::Spec;
+    use std::path::Path;
+
+    #[test]
+    fn test_to_solid() {
+        // Its stations are at 2, 5, 8, and 11 ft, and it's at most 4' 6"
+        // wide.
+        let hull = Spec::load_from(Path::new("templates"))
+            .and_then(|spec| spec.get_hull())
+            .unwrap();
+        let thickness = 1. / 12.;
+        let solid = hull.to_solid(thickness, false).unwrap();
+        assert_eq!(solid.cutouts.len(), 1);
+        let breadth = solid
+            .outside
+            .points
+            .iter()
+            .map(|p| p.y)
+            .fold(0., f32::max);
+        let (fore, aft) = (2. + thickness - 1e-4, 11. - thickness + 1e-4);
+        for p in &solid.cutouts[0].points {
+            assert!(p.x >= fore && p.x <= aft);
+            assert!(p.y.abs() < breadth);
+        }
+
+        assert!(hull.to_solid(0., false).is_err());
+        // The error gives the thickness in the spec sheets' units.
+        let error = hull.to_solid(3., true).err().unwrap();
+        assert!(error.to_string().contains("3' 0\""), "{}", error);
+    }
+}
@@ -1,13 +1,17 @@
 //! Specifications for the ship hull.
 
-use std::cmp;
 use std::fmt;
 
-use error::LapstrakeError;
+use draw::{BodyPlan, Theme};
+use error::{LapstrakeError, ResultExt};
+use load::deserialize_opt_length;
+use render_2d::SvgUnits;
+use spline::Interpolation;
 use unit::*;
+use util::sort_by_number;
 
 /// The spec for the hull of a ship, plus configuration options.
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub struct Spec {
     pub data: Data,
     pub planks: Planks,
@@ -15,8 +19,11 @@ pub struct Spec {
 }
 
 /// A standard set of reference points for the hull shape.
-#[derive(Debug)]
+#[derive(Debug, Deserialize)]
 pub struct Data {
+    /// The units that the measurements were given in.
+    #[serde(default)]
+    pub units: Units,
     /// The names of the stations (cross sections of the hull).
     pub stations: Vec<String>,
     /// The locations of each of the stations.
@@ -29,13 +36,33 @@ pub struct Data {
     /// the half-breadth from centerline
     /// at each height above base.
     pub breadths: Vec<DataRow<HeightLine>>,
+    /// For each station,
+    /// the distance along each diagonal
+    /// from where it crosses the centerline.
+    #[serde(default)]
+    pub diagonals: Vec<DataRow<DiagonalLine>>,
+    /// The lines of constant breadth, from the heights section, that are
+    /// knuckles: each station has a sharp corner where it crosses them,
+    /// like at a chine.
+    #[serde(default)]
+    pub knuckle_breadths: Vec<BreadthLine>,
+    /// Likewise, the lines of constant height, from the breadths
+    /// section, that are knuckles.
+    #[serde(default)]
+    pub knuckle_heights: Vec<HeightLine>,
+    /// The profile of the stem, as (height above base, fore-aft position)
+    /// points along its rabbet, where the outside of the planking meets
+    /// it. If it's given, the station named Stem is lofted along it
+    /// instead of from its column, which is added if it's missing.
+    #[serde(default)]
+    pub stem_profile: Vec<(Length, Length)>,
 }
 
 /// One row of Data. `T` is one of HeightLine, BreadthLine.
-pub type DataRow<T> = (T, Vec<Option<Feet>>);
+pub type DataRow<T> = (T, Vec<Option<Length>>);
 
 /// Where planks should lie on the hull.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Planks {
     pub stations: Vec<PlankStation>,
     pub plank_locations: Vec<PlankRow>,
@@ -48,41 +75,432 @@ pub type PlankRow = Vec<Option<f32>>;
 
 /// A plank's location can be specified either along an existing
 /// station, or along a cross-section of constant fore-aft position.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PlankStation {
     Station(String),
-    Position(Feet),
+    Position(Length),
+}
+
+/// An external ballast keel, hung below the hull's keel line.
+#[derive(Debug, Clone)]
+pub struct Ballast {
+    /// Cross-sections of the ballast, from fore to aft.
+    pub sections: Vec<BallastSection>,
+    /// The units that the sheet was given in, which the ballast is
+    /// reported in.
+    pub units: Units,
+}
+
+/// One cross-section of a ballast keel. It is a trapezoid, with its top
+/// against the hull's keel.
+#[derive(Debug, Clone, Copy)]
+pub struct BallastSection {
+    pub position: Length,
+    /// How far the ballast extends below the keel line.
+    pub depth: Length,
+    pub top_width: Length,
+    pub bottom_width: Length,
+}
+
+/// The name of the station that's the transom, if there is one.
+pub const TRANSOM: &str = "Transom";
+
+/// How the transom is leaned and curved. Its offsets in the data sheet
+/// are its shape as seen in the body plan, and its position is where its
+/// centerline meets the sheer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Transom {
+    /// How far the top of the transom leans aft from vertical, in
+    /// degrees.
+    pub rake: f32,
+    /// The radius of the transom's curve, as seen from above, with its
+    /// middle bowed aft. None for a flat transom.
+    pub radius: Option<f32>,
+}
+
+/// The name of the station that's the stem, if there is one.
+pub const STEM: &str = "Stem";
+
+/// The size of the stem, if not configured: sided 1-1/2", with the inner
+/// stem moulded 2-1/2" and the outer stem 1".
+const DEFAULT_STEM_SIDING: f32 = 1.5 / 12.;
+const DEFAULT_STEM_MOULDING: f32 = 2.5 / 12.;
+const DEFAULT_OUTER_STEM_MOULDING: f32 = 1. / 12.;
+
+/// The size of the stem, which is built in two parts: an inner stem that
+/// the planks are fastened to, and an outer stem glued on over their
+/// ends. The station named Stem is the rabbet line between them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stem {
+    /// How wide the stem is, across its face.
+    pub siding: f32,
+    /// How deep the inner stem is, aft from the rabbet.
+    pub moulding: f32,
+    /// How deep the outer stem is, forward from the rabbet.
+    pub outer_moulding: f32,
+}
+
+/// The name of the station that's the sternpost, if there is one.
+pub const POST: &str = "Post";
+
+/// The size of the backbone's timbers, other than the stem. The hog runs
+/// along the inside of the keel line, between the stem and sternpost,
+/// with the garboards fastened to it, and the keel is fastened on below
+/// it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Backbone {
+    pub keel_siding: f32,
+    /// How deep the keel is, below the keel line.
+    pub keel_moulding: f32,
+    pub hog_siding: f32,
+    /// How deep the hog is, above the keel line.
+    pub hog_moulding: f32,
+    /// How deep the sternpost is, forward of its rabbet. It's sided like
+    /// the stem, or like the keel if there's no stem.
+    pub post_moulding: f32,
+}
+
+/// A centerboard or daggerboard trunk, whose slot is cut up through the
+/// keel and hog, and through any station molds in its way.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Trunk {
+    /// The fore-aft positions of the ends of the slot.
+    pub fore: f32,
+    pub aft: f32,
+    /// How wide the slot is, across the hull.
+    pub width: f32,
+    /// How high above base the top of the trunk is.
+    pub top: f32,
 }
 
 /// Configuration options.
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub resolution: usize,
+    /// The units of the data and planks sheets: "feet" or "mm".
+    #[serde(default)]
+    pub units: Units,
+    /// Whether to infer omitted measurements in the data sheet, instead
+    /// of ignoring them.
+    #[serde(default)]
+    pub fill_missing: bool,
+    /// How to draw curves through the measurements: "catmullrom" (the
+    /// default) or "bspline".
+    #[serde(default)]
+    pub spline: Option<Interpolation>,
+    /// How far the crown of the deck rises above the sheer, at the
+    /// widest station. Defaults to a flat deck.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub deck_camber: Option<Length>,
+    /// Or, how far the crown rises per foot of beam at the widest
+    /// station, like 0-0-2 for 1/4" per foot. `deck_camber` comes first
+    /// if they're both given.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub deck_camber_per_foot: Option<Length>,
+    /// How deep the deck beams are. Defaults to 1-1/2".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub deck_beam_moulding: Option<Length>,
+    /// The fore-aft position at which to split the deck into two panels,
+    /// for when it is longer than a sheet of plywood.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub deck_seam: Option<Length>,
+    /// How far from each end of a plank its lap tapers away to nothing,
+    /// so that the planks lie flush where they meet the stem and
+    /// transom. Defaults to no gains.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub gain_length: Option<Length>,
+    /// How far apart to space the rivets or screws that fasten each
+    /// plank's lap to the plank below it. Defaults to no fasteners.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub fastener_spacing: Option<Length>,
+    /// How far in from the bottom edge of each plank to place its
+    /// fasteners. Defaults to 3/8".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub fastener_inset: Option<Length>,
+    /// How thick the planks are, for rendering them as solids. Defaults
+    /// to 1/2".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub plank_thickness: Option<Length>,
+    /// How many alignment holes to put in each station template.
+    #[serde(default)]
+    pub hole_count: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub hole_diameter: Option<Length>,
+    /// Where the column of alignment holes goes, as a fraction of the way
+    /// across the area that all of the templates share.
+    #[serde(default)]
+    pub hole_across: Option<f32>,
+    /// Where the lowest and highest alignment holes go, as fractions of
+    /// the way up the area that all of the templates share.
+    #[serde(default)]
+    pub hole_low: Option<f32>,
+    #[serde(default)]
+    pub hole_high: Option<f32>,
+    /// How long the mounting tab on each station template is, as a
+    /// fraction of the template's width.
+    #[serde(default)]
+    pub tab_length: Option<f32>,
+    /// How high above base the mounting tabs are.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub tab_height: Option<Length>,
+    /// How high above base to mark the design waterline on the station
+    /// templates. It's left off if this is missing.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub design_waterline: Option<Length>,
+    /// How thick the sheets that the station molds and building jig are
+    /// cut from are. Defaults to 3/4".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub material_thickness: Option<Length>,
+    /// Whether to shrink the station templates by the plank thickness, so
+    /// they fit inside the planking.
+    #[serde(default)]
+    pub inside_planking: bool,
+    /// Whether to mark the station templates with the section at their
+    /// other face, to bevel their edges to.
+    #[serde(default)]
+    pub bevel_marks: bool,
+    /// How far the transom (the station named Transom) leans aft from
+    /// vertical, in degrees. Defaults to upright.
+    #[serde(default)]
+    pub transom_rake: Option<f32>,
+    /// The radius of the transom's curve, seen from above. Defaults to a
+    /// flat transom.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub transom_radius: Option<Length>,
+    /// How wide the stem is, across its face. If the data sheet gives the
+    /// stem's profile, its rabbet is lofted at half of this. Defaults to
+    /// 1-1/2".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub stem_siding: Option<Length>,
+    /// How deep the inner and outer stems are, fore and aft of the
+    /// rabbet. They default to 2-1/2" and 1".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub stem_moulding: Option<Length>,
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub outer_stem_moulding: Option<Length>,
+    /// How wide and deep the keel is, below the keel line. They default
+    /// to 2" and 1-1/2".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub keel_siding: Option<Length>,
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub keel_moulding: Option<Length>,
+    /// How wide and deep the hog is, above the keel line. They default to
+    /// 4" and 1".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub hog_siding: Option<Length>,
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub hog_moulding: Option<Length>,
+    /// How deep the sternpost is, forward of its rabbet. Defaults to
+    /// 2-1/2".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub post_moulding: Option<Length>,
+    /// The fore-aft position of the forward end of the centerboard or
+    /// daggerboard trunk's slot, and how long the slot is. There's no
+    /// trunk if they're missing.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub trunk_position: Option<Length>,
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub trunk_length: Option<Length>,
+    /// How wide the trunk's slot is. Defaults to 1".
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub trunk_width: Option<Length>,
+    /// How high above base the top of the trunk is, which the slots in
+    /// the station molds reach up to.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub trunk_top: Option<Length>,
+    /// How near two points can be before they're treated as the same
+    /// point. Defaults to 0.05 feet, which is too coarse for models.
+    #[serde(default, deserialize_with = "deserialize_opt_length")]
+    pub equality_threshold: Option<Length>,
+    /// Which colors to draw with: "standard" (the default), "print",
+    /// "contrast", or "stations".
+    #[serde(default)]
+    pub theme: Option<Theme>,
+    /// Which side of the centerline to draw each station on in the body
+    /// plan: "split" (the default), "traditional", or "full".
+    #[serde(default)]
+    pub body_plan: Option<BodyPlan>,
+    /// Whether to put a title block in the corner of each drawing, with
+    /// the boat's name, designer, and date below.
+    #[serde(default)]
+    pub title_block: bool,
+    /// The scale to save drawings at, like 0.0625 for 1:16. Defaults to
+    /// full size. The --scale option overrides it.
+    #[serde(default)]
+    pub drawing_scale: Option<f32>,
+    /// What units to give the size of saved svg files in: "px" (the
+    /// default), "mm", or "in".
+    #[serde(default)]
+    pub svg_units: Option<SvgUnits>,
+    /// How many pixels make an inch, when svg files are sized in pixels.
+    /// Defaults to 96, like Inkscape since version 0.92.
+    #[serde(default)]
+    pub pixels_per_inch: Option<f32>,
+    #[serde(rename = "Boat Name", default)]
+    pub boat_name: Option<String>,
+    #[serde(rename = "Architect Name", default)]
+    pub designer: Option<String>,
+    #[serde(rename = "Date", default)]
+    pub date: Option<String>,
 }
 
 /// A line along the hull of constant breadth.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub enum BreadthLine {
     Sheer,
     Wale,
-    ButOut(Feet),
+    ButOut(Length),
+}
+
+/// A plane that slants down and outboard through the hull, seen as a
+/// straight line in the body plan. It crosses the centerline at `height`,
+/// and reaches the base at `breadth` out from the centerline.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct DiagonalLine {
+    pub height: Length,
+    pub breadth: Length,
 }
 
 /// A line along the hull of constant height.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub enum HeightLine {
     Sheer,
-    WLUp(Feet),
+    WLUp(Length),
+}
+
+impl DiagonalLine {
+    /// The (breadth, height) of the point that is `distance` along this
+    /// diagonal from where it crosses the centerline.
+    pub fn point_at(&self, distance: f32) -> (f32, f32) {
+        let height: f32 = self.height.into();
+        let breadth: f32 = self.breadth.into();
+        let length = (height * height + breadth * breadth).sqrt();
+        (
+            distance * breadth / length,
+            height - distance * height / length,
+        )
+    }
+}
+
+impl Default for Backbone {
+    fn default() -> Backbone {
+        Backbone {
+            keel_siding: 2. / 12.,
+            keel_moulding: 1.5 / 12.,
+            hog_siding: 4. / 12.,
+            hog_moulding: 1. / 12.,
+            post_moulding: 2.5 / 12.,
+        }
+    }
+}
+
+impl Data {
+    /// The stem's rabbet from its profile, as (fore-aft position, height)
+    /// points in feet, from the heel up to the sheer. It's empty if the
+    /// profile wasn't given.
+    pub fn stem_rabbet(&self) -> Result<Vec<(f32, f32)>, LapstrakeError> {
+        let mut rabbet: Vec<(f32, f32)> = self
+            .stem_profile
+            .iter()
+            .map(|&(height, position)| (position.into(), height.into()))
+            .collect();
+        sort_by_number(&mut rabbet, |&(_, height)| height)
+            .context("The stem's profile has a height that isn't a number")?;
+        Ok(rabbet)
+    }
 }
 
 impl Spec {
+    /// The transom, if the data sheet has a station named Transom.
+    pub fn transom(&self) -> Result<Option<Transom>, LapstrakeError> {
+        let configured = self.config.transom_rake.is_some()
+            || self.config.transom_radius.is_some();
+        if !self.data.stations.iter().any(|name| name == TRANSOM) {
+            if configured {
+                return Err(LapstrakeError::load(
+                    "The config sheet describes a transom, but the data \
+                     sheet has no station named Transom.",
+                ));
+            }
+            return Ok(None);
+        }
+        Ok(Some(Transom {
+            rake: self.config.transom_rake.unwrap_or(0.),
+            radius: self.config.transom_radius.map(|r| r.into()),
+        }))
+    }
+
+    /// The stem, if the data sheet has a station named Stem or gives the
+    /// stem's profile.
+    pub fn stem(&self) -> Option<Stem> {
+        if !self.data.stations.iter().any(|name| name == STEM) {
+            return None;
+        }
+        Some(Stem {
+            siding: self
+                .config
+                .stem_siding
+                .map_or(DEFAULT_STEM_SIDING, |s| s.into()),
+            moulding: self
+                .config
+                .stem_moulding
+                .map_or(DEFAULT_STEM_MOULDING, |m| m.into()),
+            outer_moulding: self
+                .config
+                .outer_stem_moulding
+                .map_or(DEFAULT_OUTER_STEM_MOULDING, |m| m.into()),
+        })
+    }
+
+    /// The centerboard or daggerboard trunk, if the config sheet places
+    /// one.
+    pub fn trunk(&self) -> Result<Option<Trunk>, LapstrakeError> {
+        let config = &self.config;
+        let fore: f32 = match config.trunk_position {
+            Some(position) => position.into(),
+            None => return Ok(None),
+        };
+        let (length, top): (f32, f32) =
+            match (config.trunk_length, config.trunk_top) {
+                (Some(length), Some(top)) => (length.into(), top.into()),
+                _ => {
+                    return Err(LapstrakeError::load(
+                        "The config sheet gives a trunk_position, but the \
+                         trunk also needs a trunk_length and trunk_top.",
+                    ))
+                }
+            };
+        Ok(Some(Trunk {
+            fore: fore,
+            aft: fore + length,
+            width: config.trunk_width.map_or(1. / 12., |w| w.into()),
+            top: top,
+        }))
+    }
+
+    /// The size of the backbone's timbers.
+    pub fn backbone(&self) -> Backbone {
+        let default = Backbone::default();
+        let or = |size: Option<Length>, default: f32| {
+            size.map_or(default, |size| size.into())
+        };
+        let config = &self.config;
+        Backbone {
+            keel_siding: or(config.keel_siding, default.keel_siding),
+            keel_moulding: or(config.keel_moulding, default.keel_moulding),
+            hog_siding: or(config.hog_siding, default.hog_siding),
+            hog_moulding: or(config.hog_moulding, default.hog_moulding),
+            post_moulding: or(config.post_moulding, default.post_moulding),
+        }
+    }
+
     /// Get the position of the nth station.
     /// (This is by index, not by name.)
     pub fn get_station_position(
         &self,
         station: usize,
         line: HeightLine,
-    ) -> Result<Feet, LapstrakeError> {
+    ) -> Result<Length, LapstrakeError> {
         Spec::lookup(&self.data.positions, station, line)
     }
 
@@ -90,7 +508,7 @@ impl Spec {
     pub fn get_sheer_breadth(
         &self,
         station: usize,
-    ) -> Result<Feet, LapstrakeError> {
+    ) -> Result<Length, LapstrakeError> {
         Spec::lookup(&self.data.breadths, station, HeightLine::Sheer)
     }
 
@@ -98,7 +516,7 @@ impl Spec {
     pub fn get_sheer_height(
         &self,
         station: usize,
-    ) -> Result<Feet, LapstrakeError> {
+    ) -> Result<Length, LapstrakeError> {
         Spec::lookup(&self.data.heights, station, BreadthLine::Sheer)
     }
 
@@ -106,9 +524,9 @@ impl Spec {
         rows: &Vec<DataRow<M>>,
         station_index: usize,
         measurement: M,
-    ) -> Result<Feet, LapstrakeError>
+    ) -> Result<Length, LapstrakeError>
     where
-        M: fmt::Debug + cmp::Eq + Copy,
+        M: fmt::Debug + PartialEq + Copy,
     {
         for &(ref x, ref row) in rows.iter() {
             if *x == measurement {
@@ -124,3 +542,39 @@ impl Spec {
         )))
     }
 }
+
+impl fmt::Display for PlankStation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlankStation::Station(ref name) => write!(f, "{}", name),
+            PlankStation::Position(posn) => write!(f, "{}", posn),
+        }
+    }
+}
+
+// Lines are displayed the way they're written in the data sheet.
+
+impl fmt::Display for HeightLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeightLine::Sheer => write!(f, "Sheer"),
+            HeightLine::WLUp(height) => write!(f, "{:?}", height),
+        }
+    }
+}
+
+impl fmt::Display for BreadthLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BreadthLine::Sheer => write!(f, "Sheer"),
+            BreadthLine::Wale => write!(f, "Wale"),
+            BreadthLine::ButOut(breadth) => write!(f, "{:?}", breadth),
+        }
+    }
+}
+
+impl fmt::Display for DiagonalLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} / {:?}", self.height, self.breadth)
+    }
+}
@@ -1,49 +1,251 @@
 //! A spline with any number of points.
 //!
-//! Implemented with the centripetal Catmull-Rom algorithm.
+//! Implemented with the centripetal Catmull-Rom algorithm by default, or
+//! with interpolating cubic B-splines.
 
-use scad_dots::utils::{Axis, P3};
-use util::{practically_zero, remove_duplicates};
+use scad_dots::utils::{distance, Axis, P2, P3, V2};
+use util::{
+    is_finite, practically_zero, reflect3, remove_duplicates,
+    segment_intersection, EQUALITY_THRESHOLD,
+};
 
+use bspline;
 use catmullrom::CentripetalCatmullRom;
-use catmullrom::Segment::{First, Last, Middle};
 use error::LapstrakeError;
-use util::project;
+use util::{project, project_points};
+
+/// The fewest distinct points that a spline can be made through.
+pub(crate) const MIN_SPLINE_POINTS: usize = 4;
 
 /// A spline with any number of points.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Spline {
     points: Vec<P3>,
+    // The length from the start of the spline to each of its points,
+    // measured in the y-z plane, so that finding a point by its length
+    // is a binary search.
+    lengths: Vec<f32>,
+}
+
+/// How to draw a smooth curve through the reference points of a spline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Centripetal Catmull-Rom, which only looks at the nearest points,
+    /// so it follows the reference points closely.
+    #[serde(rename = "catmullrom")]
+    CatmullRom,
+    /// An interpolating cubic B-spline, which has continuous curvature,
+    /// so it can loft noticeably fairer.
+    #[serde(rename = "bspline")]
+    BSpline,
+}
+
+impl Default for Interpolation {
+    fn default() -> Interpolation {
+        Interpolation::CatmullRom
+    }
+}
+
+/// How a Catmull-Rom spline behaves at its ends. Each segment of a
+/// Catmull-Rom spline is steered by the points on either side of it, so
+/// the end segments are steered by a phantom point beyond each end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndCondition {
+    /// Place the phantom point by reflecting the second point through
+    /// the end point, so the spline straightens out towards its ends,
+    /// like a batten that's free at its ends.
+    Reflected,
+    /// Place the phantom point on the parabola through the last three
+    /// points, so the spline keeps bending the same way to its ends.
+    Extrapolated,
+}
+
+impl Default for EndCondition {
+    fn default() -> EndCondition {
+        EndCondition::Extrapolated
+    }
+}
+
+impl EndCondition {
+    // The phantom point beyond `end`, whose neighbors are `next` and then
+    // `after`.
+    fn phantom(&self, end: P3, next: P3, after: P3) -> P3 {
+        let reflected = end + (end - next);
+        match *self {
+            EndCondition::Reflected => reflected,
+            EndCondition::Extrapolated => {
+                let extrapolated = after + (end - next) * 3.;
+                // A point on top of the end would make a degenerate
+                // segment.
+                if practically_zero(distance(&extrapolated, &end)) {
+                    reflected
+                } else {
+                    extrapolated
+                }
+            }
+        }
+    }
+}
+
+/// The curvature of a spline at one of its sample points, in the plane
+/// that it was projected onto.
+#[derive(Debug, Clone, Copy)]
+pub struct Curvature {
+    pub point: P2,
+    /// The unit normal, to the left of the direction of travel.
+    pub normal: V2,
+    /// One over the radius of curvature, in 1/feet. It's positive where
+    /// the spline bends towards the normal.
+    pub curvature: f32,
 }
 
 impl Spline {
+    /// Make a centripetal Catmull-Rom spline through the points.
     pub fn new(
         ref_points: Vec<P3>,
         resolution: usize,
     ) -> Result<Spline, LapstrakeError> {
-        let ref_points = remove_duplicates(ref_points);
+        Spline::with_interpolation(
+            ref_points,
+            resolution,
+            Interpolation::CatmullRom,
+        )
+    }
+
+    /// Make a spline through the points, using the given kind of
+    /// interpolation. It's sampled `resolution` times between each pair
+    /// of neighboring points.
+    pub fn with_interpolation(
+        ref_points: Vec<P3>,
+        resolution: usize,
+        interpolation: Interpolation,
+    ) -> Result<Spline, LapstrakeError> {
+        Spline::with_options(
+            ref_points,
+            resolution,
+            interpolation,
+            EndCondition::default(),
+            EQUALITY_THRESHOLD,
+        )
+    }
+
+    /// Like `with_interpolation`, but also choosing how a Catmull-Rom
+    /// spline behaves at its ends, and how near neighboring reference
+    /// points can be before they're treated as duplicates and one is
+    /// dropped. B-splines always straighten out towards their ends.
+    pub fn with_options(
+        ref_points: Vec<P3>,
+        resolution: usize,
+        interpolation: Interpolation,
+        end_condition: EndCondition,
+        threshold: f32,
+    ) -> Result<Spline, LapstrakeError> {
+        check_points(&ref_points)?;
+        let ref_points = remove_duplicates(ref_points, threshold);
         let n = ref_points.len();
-        if n < 4 {
-            return Err(LapstrakeError::Spline
-                .context("Splines must have at least 4 points"));
+        if n < MIN_SPLINE_POINTS {
+            return Err(LapstrakeError::Spline.context(&format!(
+                "Splines must have at least {} distinct points, but this one \
+                 has {}",
+                MIN_SPLINE_POINTS, n
+            )));
         }
+        if interpolation == Interpolation::BSpline {
+            return Ok(Spline::from_points(bspline::sample(
+                &ref_points,
+                resolution,
+            )));
+        }
+        // Pad the points with a phantom point at each end, so that every
+        // segment is the middle segment of its Catmull-Rom curve.
+        let mut padded = vec![end_condition.phantom(
+            ref_points[0],
+            ref_points[1],
+            ref_points[2],
+        )];
+        padded.extend(ref_points.iter().cloned());
+        padded.push(end_condition.phantom(
+            ref_points[n - 1],
+            ref_points[n - 2],
+            ref_points[n - 3],
+        ));
         let mut points: Vec<P3> = vec![];
-        for i in 0..n - 3 {
+        for i in 0..n - 1 {
             let catmull = CentripetalCatmullRom::new([
-                ref_points[i],
-                ref_points[i + 1],
-                ref_points[i + 2],
-                ref_points[i + 3],
+                padded[i],
+                padded[i + 1],
+                padded[i + 2],
+                padded[i + 3],
             ]);
-            if i == 0 {
-                points.extend(catmull.sample(First, resolution, false));
-            }
-            points.extend(catmull.sample(Middle, resolution, false));
-            if i == n - 4 {
-                points.extend(catmull.sample(Last, resolution, true));
-            }
+            points.extend(catmull.sample(resolution, i == n - 2));
+        }
+        Ok(Spline::from_points(points))
+    }
+
+    /// Make a spline through the points that has a sharp corner at each
+    /// of the points with the given indices, by joining up a separate
+    /// spline between each pair of corners. The first and last points
+    /// can't be corners. Points nearer than `threshold` to their
+    /// neighbors are treated as duplicates.
+    pub fn with_knuckles(
+        ref_points: Vec<P3>,
+        resolution: usize,
+        interpolation: Interpolation,
+        threshold: f32,
+        knuckles: &[usize],
+    ) -> Result<Spline, LapstrakeError> {
+        let end_condition = EndCondition::default();
+        if knuckles.is_empty() {
+            return Spline::with_options(
+                ref_points,
+                resolution,
+                interpolation,
+                end_condition,
+                threshold,
+            );
+        }
+        check_points(&ref_points)?;
+        if ref_points.len() < 2 {
+            return Err(LapstrakeError::Spline
+                .context("Splines with knuckles must have at least 2 points"));
+        }
+        let mut ends = vec![0];
+        ends.extend(knuckles.iter().cloned());
+        ends.push(ref_points.len() - 1);
+        ends.sort();
+        ends.dedup();
+        let mut points: Vec<P3> = vec![];
+        for pair in ends.windows(2) {
+            let piece = ref_points[pair[0]..pair[1] + 1].to_vec();
+            // Pieces too short for the usual interpolation are joined
+            // with a B-spline, which is straight through two points.
+            let samples = if piece.len() >= 4 {
+                Spline::with_options(
+                    piece,
+                    resolution,
+                    interpolation,
+                    end_condition,
+                    threshold,
+                )?.points
+            } else {
+                bspline::sample(&piece, resolution)
+            };
+            // Neighboring pieces share their corner point.
+            let skip = if points.is_empty() { 0 } else { 1 };
+            points.extend(samples.into_iter().skip(skip));
+        }
+        Ok(Spline::from_points(points))
+    }
+
+    // Wrap up the sampled points.
+    fn from_points(points: Vec<P3>) -> Spline {
+        let lengths = cumulative_lengths(&points, |a, b| {
+            projected_distance(Axis::X, a, b)
+        });
+        Spline {
+            points: points,
+            lengths: lengths,
         }
-        Ok(Spline { points: points })
     }
 
     /// A sample of points along the spline, at the resolution given
@@ -65,40 +267,50 @@ impl Spline {
         })
     }
 
-    /// The total length of the spline.
-    pub fn length(&self) -> f32 {
-        let mut length = 0.0;
-        let mut prev_point = self.points[0];
-        for &point in &self.points[1..] {
-            length += projected_distance(Axis::X, point, prev_point);
-            prev_point = point;
+    /// Mirror the spline across the plane normal to `axis`. Reflecting
+    /// doesn't change any distances, so the lengths carry over.
+    pub fn reflect(&self, axis: Axis) -> Spline {
+        Spline {
+            points: reflect3(axis, &self.points),
+            lengths: self.lengths.clone(),
+        }
+    }
+
+    /// Blend this spline point by point with another one, going a
+    /// fraction `t` of the way towards it. They can only be blended if
+    /// they were sampled the same number of times.
+    pub(crate) fn blend(&self, other: &Spline, t: f32) -> Option<Spline> {
+        if self.points.len() != other.points.len() {
+            return None;
         }
-        length
+        let points = self
+            .points
+            .iter()
+            .zip(&other.points)
+            .map(|(&p, &q)| linear_interpolate(t, p, q))
+            .collect();
+        Some(Spline::from_points(points))
+    }
+
+    /// The total length of the spline, measured in the y-z plane.
+    pub fn length(&self) -> f32 {
+        self.lengths.last().cloned().unwrap_or(0.)
     }
 
     /// Get the point at a given distance along the curve from the
-    /// start of the spline.
+    /// start of the spline, measured in the y-z plane.
     pub fn at_len(&self, desired_length: f32) -> Result<P3, LapstrakeError> {
-        let mut length = 0.0;
-        let mut prev_point = self.points[0];
-        for &point in &self.points[1..] {
-            let delta = projected_distance(Axis::X, point, prev_point);
-            if length + delta >= desired_length {
-                // We are between prev_point and point. Linearly interpolate.
-                // The projection throws this off a bit, but it shouldn't matter.
-                if practically_zero(delta) {
-                    return Ok(prev_point);
-                } else {
-                    let t = (desired_length - length) / delta;
-                    return Ok(linear_interpolate(t, prev_point, point));
-                }
-            } else {
-                length += delta;
-                prev_point = point;
-            }
+        let length = self.length();
+        if desired_length > length
+            && !practically_zero(desired_length - length)
+        {
+            return Err(
+                LapstrakeError::Spline.context("Fell off the end of a spline!")
+            );
         }
-        // We shouldn't ever get here.
-        Err(LapstrakeError::Spline.context("Fell off the end of a spline!"))
+        // Linearly interpolate between the points on either side. The
+        // projection throws this off a bit, but it shouldn't matter.
+        Ok(self.at_cumulative_length(&self.lengths, desired_length))
     }
 
     /// Get the point at a given fraction along the curve.
@@ -107,19 +319,140 @@ impl Spline {
         self.at_len(t * len)
     }
 
-    /// Get the point at a given x coordinate (a.k.a. position).
+    /// Get `n` points spaced evenly along the spline by arc length,
+    /// including both of its ends. Unlike `sample()`, whose spacing
+    /// depends on the spacing of the reference points, and `at_len()`,
+    /// which measures length in the y-z plane, this measures true 3d
+    /// distance.
+    pub fn resample_even(&self, n: usize) -> Result<Vec<P3>, LapstrakeError> {
+        if n < 2 {
+            return Err(LapstrakeError::Spline
+                .context("Must resample a spline with at least 2 points"));
+        }
+        let table = cumulative_lengths(&self.points, |a, b| distance(&a, &b));
+        let total = table[table.len() - 1];
+        Ok((0..n)
+            .map(|i| {
+                let target = total * i as f32 / (n - 1) as f32;
+                self.at_cumulative_length(&table, target)
+            }).collect())
+    }
+
+    // Look up the point at a given length in a table of cumulative
+    // lengths, interpolating between points.
+    fn at_cumulative_length(&self, table: &[f32], length: f32) -> P3 {
+        let i = match table
+            .binary_search_by(|len| len.partial_cmp(&length).expect("NaN"))
+        {
+            Ok(i) => return self.points[i],
+            Err(i) => i,
+        };
+        if i == 0 {
+            return self.points[0];
+        }
+        if i >= table.len() {
+            return self.points[self.points.len() - 1];
+        }
+        let delta = table[i] - table[i - 1];
+        if practically_zero(delta) {
+            return self.points[i];
+        }
+        let t = (length - table[i - 1]) / delta;
+        linear_interpolate(t, self.points[i - 1], self.points[i])
+    }
+
+    /// Get the point at a given x coordinate (a.k.a. position),
+    /// interpolating between the samples on either side of it. The
+    /// samples must be in order of increasing x. It's an error if the
+    /// spline doesn't reach that x coordinate.
     pub fn at_x(&self, desired_x: f32) -> Result<P3, LapstrakeError> {
+        if desired_x.is_nan() {
+            return Err(LapstrakeError::Spline
+                .context("Can't find the point at a position that's NaN"));
+        }
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(LapstrakeError::Spline.context("Empty spline")),
+        };
+        if practically_zero(desired_x - first.x) {
+            return Ok(first);
+        }
+        if practically_zero(desired_x - last.x) {
+            return Ok(last);
+        }
+        if desired_x < first.x || desired_x > last.x {
+            return Err(LapstrakeError::Spline.context(&format!(
+                "Position {} is outside of the spline, which runs from {} \
+                 to {}",
+                desired_x, first.x, last.x
+            )));
+        }
         let result = self.points.binary_search_by(|pt| {
             pt.x.partial_cmp(&desired_x).expect("Not a number!")
         });
         let i = match result {
-            Ok(i) => i,
+            Ok(i) => return Ok(self.points[i]),
             Err(i) => i,
         };
-        Ok(*self
-            .points
-            .get(i)
-            .expect(&format!("Could not get point at position {}", desired_x)))
+        // The desired x is strictly between the ends, so there are
+        // samples on both sides of it.
+        let (before, after) = (self.points[i - 1], self.points[i]);
+        let f = (desired_x - before.x) / (after.x - before.x);
+        Ok(before + (after - before) * f)
+    }
+
+    /// The curvature at each of the spline's sample points except its
+    /// ends, after projecting it along the given axis. Plotting this shows
+    /// humps and hollows that are hard to see in the spline itself.
+    pub fn curvature_profile(&self, axis: Axis) -> Vec<Curvature> {
+        let points = project_points(axis, &self.points);
+        let mut profile = vec![];
+        for triple in points.windows(3) {
+            let (a, b, c) = (triple[0], triple[1], triple[2]);
+            let chord = c - a;
+            let lengths = (b - a).norm() * (c - b).norm() * chord.norm();
+            if practically_zero(lengths) {
+                continue;
+            }
+            // The curvature of the circle through all three points.
+            let cross = (b - a).x * (c - b).y - (b - a).y * (c - b).x;
+            profile.push(Curvature {
+                point: b,
+                normal: V2::new(-chord.y, chord.x) / chord.norm(),
+                curvature: 2. * cross / lengths,
+            });
+        }
+        profile
+    }
+
+    /// Whether the spline reaches the given x coordinate.
+    pub fn spans_x(&self, x: f32) -> bool {
+        self.points.iter().any(|p| p.x <= x)
+            && self.points.iter().any(|p| p.x >= x)
+    }
+}
+
+impl Spline {
+    /// Find all the places where the spline crosses over itself, when
+    /// projected along the given axis.
+    pub fn self_intersections(&self, axis: Axis) -> Vec<P2> {
+        let points: Vec<P2> =
+            self.points.iter().map(|&p| project(axis, p)).collect();
+        let mut crossings = vec![];
+        for i in 0..points.len().saturating_sub(1) {
+            // Neighboring segments always touch, so skip them.
+            for j in i + 2..points.len() - 1 {
+                if let Some(crossing) = segment_intersection(
+                    points[i],
+                    points[i + 1],
+                    points[j],
+                    points[j + 1],
+                ) {
+                    crossings.push(crossing);
+                }
+            }
+        }
+        crossings
     }
 }
 
@@ -127,7 +460,89 @@ fn linear_interpolate(t: f32, pt1: P3, pt2: P3) -> P3 {
     P3::from_coordinates((1.0 - t) * pt1.coords + t * pt2.coords)
 }
 
+// Make sure the reference points can be splined: they must all be
+// numbers.
+fn check_points(points: &[P3]) -> Result<(), LapstrakeError> {
+    match points.iter().find(|p| !is_finite(p)) {
+        Some(point) => Err(LapstrakeError::Spline.context(&format!(
+            "Spline has a point that isn't a number: {:?}",
+            point
+        ))),
+        None => Ok(()),
+    }
+}
+
+// The length from the first point to each point, adding up the given
+// distance between neighbors.
+fn cumulative_lengths<F>(points: &[P3], measure: F) -> Vec<f32>
+where
+    F: Fn(P3, P3) -> f32,
+{
+    let mut table = Vec::with_capacity(points.len());
+    let mut length = 0.0;
+    table.push(length);
+    for pair in points.windows(2) {
+        length += measure(pair[0], pair[1]);
+        table.push(length);
+    }
+    table
+}
+
 fn projected_distance(axis: Axis, point_a: P3, point_b: P3) -> f32 {
     let v = project(axis, point_b) - project(axis, point_a);
     (v.x.powf(2.) + v.y.powf(2.)).sqrt()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_even() {
+        let spline = Spline::new(
+            vec![
+                P3::new(0., 0., 0.),
+                P3::new(1., 0., 0.),
+                P3::new(3., 0., 0.),
+                P3::new(8., 0., 0.),
+            ],
+            10,
+        ).unwrap();
+        let points = spline.resample_even(5).unwrap();
+        assert_eq!(points.len(), 5);
+        for (i, point) in points.iter().enumerate() {
+            assert!((point.x - 2. * i as f32).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_at_x() {
+        let spline = Spline::new(
+            vec![
+                P3::new(0., 0., 0.),
+                P3::new(1., 2., 0.),
+                P3::new(3., 6., 0.),
+                P3::new(8., 16., 0.),
+            ],
+            3,
+        ).unwrap();
+        let point = spline.at_x(2.5).unwrap();
+        assert!((point.y - 5.).abs() < 0.001);
+        assert!(spline.at_x(8.).is_ok());
+        assert!(spline.at_x(9.).is_err());
+        assert!(spline.at_x(-1.).is_err());
+    }
+
+    #[test]
+    fn test_bad_points() {
+        let mut points = vec![
+            P3::new(0., 0., 0.),
+            P3::new(1., 2., 0.),
+            P3::new(3., 6., 0.),
+        ];
+        assert!(Spline::new(points.clone(), 3).is_err());
+        assert!(Spline::new(vec![], 3).is_err());
+        points.push(P3::new(::std::f32::NAN, 0., 0.));
+        assert!(Spline::new(points, 3).is_err());
+    }
+}
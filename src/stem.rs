@@ -0,0 +1,196 @@
+//! The stem: the timber at the bow that the planks end on. It's built in
+//! two parts, with the planks fastened to the inner stem and the outer
+//! stem glued on over their ends, so its templates are drawn in profile
+//! on either side of the rabbet line between them.
+
+use scad_dots::utils::{Axis, P2, V2};
+
+use draw::{Drawing, Layer};
+use error::LapstrakeError;
+use hull::{Hull, Station};
+use render_2d::{
+    Align, Baseline, Bounded, PathStyle2, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use spec::{PlankStation, Stem, STEM};
+use util::{offset_polyline, practically_zero, project, project_points};
+
+/// How many longitudinal lines to measure the bearding line along.
+const BEARDING_SPACES: usize = 20;
+
+/// How far along the hull's lines to look, as a fraction of their length,
+/// to find which way they leave the stem.
+const BEARDING_STEP: f32 = 0.01;
+
+impl Hull {
+    /// The stem's bearding line in profile, as (fore-aft, height) points
+    /// from the heel up: where the inside of the planking meets the side
+    /// of the stem. It's aft of the rabbet by however much the plank's
+    /// thickness is stretched out where the hull meets the stem at a
+    /// slant, but never past the back of the inner stem.
+    pub fn bearding_line(&self) -> Result<Vec<P2>, LapstrakeError> {
+        let (station, stem) = self.stem_station()?;
+        let mut bearding = vec![];
+        for i in 0..BEARDING_SPACES + 1 {
+            let t = i as f32 / BEARDING_SPACES as f32;
+            let rabbet = station.at_t(t)?;
+            // The line along the hull from this point on the rabbet,
+            // starting from whichever of its ends is on the stem.
+            let line = self.get_line(t)?;
+            let (end, next) = if (line.at_t(0.)? - rabbet).norm()
+                <= (line.at_t(1.)? - rabbet).norm()
+            {
+                (line.at_t(0.)?, line.at_t(BEARDING_STEP)?)
+            } else {
+                (line.at_t(1.)?, line.at_t(1. - BEARDING_STEP)?)
+            };
+            let up = station.at_t((t + BEARDING_STEP).min(1.))?
+                - station.at_t((t - BEARDING_STEP).max(0.))?;
+            let along = next - end;
+            let surface = up.cross(&along);
+            if practically_zero(surface.norm() / (up.norm() * along.norm()))
+                || practically_zero(up.x.hypot(up.z))
+            {
+                continue;
+            }
+            // How steeply the hull meets the side of the stem.
+            let normal = surface / surface.norm();
+            let slant = normal.x.hypot(normal.z);
+            let depth = if practically_zero(slant) {
+                stem.moulding
+            } else {
+                (self.plank_thickness / slant).min(stem.moulding)
+            };
+            // Turned clockwise, into the hull, since the rabbet runs up
+            // from the heel.
+            let inward = V2::new(up.z, -up.x) / up.x.hypot(up.z);
+            bearding.push(project(Axis::Y, rabbet) + inward * depth);
+        }
+        Ok(bearding)
+    }
+
+    /// Draw templates for the inner and outer stems in profile, with the
+    /// rabbet and bearding lines, and where each plank's edges land, marked
+    /// on the inner stem.
+    pub fn draw_stem(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.1;
+        let palette = self.theme.palette();
+        let (station, stem) = self.stem_station()?;
+        // Flip heights, so that the templates are right side up.
+        let flip = |points: &[P2]| -> Vec<P2> {
+            points.iter().map(|p| P2::new(p.x, -p.y)).collect()
+        };
+        let rabbet = project_points(Axis::Y, &station.spline.sample(None)?);
+        let back = offset_polyline(&rabbet, -stem.moulding);
+        let face = offset_polyline(&rabbet, stem.outer_moulding);
+
+        let mut inner = flip(&rabbet);
+        inner.extend(flip(&back).into_iter().rev());
+        let inner = SvgPath::new(inner)
+            .stroke(palette.outline, STROKE)
+            .style(PathStyle2::Line)
+            .close();
+        let mut outer = flip(&face);
+        outer.extend(flip(&rabbet).into_iter().rev());
+        let outer = SvgPath::new(outer)
+            .stroke(palette.outline, STROKE)
+            .style(PathStyle2::Line)
+            .close();
+        let inner_bound = inner.bound().expect("inner stem has no bound");
+        let outer_bound = outer.bound().expect("outer stem has no bound");
+
+        let mut marks = SvgGroup::new();
+        let mut labels = SvgGroup::new();
+        marks.append(
+            SvgPath::new(flip(&self.bearding_line()?))
+                .stroke(palette.grid, STROKE / 2.)
+                .style(PathStyle2::Dashed),
+        );
+        // Tick the plank edges where they land on the rabbet.
+        let column = self.planks.stations.iter().position(|s| match *s {
+            PlankStation::Station(ref name) => name == STEM,
+            PlankStation::Position(_) => false,
+        });
+        if let Some(column) = column {
+            for (i, row) in self.planks.plank_locations.iter().enumerate() {
+                let t = match row.get(column) {
+                    Some(&Some(t)) => t,
+                    _ => continue,
+                };
+                let at = |t: f32| {
+                    station.at_t(t.max(0.).min(1.)).map(|p| project(Axis::Y, p))
+                };
+                let up = at(t + BEARDING_STEP)? - at(t - BEARDING_STEP)?;
+                if practically_zero(up.norm()) {
+                    continue;
+                }
+                let inward = V2::new(up.y, -up.x) / up.norm();
+                let edge = at(t)?;
+                let tick = vec![edge, edge + inward * FONT_SIZE];
+                marks.append(
+                    SvgPath::new(flip(&tick))
+                        .stroke(palette.grid, STROKE / 2.)
+                        .style(PathStyle2::Line),
+                );
+                let label = edge + inward * FONT_SIZE * 1.5;
+                labels.append(
+                    SvgText {
+                        lines: vec![format!("{}", i / 2 + 1)],
+                        pos: P2::new(label.x, -label.y),
+                        color: palette.label,
+                        size: FONT_SIZE / 2.,
+                        style: TextStyle::default(),
+                    }.anchored(Align::Middle, Baseline::Central),
+                );
+            }
+        }
+        let sided = self.units.from_feet(stem.siding);
+        // Each is labeled on its own side, clear of the other.
+        let gap = V2::new(FONT_SIZE, 0.);
+        labels.append(
+            SvgText {
+                lines: vec!["Inner stem".into(), format!("Sided {}", sided)],
+                pos: inner_bound.relative_pos(1., 0.5) + gap,
+                color: palette.label,
+                size: FONT_SIZE,
+                style: TextStyle::default(),
+            }.anchored(Align::Start, Baseline::Central),
+        );
+        labels.append(
+            SvgText {
+                lines: vec!["Outer stem".into(), format!("Sided {}", sided)],
+                pos: outer_bound.relative_pos(0., 0.5) - gap,
+                color: palette.label,
+                size: FONT_SIZE,
+                style: TextStyle::default(),
+            }.anchored(Align::End, Baseline::Central),
+        );
+
+        // The outer stem is drawn where it goes, forward of the inner
+        // stem, so the two share the rabbet line.
+        let mut pattern = SvgGroup::new();
+        pattern.append(Layer::Stations.wrap(inner));
+        pattern.append(Layer::Stations.wrap(outer));
+        pattern.append(marks.layer(Layer::Marks.name()));
+        pattern.append(labels.layer(Layer::Labels.name()));
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![self.scale_bar()?, pattern],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::Stem, doc)
+    }
+
+    // The stem station, and the size of the stem.
+    fn stem_station(&self) -> Result<(&Station, Stem), LapstrakeError> {
+        let stem = self.stem.ok_or_else(|| {
+            LapstrakeError::General(
+                "The data sheet has no station named Stem, or profile for \
+                 it."
+                    .into(),
+            )
+        })?;
+        Ok((self.get_station(STEM)?, stem))
+    }
+}
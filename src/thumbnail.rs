@@ -0,0 +1,146 @@
+//! Rasterize drawings into small png thumbnails, so that output folders
+//! can be browsed visually. Enabled with the `thumbnails` feature.
+//!
+//! This is a very simple rasterizer: it draws lines and circles one
+//! pixel wide, and leaves out text entirely.
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+use scad_dots::utils::{P2, V2};
+
+use error::{LapstrakeError, ResultExt};
+use render_2d::{
+    Bound, Bounded, Canvas, Stroke, SvgColor, SvgDoc, TextStyle,
+};
+
+impl SvgDoc {
+    /// Save a png thumbnail of the document, scaled so that its longest
+    /// side is `size` pixels.
+    pub fn save_thumbnail(
+        &self,
+        filename: &Path,
+        size: u32,
+    ) -> Result<(), LapstrakeError> {
+        println!("Saving thumbnail: {:?}.", filename);
+        let bound = self.bound().ok_or_else(|| {
+            LapstrakeError::Draw.context("Cannot make thumbnail of empty doc")
+        })?;
+        let mut thumbnail = Thumbnail::new(bound, size);
+        self.render_to(&mut thumbnail);
+        thumbnail
+            .image
+            .save(filename)
+            .map_err(|err| LapstrakeError::General(err.to_string()))
+            .with_context(|| format!("Could not save {:?}", filename))
+    }
+}
+
+struct Thumbnail {
+    image: RgbImage,
+    // The position in feet of the image's top left corner.
+    origin: P2,
+    // Pixels per foot.
+    scale: f32,
+    current_path: Vec<P2>,
+}
+
+impl Thumbnail {
+    fn new(bound: Bound, size: u32) -> Thumbnail {
+        let scale = size as f32 / bound.width().max(bound.height());
+        let width = (bound.width() * scale).ceil().max(1.) as u32;
+        let height = (bound.height() * scale).ceil().max(1.) as u32;
+        Thumbnail {
+            image: RgbImage::from_pixel(width, height, Rgb([255, 255, 255])),
+            origin: bound.low,
+            scale: scale,
+            current_path: vec![],
+        }
+    }
+
+    fn to_pixels(&self, pos: P2) -> V2 {
+        (pos - self.origin) * self.scale
+    }
+
+    fn plot(&mut self, pixel: V2, color: SvgColor) {
+        let (x, y) = (pixel.x.round(), pixel.y.round());
+        if x < 0. || y < 0. {
+            return;
+        }
+        let (x, y) = (x as u32, y as u32);
+        if x < self.image.width() && y < self.image.height() {
+            let (r, g, b) = color.rgb();
+            self.image.put_pixel(x, y, Rgb([r, g, b]));
+        }
+    }
+
+    fn line(&mut self, start: P2, end: P2, color: SvgColor) {
+        let (start, end) = (self.to_pixels(start), self.to_pixels(end));
+        let steps = (end - start).x.abs().max((end - start).y.abs()).ceil();
+        let steps = steps.max(1.) as usize;
+        for i in 0..steps + 1 {
+            let t = i as f32 / steps as f32;
+            self.plot(start + (end - start) * t, color);
+        }
+    }
+}
+
+impl Canvas for Thumbnail {
+    fn move_to(&mut self, pos: P2) {
+        self.current_path = vec![pos];
+    }
+
+    fn line_to(&mut self, pos: P2) {
+        self.current_path.push(pos);
+    }
+
+    fn end_path(&mut self, closed: bool, stroke: Stroke) {
+        let mut points = ::std::mem::replace(&mut self.current_path, vec![]);
+        if closed && !points.is_empty() {
+            let first = points[0];
+            points.push(first);
+        }
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], stroke.color);
+        }
+    }
+
+    fn circle(
+        &mut self,
+        center: P2,
+        radius: f32,
+        stroke: Option<Stroke>,
+        fill: Option<SvgColor>,
+    ) {
+        // Tiny circles, like the dots along paths, are just one pixel.
+        if radius * self.scale < 1. {
+            if let Some(color) = fill.or(stroke.map(|s| s.color)) {
+                let pixel = self.to_pixels(center);
+                self.plot(pixel, color);
+            }
+            return;
+        }
+        if let Some(color) = stroke.map(|s| s.color).or(fill) {
+            let segments = 24;
+            let point_at = |i: usize| {
+                let angle = i as f32 / segments as f32 * 2. * PI;
+                center + V2::new(angle.cos(), angle.sin()) * radius
+            };
+            for i in 0..segments {
+                self.line(point_at(i), point_at(i + 1), color);
+            }
+        }
+    }
+
+    fn text(
+        &mut self,
+        _pos: P2,
+        _text: &str,
+        _size: f32,
+        _color: SvgColor,
+        _style: TextStyle,
+    ) {
+        // Text is too small to read in a thumbnail anyways.
+    }
+}
@@ -0,0 +1,241 @@
+//! The transom: a flat or curved board across the stern, leaned aft, that
+//! the planks end on. Its pattern is its true shape, unrolled flat.
+
+use scad_dots::utils::{Axis, P2, P3, V2};
+
+use draw::{Drawing, Layer};
+use error::LapstrakeError;
+use hull::{Hull, Station};
+use render_2d::{
+    Align, Baseline, Bounded, PathStyle2, SvgDoc, SvgGroup, SvgPath, SvgText,
+    TextStyle,
+};
+use spec::{Transom, TRANSOM};
+use util::reflect2;
+
+/// How many steps to measure the girth of a curved transom in.
+const GIRTH_STEPS: usize = 32;
+
+impl Transom {
+    /// How far aft of the transom's position the transom is, at the
+    /// given breadth and height above the sheer (at the centerline).
+    pub fn setback(
+        &self,
+        breadth: f32,
+        rise: f32,
+    ) -> Result<f32, LapstrakeError> {
+        Ok(rise * self.rake.to_radians().tan() - self.crown(breadth)?)
+    }
+
+    /// Unroll a point on the transom onto its pattern, as (distance around
+    /// the transom from the centerline, distance up its face from the
+    /// height of the sheer).
+    pub fn develop(
+        &self,
+        point: P3,
+        sheer_height: f32,
+    ) -> Result<P2, LapstrakeError> {
+        let rake = self.rake.to_radians();
+        let breadth = point.y.abs();
+        let girth = self.girth(breadth)?;
+        let across = if point.y < 0. { -girth } else { girth };
+        let up = (point.z - sheer_height) / rake.cos()
+            - self.crown(breadth)? * rake.sin();
+        Ok(P2::new(across, up))
+    }
+
+    // How far forward of its middle the transom's curve comes at the
+    // given breadth.
+    fn crown(&self, breadth: f32) -> Result<f32, LapstrakeError> {
+        match self.radius {
+            None => Ok(0.),
+            Some(radius) if breadth.abs() <= radius => {
+                Ok(radius - (radius * radius - breadth * breadth).sqrt())
+            }
+            Some(radius) => Err(LapstrakeError::General(format!(
+                "The transom is {} ft wide, but its radius is only {} ft.",
+                2. * breadth.abs(),
+                radius
+            ))),
+        }
+    }
+
+    // The distance across the transom from the centerline to the given
+    // breadth, square to the rake.
+    fn girth(&self, breadth: f32) -> Result<f32, LapstrakeError> {
+        let radius = match self.radius {
+            None => return Ok(breadth),
+            Some(radius) => radius,
+        };
+        // Check that it's not wider than the curve.
+        self.crown(breadth)?;
+        // Where the curve is steep across the hull, leaning it aft
+        // foreshortens it.
+        let lean = self.rake.to_radians().cos();
+        let step = breadth / GIRTH_STEPS as f32;
+        let mut girth = 0.;
+        for i in 0..GIRTH_STEPS {
+            let y = (i as f32 + 0.5) * step;
+            let slope = y / (radius * radius - y * y).sqrt();
+            girth += step * (1. + (slope * lean).powi(2)).sqrt();
+        }
+        Ok(girth)
+    }
+}
+
+impl Hull {
+    /// Draw the pattern for the transom, in its true shape. The
+    /// centerline is marked on it, along with where the edge of each
+    /// plank lands.
+    pub fn draw_transom(&self) -> Result<SvgDoc, LapstrakeError> {
+        const STROKE: f32 = 0.02;
+        const FONT_SIZE: f32 = 0.25;
+        let palette = self.theme.palette();
+        let (station, transom) = self.transom_station()?;
+        let top = station.sheer().z;
+
+        let mut half = vec![];
+        for p in station.spline.sample(None)?.into_iter().rev() {
+            half.push(transom.develop(p, top)?);
+        }
+        let mut outline = half.clone();
+        outline.extend(reflect2(Axis::X, &half).into_iter().rev());
+        let path = SvgPath::new(outline)
+            .stroke(palette.outline, STROKE)
+            .style(PathStyle2::Line)
+            .close();
+        let bound = path.bound().expect("transom has no bound");
+
+        let mut marks = SvgGroup::new();
+        let mut labels = SvgGroup::new();
+        marks.append(
+            SvgPath::new(vec![
+                P2::new(0., bound.low.y),
+                P2::new(0., bound.high.y),
+            ]).stroke(palette.grid, STROKE / 2.)
+                .style(PathStyle2::Dashed),
+        );
+        // Tick the plank edges that end on the transom.
+        for (i, plank) in self.get_planks()?.iter().enumerate() {
+            for line in &[&plank.bottom_line, &plank.top_line] {
+                for &t in &[0., 1.] {
+                    let end = line.at_t(t)?;
+                    let posn = station.position()
+                        + transom.setback(end.y, end.z - top)?
+                        - transom.setback(station.sheer().y, 0.)?;
+                    if (end.x - posn).abs() > self.equality_threshold {
+                        continue;
+                    }
+                    let edge = transom.develop(end, top)?;
+                    for &side in &[1., -1.] {
+                        let edge = P2::new(side * edge.x, edge.y);
+                        let inward = V2::new(-side * FONT_SIZE / 2., 0.);
+                        marks.append(
+                            SvgPath::new(vec![edge, edge + inward])
+                                .stroke(palette.grid, STROKE / 2.)
+                                .style(PathStyle2::Line),
+                        );
+                        labels.append(
+                            SvgText {
+                                lines: vec![format!("{}", i + 1)],
+                                pos: edge + inward * 1.5,
+                                color: palette.label,
+                                size: FONT_SIZE / 2.,
+                                style: TextStyle::default(),
+                            }.anchored(Align::Middle, Baseline::Central),
+                        );
+                    }
+                }
+            }
+        }
+        labels.append(SvgText {
+            lines: vec![
+                TRANSOM.to_string(),
+                format!("Raked {:.1} deg", transom.rake),
+            ],
+            pos: bound.relative_pos(0.5, 0.6),
+            color: palette.label,
+            size: FONT_SIZE,
+            style: TextStyle::default(),
+        });
+
+        let mut pattern = SvgGroup::new();
+        pattern.append(Layer::Stations.wrap(path));
+        pattern.append(marks.layer(Layer::Marks.name()));
+        pattern.append(labels.layer(Layer::Labels.name()));
+        let mut doc = SvgDoc::new();
+        doc.append(SvgGroup::new_vertical(
+            vec![self.scale_bar()?, pattern],
+            1.1,
+        )?);
+        self.run_drawing_passes(Drawing::Transom, doc)
+    }
+
+    // The transom station, and how it's leaned and curved.
+    fn transom_station(&self) -> Result<(&Station, Transom), LapstrakeError> {
+        let transom = self.transom.ok_or_else(|| {
+            LapstrakeError::General(
+                "The data sheet has no station named Transom.".into(),
+            )
+        })?;
+        let station = self
+            .stations
+            .iter()
+            .find(|station| station.name == TRANSOM)
+            .ok_or_else(|| {
+                LapstrakeError::General("The transom has no station.".into())
+            })?;
+        Ok((station, transom))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_flat_transom() {
+        let transom = Transom {
+            rake: 30.,
+            radius: None,
+        };
+        let rake = 30_f32.to_radians();
+        assert_eq!(transom.girth(1.5).unwrap(), 1.5);
+        assert!(near(transom.setback(1.5, 2.).unwrap(), 2. * rake.tan()));
+        // Across the face is the same as across the hull, but up the face
+        // is longer than the height, since it's leaned.
+        let p = transom.develop(P3::new(20., -1.5, 3.), 4.).unwrap();
+        assert!(near(p.x, -1.5));
+        assert!(near(p.y, -1. / rake.cos()));
+    }
+
+    #[test]
+    fn test_curved_transom() {
+        let transom = Transom {
+            rake: 0.,
+            radius: Some(2.),
+        };
+        // Upright, the girth is just the length of the arc.
+        let arc = 2. * 0.5_f32.asin();
+        assert!((transom.girth(1.).unwrap() - arc).abs() < 1e-3);
+        let crown = 2. - 3_f32.sqrt();
+        assert!(near(transom.setback(1., 0.).unwrap(), -crown));
+        assert!(transom.girth(2.5).is_err());
+
+        // Leaning it back foreshortens the curve, so there's less girth,
+        // and the crown comes up the face.
+        let raked = Transom {
+            rake: 20.,
+            radius: Some(2.),
+        };
+        let girth = raked.girth(1.).unwrap();
+        assert!(girth > 1. && girth < transom.girth(1.).unwrap());
+        let p = raked.develop(P3::new(0., 1., 3.), 3.).unwrap();
+        assert!(near(p.x, girth));
+        assert!(near(p.y, -crown * 20_f32.to_radians().sin()));
+    }
+}
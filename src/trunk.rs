@@ -0,0 +1,123 @@
+//! The centerboard or daggerboard trunk. Its slot runs up through the
+//! keel and hog, and any station molds that it passes through have to be
+//! slotted for it too, so that the trunk can be set up with the hull.
+
+use scad_dots::utils::P2;
+
+use hull::Hull;
+use spec::Trunk;
+use util::practically_zero;
+
+impl Trunk {
+    /// Whether the slot passes through any of a part that spans the
+    /// given fore-aft positions.
+    pub fn crosses(&self, fore: f32, aft: f32) -> bool {
+        fore < self.aft && self.fore < aft
+    }
+
+    /// Cut the slot up into a cross-section from below, to the top of the
+    /// trunk. The outline is (breadth, height) points, going from one
+    /// side's sheer down around the bottom to the other's; the slot is
+    /// cut where it first crosses the centerline below the top of the
+    /// trunk, and the outline still starts and ends at the same points.
+    /// It's left alone if it's never wider than the slot there.
+    pub fn notch(&self, outline: &[P2]) -> Vec<P2> {
+        let half = self.width / 2.;
+        let n = outline.len();
+        let outside = |p: &P2| p.x.abs() >= half;
+        // Where the edge after the `i`th point crosses the given side of
+        // the slot.
+        let cross = |i: usize, side: f32| {
+            let (p, q) = (outline[i], outline[i + 1]);
+            if practically_zero(q.x - p.x) {
+                return P2::new(side, p.y);
+            }
+            let t = (side - p.x) / (q.x - p.x);
+            P2::new(side, p.y + t * (q.y - p.y))
+        };
+        // Find the edges where the outline goes into and back out of the
+        // slot, along the bottom. A long edge can go right across it.
+        let enter = (0..n.saturating_sub(1)).find(|&i| {
+            let (p, q) = (outline[i], outline[i + 1]);
+            outside(&p)
+                && q.x * p.x.signum() < half
+                && cross(i, half * p.x.signum()).y < self.top
+        });
+        let enter = match enter {
+            Some(enter) => enter,
+            None => return outline.to_vec(),
+        };
+        let into = cross(enter, half * outline[enter].x.signum());
+        let exit = (enter..n - 1).find(|&i| {
+            let (p, q) = (outline[i], outline[i + 1]);
+            outside(&q) && (i > enter || p.x.signum() != q.x.signum())
+        });
+        let exit = match exit {
+            Some(exit) => exit,
+            None => return outline.to_vec(),
+        };
+        let out_of = cross(exit, half * outline[exit + 1].x.signum());
+        let mut notched = outline[..enter + 1].to_vec();
+        notched.extend(vec![
+            into,
+            P2::new(into.x, self.top),
+            P2::new(out_of.x, self.top),
+            out_of,
+        ]);
+        notched.extend_from_slice(&outline[exit + 1..]);
+        notched
+    }
+}
+
+impl Hull {
+    /// The trunk, if its slot passes through a station mold or template
+    /// whose face is at the given fore-aft position. Like the molds, it's
+    /// a material thickness thick towards midships.
+    pub fn trunk_through(&self, position: f32) -> Option<Trunk> {
+        let trunk = self.trunk?;
+        let towards = if position < self.midships() { 1. } else { -1. };
+        let far = position + towards * self.template_layout.material_thickness;
+        if trunk.crosses(position.min(far), position.max(far)) {
+            Some(trunk)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notch() {
+        let trunk = Trunk {
+            fore: 1.,
+            aft: 2.,
+            width: 0.2,
+            top: 0.5,
+        };
+        // A square U-section, from the starboard sheer down around the
+        // bottom and up to the port sheer.
+        let outline = vec![
+            P2::new(1., 1.),
+            P2::new(1., 0.),
+            P2::new(-1., 0.),
+            P2::new(-1., 1.),
+        ];
+        let notched = trunk.notch(&outline);
+        assert_eq!(
+            notched,
+            vec![
+                P2::new(1., 1.),
+                P2::new(1., 0.),
+                P2::new(0.1, 0.),
+                P2::new(0.1, 0.5),
+                P2::new(-0.1, 0.5),
+                P2::new(-0.1, 0.),
+                P2::new(-1., 0.),
+                P2::new(-1., 1.),
+            ]
+        );
+    }
+}
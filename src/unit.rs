@@ -1,4 +1,5 @@
-//! Units. Right now only feet are supported.
+//! Units. Measurements can be given in feet or in millimeters, but all
+//! of the hull geometry is computed in feet.
 
 use std::fmt;
 use std::str::FromStr;
@@ -6,7 +7,7 @@ use std::str::FromStr;
 use error::{LapstrakeError, ResultExt};
 
 /// Feet, inches, and eighths of an inch.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Feet {
     pub feet: u32,
     pub inches: u32,
@@ -22,6 +23,17 @@ impl Feet {
         }
     }
 
+    /// Round a decimal number of feet to the nearest eighth of an inch.
+    /// Negative lengths become zero.
+    pub fn from_f32(length: f32) -> Feet {
+        let eighths = (length.max(0.) * 12. * 8.).round() as u32;
+        Feet {
+            feet: eighths / (12 * 8),
+            inches: eighths / 8 % 12,
+            eighths: eighths % 8,
+        }
+    }
+
     /// Parse Feet from a string, using the format 2-3-4.
     pub fn parse(text: &str) -> Result<Feet, LapstrakeError> {
         match Feet::parse_opt(text)? {
@@ -81,6 +93,144 @@ impl Feet {
     }
 }
 
+/// The units that the measurements in the spec sheets are given in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    #[serde(rename = "feet")]
+    Feet,
+    #[serde(rename = "mm")]
+    Millimeters,
+}
+
+/// A measurement from the spec sheets, in whichever units it was given.
+/// In json, feet are written like `{"feet": 2, "inches": 3, "eighths": 4}`
+/// and millimeters as a plain number.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Length {
+    Feet(Feet),
+    Millimeters(f32),
+}
+
+pub(crate) const MILLIMETERS_PER_FOOT: f32 = 304.8;
+
+impl Units {
+    /// Parse a length in these units. Feet use the format 2-3-4, and
+    /// millimeters are a plain number like 1250.
+    pub fn parse(self, text: &str) -> Result<Length, LapstrakeError> {
+        match self.parse_opt(text)? {
+            None => Err(LapstrakeError::load(
+                "this required measurement was omitted",
+            )),
+            Some(length) => Ok(length),
+        }
+    }
+
+    /// Parse a length in these units, or "x" for None.
+    pub fn parse_opt(
+        self,
+        text: &str,
+    ) -> Result<Option<Length>, LapstrakeError> {
+        match self {
+            Units::Feet => Ok(Feet::parse_opt(text)?.map(Length::Feet)),
+            Units::Millimeters => {
+                if text == "x" {
+                    return Ok(None);
+                }
+                let mm = f32::from_str(text).map_err(|_| {
+                    LapstrakeError::Load(format!(
+                        "Was not able to read measurement '{}' in millimeters.",
+                        text
+                    ))
+                })?;
+                if !mm.is_finite() {
+                    return Err(LapstrakeError::Load(format!(
+                        "The measurement '{}' isn't a finite number of \
+                         millimeters.",
+                        text
+                    )));
+                }
+                Ok(Some(Length::Millimeters(mm)))
+            }
+        }
+    }
+
+    /// Express a length, given in feet, in these units.
+    pub fn from_feet(self, length: f32) -> Length {
+        match self {
+            Units::Feet => Length::Feet(Feet::from_f32(length)),
+            Units::Millimeters => {
+                Length::Millimeters(length * MILLIMETERS_PER_FOOT)
+            }
+        }
+    }
+
+    /// Write out an area, given in square feet, in these units. Square
+    /// millimeters are too small to read easily, so metric areas are
+    /// given in square meters.
+    pub fn describe_area(self, square_feet: f32) -> String {
+        match self {
+            Units::Feet => format!("{:.3} sq ft", square_feet),
+            Units::Millimeters => {
+                let meters_per_foot = MILLIMETERS_PER_FOOT / 1000.;
+                format!("{:.4} sq m", square_feet * meters_per_foot.powi(2))
+            }
+        }
+    }
+
+    /// Write out a volume, given in cubic feet, in these units. Metric
+    /// volumes are given in cubic meters.
+    pub fn describe_volume(self, cubic_feet: f32) -> String {
+        match self {
+            Units::Feet => format!("{:.3} cu ft", cubic_feet),
+            Units::Millimeters => {
+                let meters_per_foot = MILLIMETERS_PER_FOOT / 1000.;
+                format!("{:.4} cu m", cubic_feet * meters_per_foot.powi(3))
+            }
+        }
+    }
+}
+
+impl Default for Units {
+    fn default() -> Units {
+        Units::Feet
+    }
+}
+
+impl From<Feet> for Length {
+    fn from(feet: Feet) -> Length {
+        Length::Feet(feet)
+    }
+}
+
+/// Convert to feet, which all of the hull geometry is computed in.
+impl Into<f32> for Length {
+    fn into(self) -> f32 {
+        match self {
+            Length::Feet(feet) => feet.into(),
+            Length::Millimeters(mm) => mm / MILLIMETERS_PER_FOOT,
+        }
+    }
+}
+
+impl fmt::Debug for Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Length::Feet(feet) => write!(f, "{:?}", feet),
+            Length::Millimeters(mm) => write!(f, "{}", mm),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Length::Feet(feet) => write!(f, "{}", feet),
+            Length::Millimeters(mm) => write!(f, "{}mm", mm),
+        }
+    }
+}
+
 impl Into<f32> for Feet {
     fn into(self) -> f32 {
         (self.feet as f32)
@@ -184,4 +334,25 @@ mod tests {
             "0 6/8\""
         );
     }
+
+    #[test]
+    fn test_millimeters() {
+        let length = Units::Millimeters.parse("1524").unwrap();
+        assert_eq!(length, Length::Millimeters(1524.));
+        let feet: f32 = length.into();
+        assert!((feet - 5.).abs() < 1e-5);
+        assert_eq!(Units::Millimeters.parse_opt("x").unwrap(), None);
+        assert!(Units::Millimeters.parse("2-3-4").is_err());
+        assert!(Units::Millimeters.parse("NaN").is_err());
+        assert!(Units::Millimeters.parse("inf").is_err());
+        assert!(Units::Millimeters.parse("-inf").is_err());
+        assert_eq!(
+            Units::Feet.parse("2-3-4").unwrap(),
+            Length::Feet(Feet {
+                feet: 2,
+                inches: 3,
+                eighths: 4,
+            })
+        );
+    }
 }
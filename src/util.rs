@@ -1,12 +1,51 @@
-use scad_dots::utils::{distance, Axis, P2, P3};
+use nalgebra::{Point2, Vector2};
+use scad_dots::utils::{distance, Axis, P2, P3, V2};
 
-// How near points must be to be considered equal, in feet.
+use error::LapstrakeError;
+
+// How near points must be to be considered equal, in feet, unless the
+// config sheet says otherwise.
 pub const EQUALITY_THRESHOLD: f32 = 0.05;
 
+/// The scalar type of computations whose rounding errors add up, like
+/// flattening a plank one triangle at a time. It's `f64` with the `f64`
+/// feature, and `f32` otherwise. Everything else is in `f32`, and is
+/// converted to and from this at the boundary.
+#[cfg(feature = "f64")]
+pub type Real = f64;
+#[cfg(not(feature = "f64"))]
+pub type Real = f32;
+
+pub type RealP2 = Point2<Real>;
+pub type RealV2 = Vector2<Real>;
+
+/// The distance between two points, computed in `Real` precision.
+pub fn real_distance(a: &P3, b: &P3) -> Real {
+    let dx = Real::from(a.x) - Real::from(b.x);
+    let dy = Real::from(a.y) - Real::from(b.y);
+    let dz = Real::from(a.z) - Real::from(b.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Round a `Real` point to `f32`.
+#[cfg(feature = "f64")]
+pub fn from_real(point: RealP2) -> P2 {
+    P2::new(point.x as f32, point.y as f32)
+}
+#[cfg(not(feature = "f64"))]
+pub fn from_real(point: RealP2) -> P2 {
+    point
+}
+
 pub fn practically_zero(x: f32) -> bool {
     f32::abs(x) < 0.00000001
 }
 
+/// Like `practically_zero()`, but in `Real` precision.
+pub fn practically_zero_real(x: Real) -> bool {
+    x.abs() < 0.00000001
+}
+
 pub fn project_points(axis: Axis, points: &[P3]) -> Vec<P2> {
     points.iter().map(|&p| project(axis, p)).collect()
 }
@@ -19,12 +58,262 @@ pub fn project(axis: Axis, point: P3) -> P2 {
     }
 }
 
-pub fn remove_duplicates(points: Vec<P3>) -> Vec<P3> {
+/// Find where the line segment from `a` to `b` crosses the line segment
+/// from `c` to `d`, if they do.
+pub fn segment_intersection(a: P2, b: P2, c: P2, d: P2) -> Option<P2> {
+    let r = b - a;
+    let s = d - c;
+    let denom = r.x * s.y - r.y * s.x;
+    if practically_zero(denom) {
+        // Parallel (or degenerate) segments.
+        return None;
+    }
+    let ac = c - a;
+    let t = (ac.x * s.y - ac.y * s.x) / denom;
+    let u = (ac.x * r.y - ac.y * r.x) / denom;
+    if t >= 0. && t <= 1. && u >= 0. && u <= 1. {
+        Some(a + r * t)
+    } else {
+        None
+    }
+}
+
+/// Offset every point of a polyline sideways by `distance`, to the left
+/// of the direction of travel (in a y-up coordinate system). A negative
+/// distance offsets to the right.
+pub fn offset_polyline(points: &[P2], distance: f32) -> Vec<P2> {
+    let n = points.len();
+    let mut offset = vec![];
+    for i in 0..n {
+        let before = points[i.saturating_sub(1)];
+        let after = points[(i + 1).min(n - 1)];
+        let tangent = after - before;
+        let length = tangent.norm();
+        if practically_zero(length) {
+            offset.push(points[i]);
+        } else {
+            let normal = V2::new(-tangent.y, tangent.x) / length;
+            offset.push(points[i] + normal * distance);
+        }
+    }
+    offset
+}
+
+/// The total length of a polyline.
+pub fn polyline_length(points: &[P2]) -> f32 {
+    points.windows(2).map(|pair| (pair[1] - pair[0]).norm()).sum()
+}
+
+/// The point that's `distance` along a polyline from its start, stopping
+/// at its ends. The polyline can't be empty.
+pub fn point_along(points: &[P2], distance: f32) -> P2 {
+    let mut left = distance.max(0.);
+    for pair in points.windows(2) {
+        let step = pair[1] - pair[0];
+        let length = step.norm();
+        if left <= length {
+            if practically_zero(length) {
+                return pair[0];
+            }
+            return pair[0] + step * (left / length);
+        }
+        left -= length;
+    }
+    points[points.len() - 1]
+}
+
+/// The part of a polyline between two distances along it from its start.
+pub fn trim_polyline(points: &[P2], from: f32, to: f32) -> Vec<P2> {
+    let mut trimmed = vec![point_along(points, from)];
+    let mut along = 0.;
+    for pair in points.windows(2) {
+        along += (pair[1] - pair[0]).norm();
+        if from < along && along < to {
+            trimmed.push(pair[1]);
+        }
+    }
+    trimmed.push(point_along(points, to));
+    trimmed
+}
+
+/// Offset a closed polygon outwards by `distance`, whichever way around
+/// its points go. A negative distance shrinks it instead. Corners are
+/// mitered, but sharp corners are limited so they don't spike outwards.
+/// The polygon may repeat its first point at the end, but the offset one
+/// won't.
+pub fn offset_polygon(polygon: &[P2], distance: f32) -> Vec<P2> {
+    const MITER_LIMIT: f32 = 4.;
+    // Skip repeated points, which have no edge direction, including the
+    // last point wrapping around to the first.
+    let mut points: Vec<P2> = vec![];
+    for &p in polygon {
+        let repeated = match points.last() {
+            Some(&last) => practically_zero((p - last).norm()),
+            None => false,
+        };
+        if !repeated {
+            points.push(p);
+        }
+    }
+    if points.len() > 1
+        && practically_zero((points[0] - points[points.len() - 1]).norm())
+    {
+        points.pop();
+    }
+    let n = points.len();
+    if n < 3 {
+        return points;
+    }
+    // The outward normal is to the right of the direction of travel for
+    // a counter-clockwise polygon, and to the left for a clockwise one.
+    let mut twice_area = 0.;
+    for i in 0..n {
+        let (p, q) = (points[i], points[(i + 1) % n]);
+        twice_area += p.x * q.y - q.x * p.y;
+    }
+    let side = if twice_area > 0. { 1. } else { -1. };
+    let outward = |from: P2, to: P2| {
+        let direction = (to - from).normalize();
+        V2::new(direction.y, -direction.x) * side
+    };
+
+    let mut offset = vec![];
+    for i in 0..n {
+        let before = points[(i + n - 1) % n];
+        let after = points[(i + 1) % n];
+        let normal_in = outward(before, points[i]);
+        let normal_out = outward(points[i], after);
+        let bisector = normal_in + normal_out;
+        if practically_zero(bisector.norm()) {
+            // The polygon doubles back on itself here.
+            offset.push(points[i] + normal_in * distance);
+            continue;
+        }
+        let bisector = bisector.normalize();
+        let miter = 1. / bisector.dot(&normal_in).max(1. / MITER_LIMIT);
+        offset.push(points[i] + bisector * distance * miter);
+    }
+    offset
+}
+
+/// Clip a polygon, keeping only the part of it whose y coordinates are
+/// at or below `height`.
+pub fn clip_below(polygon: &[P2], height: f32) -> Vec<P2> {
+    let n = polygon.len();
+    let mut clipped = vec![];
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        let p_inside = p.y <= height;
+        let q_inside = q.y <= height;
+        if p_inside {
+            clipped.push(p);
+        }
+        if p_inside != q_inside {
+            // The edge crosses the clipping line.
+            let t = (height - p.y) / (q.y - p.y);
+            clipped.push(P2::new(p.x + t * (q.x - p.x), height));
+        }
+    }
+    clipped
+}
+
+/// The area enclosed by a polygon, using the shoelace formula. The
+/// polygon is implicitly closed.
+pub fn polygon_area(points: &[P2]) -> f32 {
+    let n = points.len();
+    let mut twice_area = 0.;
+    for i in 0..n {
+        let (p, q) = (points[i], points[(i + 1) % n]);
+        twice_area += p.x * q.y - q.x * p.y;
+    }
+    f32::abs(twice_area) / 2.
+}
+
+/// Whether a point lies inside a polygon, by counting how many of its
+/// edges a ray going in the +x direction crosses.
+pub fn polygon_contains(polygon: &[P2], point: P2) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        if (p.y > point.y) != (q.y > point.y) {
+            let x = p.x + (point.y - p.y) / (q.y - p.y) * (q.x - p.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Linearly interpolate the y value of a polyline at the given x. The
+/// points must be sorted by x. Returns None if x is out of range.
+pub fn interpolate_y(points: &[P2], x: f32) -> Option<f32> {
+    for pair in points.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        if p.x <= x && x <= q.x {
+            if practically_zero(q.x - p.x) {
+                return Some(p.y);
+            }
+            let t = (x - p.x) / (q.x - p.x);
+            return Some(p.y + t * (q.y - p.y));
+        }
+    }
+    None
+}
+
+/// Whether every coordinate of the point is a number.
+pub fn is_finite(point: &P3) -> bool {
+    point.x.is_finite() && point.y.is_finite() && point.z.is_finite()
+}
+
+/// Sort the points by one of their coordinates. It's an error if any of
+/// them isn't a number, since then they have no order.
+pub fn sort_points<F>(
+    points: &mut [P3],
+    coordinate: F,
+) -> Result<(), LapstrakeError>
+where
+    F: Fn(&P3) -> f32,
+{
+    if let Some(point) = points.iter().find(|p| !is_finite(p)) {
+        return Err(LapstrakeError::General(format!(
+            "Found a point that isn't a number: {:?}",
+            point
+        )));
+    }
+    sort_by_number(points, coordinate)
+}
+
+/// Sort anything by a number found from each item, like a station by its
+/// position. It's an error if any of the numbers is NaN.
+pub fn sort_by_number<T, F>(
+    items: &mut [T],
+    number: F,
+) -> Result<(), LapstrakeError>
+where
+    F: Fn(&T) -> f32,
+{
+    if items.iter().any(|item| number(item).is_nan()) {
+        return Err(LapstrakeError::General(
+            "Found a position that isn't a number.".into(),
+        ));
+    }
+    items.sort_by(|a, b| {
+        number(a).partial_cmp(&number(b)).expect("Checked for NaN")
+    });
+    Ok(())
+}
+
+pub fn remove_duplicates(points: Vec<P3>, threshold: f32) -> Vec<P3> {
+    if points.is_empty() {
+        return points;
+    }
     let mut good_points = vec![];
     good_points.push(points[0]);
     let mut prev_point = points[0];
     for &point in &points[1..] {
-        if distance(&point, &prev_point) >= EQUALITY_THRESHOLD {
+        if distance(&point, &prev_point) >= threshold {
             good_points.push(point);
         }
         prev_point = point;
@@ -52,6 +341,19 @@ pub fn reflect3(axis: Axis, points: &[P3]) -> Vec<P3> {
         }).collect()
 }
 
+/// The points all the way around a section of the hull, given the points
+/// up its starboard side from the keel: up that side, then back down the
+/// port side. The keel is put on the centerline, and only appears once,
+/// so that surfaces lofted through sections have no faces of zero area.
+pub fn both_sides(mut starboard: Vec<P3>) -> Vec<P3> {
+    if let Some(keel) = starboard.first_mut() {
+        keel.y = 0.;
+    }
+    let port = reflect3(Axis::Y, &starboard);
+    starboard.extend(port.into_iter().skip(1).rev());
+    starboard
+}
+
 // pub fn print_error(error: Error) {
 //     let mut causes = error.causes();
 //     if let Some(first) = causes.next() {
@@ -61,3 +363,92 @@ pub fn reflect3(axis: Axis, points: &[P3]) -> Vec<P3> {
 //         println!("Cause: {}", cause);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_closed_polygon() {
+        let square = vec![
+            P2::new(0., 0.),
+            P2::new(1., 0.),
+            P2::new(1., 1.),
+            P2::new(0., 1.),
+            P2::new(0., 0.),
+        ];
+        let grown = offset_polygon(&square, 0.5);
+        let expected = vec![
+            P2::new(-0.5, -0.5),
+            P2::new(1.5, -0.5),
+            P2::new(1.5, 1.5),
+            P2::new(-0.5, 1.5),
+        ];
+        assert_eq!(grown.len(), expected.len());
+        for (p, q) in grown.iter().zip(&expected) {
+            assert!(practically_zero((p - q).norm()), "{} != {}", p, q);
+        }
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let cross = segment_intersection(
+            P2::new(0., 0.),
+            P2::new(2., 2.),
+            P2::new(0., 2.),
+            P2::new(2., 0.),
+        );
+        assert_eq!(cross, Some(P2::new(1., 1.)));
+        let miss = segment_intersection(
+            P2::new(0., 0.),
+            P2::new(1., 1.),
+            P2::new(3., 0.),
+            P2::new(2., 1.),
+        );
+        assert_eq!(miss, None);
+    }
+
+    #[test]
+    fn test_trim_polyline() {
+        let corner = vec![P2::new(0., 0.), P2::new(2., 0.), P2::new(2., 2.)];
+        assert_eq!(polyline_length(&corner), 4.);
+        assert_eq!(point_along(&corner, 3.), P2::new(2., 1.));
+        assert_eq!(point_along(&corner, 5.), P2::new(2., 2.));
+        assert_eq!(
+            trim_polyline(&corner, 1., 3.),
+            vec![P2::new(1., 0.), P2::new(2., 0.), P2::new(2., 1.)]
+        );
+    }
+
+    #[test]
+    fn test_clip_below() {
+        let square = vec![
+            P2::new(0., 0.),
+            P2::new(2., 0.),
+            P2::new(2., 2.),
+            P2::new(0., 2.),
+        ];
+        assert_eq!(polygon_area(&clip_below(&square, 0.5)), 1.);
+        assert_eq!(polygon_area(&clip_below(&square, 3.)), 4.);
+        assert_eq!(polygon_area(&clip_below(&square, -1.)), 0.);
+    }
+
+    #[test]
+    fn test_both_sides() {
+        let starboard = vec![
+            P3::new(0., 0.1, 0.),
+            P3::new(0., 1., 1.),
+            P3::new(0., 2., 2.),
+        ];
+        assert_eq!(
+            both_sides(starboard),
+            vec![
+                P3::new(0., 0., 0.),
+                P3::new(0., 1., 1.),
+                P3::new(0., 2., 2.),
+                P3::new(0., -2., 2.),
+                P3::new(0., -1., 1.),
+            ]
+        );
+    }
+}